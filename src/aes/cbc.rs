@@ -0,0 +1,308 @@
+//! Cipher Block Chaining (CBC)
+//!
+//! <https://nvlpubs.nist.gov/nistpubs/Legacy/SP/nistspecialpublication800-38a.pdf>
+//! <https://www.cryptopals.com/sets/2/challenges/10>
+//!
+//! [`encrypt()`] and [`decrypt()`] operate on block-aligned input. Messages of other
+//! lengths can either be padded, or be encrypted with ciphertext stealing (see
+//! [`encrypt_cts()`]), which keeps the ciphertext as long as the plaintext.
+//!
+//! The ciphertext stealing variants are specified in the addendum to SP 800-38A:
+//! <https://nvlpubs.nist.gov/nistpubs/Legacy/SP/nistspecialpublication800-38a-add.pdf>
+
+use super::{cipher, inv_cipher, key::RoundKeys128, Key128};
+
+/// The ciphertext stealing variants of the SP 800-38A addendum. They only differ in
+/// the order of the last two ciphertext blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    /// The truncated second to last block precedes the last block.
+    Cs1,
+    /// Like [`Variant::Cs3`] if the input is not block-aligned, like plain CBC
+    /// otherwise.
+    Cs2,
+    /// The last two blocks are always swapped. This is used by Kerberos.
+    Cs3,
+}
+
+pub(super) fn xor_block(a: [u8; 16], b: [u8; 16]) -> [u8; 16] {
+    let mut output = a;
+    for (o, b) in output.iter_mut().zip(b.iter()) {
+        *o ^= b;
+    }
+    output
+}
+
+fn encrypt_blocks(round_keys: &RoundKeys128, iv: [u8; 16], plaintext: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(plaintext.len());
+    let mut previous = iv;
+
+    for chunk in plaintext.chunks(16) {
+        let chunk = chunk
+            .try_into()
+            .expect("input length needs to be a multiple of 16");
+        previous = cipher(xor_block(chunk, previous), round_keys);
+        output.extend_from_slice(&previous);
+    }
+
+    output
+}
+
+fn decrypt_blocks(round_keys: &RoundKeys128, iv: [u8; 16], ciphertext: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(ciphertext.len());
+    let mut previous = iv;
+
+    for chunk in ciphertext.chunks(16) {
+        let chunk = chunk
+            .try_into()
+            .expect("input length needs to be a multiple of 16");
+        output.extend_from_slice(&xor_block(inv_cipher(chunk, round_keys), previous));
+        previous = chunk;
+    }
+
+    output
+}
+
+/// Encrypts block-aligned `plaintext`.
+pub fn encrypt(plaintext: &[u8], key: Key128, iv: [u8; 16]) -> Vec<u8> {
+    encrypt_blocks(&key.expand(), iv, plaintext)
+}
+
+/// Decrypts block-aligned `ciphertext`.
+pub fn decrypt(ciphertext: &[u8], key: Key128, iv: [u8; 16]) -> Vec<u8> {
+    decrypt_blocks(&key.expand(), iv, ciphertext)
+}
+
+/// Returns the length of the last (possibly partial) block, which is always in `1..=16`
+fn last_block_len(input: &[u8]) -> usize {
+    match input.len() % 16 {
+        0 => 16,
+        len => len,
+    }
+}
+
+/// Whether the last two blocks are swapped compared to [`Variant::Cs1`]
+fn swapped(variant: Variant, last_block_len: usize) -> bool {
+    match variant {
+        Variant::Cs1 => false,
+        Variant::Cs2 => last_block_len != 16,
+        Variant::Cs3 => true,
+    }
+}
+
+/// Encrypts `plaintext` of at least 16 bytes without padding.
+///
+/// The incomplete last block is padded with zeroes, then the input is encrypted with
+/// regular CBC. The zeroes do not need to be transmitted, because they can be
+/// recovered during decryption. So the second to last ciphertext block is truncated.
+pub fn encrypt_cts(plaintext: &[u8], key: Key128, iv: [u8; 16], variant: Variant) -> Vec<u8> {
+    assert!(
+        plaintext.len() >= 16,
+        "ciphertext stealing requires at least one block of input"
+    );
+
+    let last_block_len = last_block_len(plaintext);
+
+    let mut padded = plaintext.to_vec();
+    padded.resize(
+        plaintext
+            .len()
+            .checked_add(16_usize.saturating_sub(last_block_len))
+            .expect("input too large"),
+        0,
+    );
+
+    let mut output = encrypt_blocks(&key.expand(), iv, &padded);
+
+    if output.len() == 16 {
+        return output;
+    }
+
+    let last = output.split_off(output.len().saturating_sub(16));
+    let second_to_last = output.split_off(output.len().saturating_sub(16));
+
+    if swapped(variant, last_block_len) {
+        output.extend_from_slice(&last);
+        output.extend_from_slice(&second_to_last[..last_block_len]);
+    } else {
+        output.extend_from_slice(&second_to_last[..last_block_len]);
+        output.extend_from_slice(&last);
+    }
+
+    output
+}
+
+/// Decrypts the output of [`encrypt_cts()`]. The variant has to match the one used
+/// for encryption.
+pub fn decrypt_cts(ciphertext: &[u8], key: Key128, iv: [u8; 16], variant: Variant) -> Vec<u8> {
+    assert!(
+        ciphertext.len() >= 16,
+        "ciphertext stealing requires at least one block of input"
+    );
+
+    let round_keys = key.expand();
+
+    if ciphertext.len() <= 16 {
+        return decrypt_blocks(&round_keys, iv, ciphertext);
+    }
+
+    let last_block_len = last_block_len(ciphertext);
+
+    let (head, tail) = ciphertext.split_at(
+        ciphertext
+            .len()
+            .saturating_sub(16)
+            .saturating_sub(last_block_len),
+    );
+
+    let (truncated, last) = if swapped(variant, last_block_len) {
+        let (last, truncated) = tail.split_at(16);
+        (truncated, last)
+    } else {
+        tail.split_at(last_block_len)
+    };
+    let last: [u8; 16] = last.try_into().expect("split at 16 bytes");
+
+    // Decrypting the last block yields the second to last ciphertext block XORed with
+    // the zero-padded plaintext. So the missing bytes of the truncated block are
+    // visible in the padding area.
+    let decrypted_last = inv_cipher(last, &round_keys);
+
+    let mut second_to_last = decrypted_last;
+    second_to_last[..last_block_len].copy_from_slice(truncated);
+
+    let last_plaintext = xor_block(decrypted_last, second_to_last);
+
+    let mut chained = head.to_vec();
+    chained.extend_from_slice(&second_to_last);
+
+    let mut output = decrypt_blocks(&round_keys, iv, &chained);
+    output.extend_from_slice(&last_plaintext[..last_block_len]);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{aes::parse_key, hex::parse};
+
+    fn key() -> Key128 {
+        Key128::from_bytes(*b"chicken teriyaki")
+    }
+
+    const PLAINTEXT: &[u8] = b"I would like the General Gau's Chicken, please, and wonton soup.";
+
+    /// The AES-128 vectors from appendix B of RFC 3962, which uses CS3 with a zero IV
+    const RFC_3962_VECTORS: [(usize, &str); 6] = [
+        (17, "c6353568f2bf8cb4d8a580362da7ff7f97"),
+        (
+            31,
+            "fc00783e0efdb2c1d445d4c8eff7ed2297687268d6ecccc0c07b25e25ecfe5",
+        ),
+        (
+            32,
+            "39312523a78662d5be7fcbcc98ebf5a897687268d6ecccc0c07b25e25ecfe584",
+        ),
+        (
+            47,
+            "97687268d6ecccc0c07b25e25ecfe584b3fffd940c16a18c1b5549d2f838029e39312523a78662d5be7fcbcc98ebf5",
+        ),
+        (
+            48,
+            "97687268d6ecccc0c07b25e25ecfe5849dad8bbb96c4cdc03bc103e1a194bbd839312523a78662d5be7fcbcc98ebf5a8",
+        ),
+        (
+            64,
+            "97687268d6ecccc0c07b25e25ecfe58439312523a78662d5be7fcbcc98ebf5a84807efe836ee89a526730dbc2f7bc8409dad8bbb96c4cdc03bc103e1a194bbd8",
+        ),
+    ];
+
+    /// Converts CS3 output into CS1 output by swapping back the last two blocks
+    fn cs3_to_cs1(input: &[u8]) -> Vec<u8> {
+        let last_block_len = last_block_len(input);
+        let (head, tail) = input.split_at(
+            input
+                .len()
+                .saturating_sub(16)
+                .saturating_sub(last_block_len),
+        );
+        let (last, truncated) = tail.split_at(16);
+
+        [head, truncated, last].concat()
+    }
+
+    #[test]
+    /// taken from appendix F.2.1 of SP 800-38A
+    fn test_cbc_from_spec() {
+        let key = || parse_key("2b7e151628aed2a6abf7158809cf4f3c");
+        let iv = parse("000102030405060708090a0b0c0d0e0f")
+            .try_into()
+            .expect("test iv has 16 bytes");
+        let plaintext = parse("6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e5130c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710");
+        let ciphertext = parse("7649abac8119b246cee98e9b12e9197d5086cb9b507219ee95db113a917678b273bed6b8e3c1743b7116e69e222295163ff1caa1681fac09120eca307586e1a7");
+
+        assert_eq!(encrypt(&plaintext, key(), iv), ciphertext);
+        assert_eq!(decrypt(&ciphertext, key(), iv), plaintext);
+    }
+
+    #[test]
+    fn test_cs3_from_rfc() {
+        for (len, expected) in RFC_3962_VECTORS {
+            let expected = parse(expected);
+
+            assert_eq!(
+                encrypt_cts(&PLAINTEXT[..len], key(), [0; 16], Variant::Cs3),
+                expected
+            );
+            assert_eq!(
+                decrypt_cts(&expected, key(), [0; 16], Variant::Cs3),
+                PLAINTEXT[..len]
+            );
+        }
+    }
+
+    #[test]
+    fn test_cs1_and_cs2() {
+        for (len, cs3) in RFC_3962_VECTORS {
+            let cs3 = parse(cs3);
+            let cs1 = cs3_to_cs1(&cs3);
+            let cs2 = if len % 16 == 0 { &cs1 } else { &cs3 };
+
+            assert_eq!(
+                encrypt_cts(&PLAINTEXT[..len], key(), [0; 16], Variant::Cs1),
+                cs1
+            );
+            assert_eq!(
+                decrypt_cts(&cs1, key(), [0; 16], Variant::Cs1),
+                PLAINTEXT[..len]
+            );
+
+            assert_eq!(
+                encrypt_cts(&PLAINTEXT[..len], key(), [0; 16], Variant::Cs2),
+                *cs2
+            );
+            assert_eq!(
+                decrypt_cts(cs2, key(), [0; 16], Variant::Cs2),
+                PLAINTEXT[..len]
+            );
+        }
+    }
+
+    #[test]
+    fn test_cs1_aligned_is_cbc() {
+        assert_eq!(
+            encrypt_cts(PLAINTEXT, key(), [0x42; 16], Variant::Cs1),
+            encrypt(PLAINTEXT, key(), [0x42; 16])
+        );
+    }
+
+    #[test]
+    fn test_single_block() {
+        let ciphertext = encrypt_cts(&PLAINTEXT[..16], key(), [0; 16], Variant::Cs3);
+        assert_eq!(ciphertext, encrypt(&PLAINTEXT[..16], key(), [0; 16]));
+        assert_eq!(
+            decrypt_cts(&ciphertext, key(), [0; 16], Variant::Cs3),
+            PLAINTEXT[..16]
+        );
+    }
+}
@@ -0,0 +1,235 @@
+//! Raw CBC-MAC
+//!
+//! The tag is the last block of the CBC encryption of the message. This is only
+//! secure for messages of a single fixed length: Given the tags of two messages, an
+//! attacker can construct a third message with a valid tag (see [`forge_extension()`]).
+//! [`super::cmac`] fixes this by treating the last block differently.
+//!
+//! <https://en.wikipedia.org/wiki/CBC-MAC>
+//! <https://www.cryptopals.com/sets/7/challenges/49>
+//!
+//! Messages need to be block-aligned, no padding is applied.
+
+use super::{cipher, constant_time_eq, key::RoundKeys128, AuthenticationError, Key128};
+
+/// Incremental CBC-MAC computation.
+///
+/// Data can be passed to [`CbcMac::update()`] in pieces of any size, complete blocks
+/// are processed immediately.
+#[derive(Clone)]
+pub struct CbcMac {
+    round_keys: RoundKeys128,
+    state: [u8; 16],
+    buffer: [u8; 16],
+    buffered: usize,
+}
+
+impl CbcMac {
+    /// Starts a new computation. Standard CBC-MAC uses an all-zero `iv`.
+    pub fn new(key: Key128, iv: [u8; 16]) -> Self {
+        Self {
+            round_keys: key.expand(),
+            state: iv,
+            buffer: [0; 16],
+            buffered: 0,
+        }
+    }
+
+    /// Encrypts a single block with the key of this computation
+    pub(super) fn encrypt_block(&self, block: [u8; 16]) -> [u8; 16] {
+        cipher(block, &self.round_keys)
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let (head, tail) =
+                data.split_at(16_usize.saturating_sub(self.buffered).min(data.len()));
+
+            let end = self
+                .buffered
+                .checked_add(head.len())
+                .expect("buffer holds at most 16 bytes");
+            self.buffer[self.buffered..end].copy_from_slice(head);
+            self.buffered = end;
+
+            if self.buffered == 16 {
+                for (s, b) in self.state.iter_mut().zip(self.buffer.iter()) {
+                    *s ^= b;
+                }
+                self.state = cipher(self.state, &self.round_keys);
+                self.buffered = 0;
+            }
+
+            data = tail;
+        }
+    }
+
+    /// Returns the tag.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of the data passed to [`CbcMac::update()`] is not a
+    /// multiple of 16.
+    pub fn finalize(self) -> [u8; 16] {
+        assert!(
+            self.buffered == 0,
+            "input length needs to be a multiple of 16"
+        );
+        self.state
+    }
+
+    /// Compares the tag against `tag` in constant time. Input that is not a multiple
+    /// of 16 bytes long fails like a wrong tag.
+    pub fn verify(self, tag: &[u8]) -> Result<(), AuthenticationError> {
+        if self.buffered != 0 {
+            return Err(AuthenticationError);
+        }
+
+        if constant_time_eq(&self.finalize(), tag) {
+            Ok(())
+        } else {
+            Err(AuthenticationError)
+        }
+    }
+}
+
+/// Computes the CBC-MAC of block-aligned `message` in one go.
+pub fn cbc_mac(key: Key128, iv: [u8; 16], message: &[u8]) -> [u8; 16] {
+    let mut mac = CbcMac::new(key, iv);
+    mac.update(message);
+    mac.finalize()
+}
+
+/// Forges a message that carries the same tag as `extension`, without knowing the key.
+///
+/// `tag` is the tag of `message`, both computed with `iv`. The first block of
+/// `extension` is combined with `tag` (and `iv`), which cancels out the chaining value
+/// that `message` leaves behind. The MAC of `message` followed by the modified
+/// `extension` is therefore the MAC of `extension` alone.
+pub fn forge_extension(message: &[u8], tag: [u8; 16], iv: [u8; 16], extension: &[u8]) -> Vec<u8> {
+    assert!(
+        message.len().is_multiple_of(16) && extension.len().is_multiple_of(16),
+        "input length needs to be a multiple of 16"
+    );
+    assert!(!extension.is_empty(), "extension cannot be empty");
+
+    let mut output = message.to_vec();
+    output.extend_from_slice(extension);
+
+    for ((o, t), iv) in output[message.len()..]
+        .iter_mut()
+        .zip(tag.iter())
+        .zip(iv.iter())
+    {
+        *o ^= t ^ iv;
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        aes::{cmac, parse_key},
+        hex::parse,
+    };
+
+    fn key() -> Key128 {
+        parse_key("2b7e151628aed2a6abf7158809cf4f3c")
+    }
+
+    const MESSAGE: &str = "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e5130c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710";
+
+    #[test]
+    /// The tag is the last ciphertext block of the CBC example in appendix F.2.1 of
+    /// SP 800-38A
+    fn test_cbc_mac_from_spec() {
+        let iv = parse("000102030405060708090a0b0c0d0e0f")
+            .try_into()
+            .expect("test iv has 16 bytes");
+
+        assert_eq!(
+            cbc_mac(key(), iv, &parse(MESSAGE)).to_vec(),
+            parse("3ff1caa1681fac09120eca307586e1a7")
+        );
+    }
+
+    #[test]
+    fn test_streaming() {
+        let message = parse(MESSAGE);
+        let expected = cbc_mac(key(), [0; 16], &message);
+
+        for chunk_size in 1..=message.len() {
+            let mut mac = CbcMac::new(key(), [0; 16]);
+            for chunk in message.chunks(chunk_size) {
+                mac.update(chunk);
+            }
+            assert_eq!(mac.finalize(), expected);
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let message = parse(MESSAGE);
+        let tag = cbc_mac(key(), [0; 16], &message);
+
+        let mut mac = CbcMac::new(key(), [0; 16]);
+        mac.update(&message);
+        assert_eq!(mac.verify(&tag), Ok(()));
+
+        let mut modified = tag;
+        modified[15] ^= 0x01;
+        let mut mac = CbcMac::new(key(), [0; 16]);
+        mac.update(&message);
+        assert_eq!(mac.verify(&modified), Err(AuthenticationError));
+
+        let mut mac = CbcMac::new(key(), [0; 16]);
+        mac.update(&message);
+        assert_eq!(mac.verify(&tag[..8]), Err(AuthenticationError));
+
+        // trailing input that is not a full block cannot have a valid tag
+        let mut mac = CbcMac::new(key(), [0; 16]);
+        mac.update(&message);
+        mac.update(b"not a block");
+        assert_eq!(mac.verify(&tag), Err(AuthenticationError));
+    }
+
+    #[test]
+    #[should_panic(expected = "multiple of 16")]
+    fn test_unaligned_input() {
+        cbc_mac(key(), [0; 16], b"not a block");
+    }
+
+    #[test]
+    fn test_length_extension_forgery() {
+        for iv in [[0; 16], [0x42; 16]] {
+            let message = b"from=alice&to=bob&amount=0000010";
+            let extension = b"to=mallory&amount=10000000000000";
+
+            // the attacker observes both tags, e.g. by requesting them from a server
+            let tag = cbc_mac(key(), iv, message);
+            let extension_tag = cbc_mac(key(), iv, extension);
+
+            let forged = forge_extension(message, tag, iv, extension);
+            assert_eq!(forged.len(), message.len() + extension.len());
+            assert_eq!(cbc_mac(key(), iv, &forged), extension_tag);
+        }
+    }
+    #[test]
+    /// the same forgery does not work against CMAC
+    fn test_cmac_resists_extension_forgery() {
+        let message = b"from=alice&to=bob&amount=0000010";
+        let extension = b"to=mallory&amount=10000000000000";
+
+        let tag = cmac::cmac(key(), message);
+        let forged = forge_extension(message, tag, [0; 16], extension);
+
+        let mut mac = cmac::Cmac::new(key());
+        mac.update(&forged);
+        assert_eq!(
+            mac.verify(&cmac::cmac(key(), extension)),
+            Err(AuthenticationError)
+        );
+    }
+}
@@ -0,0 +1,408 @@
+//! Counter with CBC-MAC (CCM)
+//!
+//! CCM authenticates the input with a CBC-MAC, and then encrypts both the plaintext
+//! and the MAC in counter mode.
+//!
+//! <https://nvlpubs.nist.gov/nistpubs/Legacy/SP/nistspecialpublication800-38c.pdf>
+//! <https://www.rfc-editor.org/rfc/rfc3610>
+//!
+//! The nonce length determines how large a message can be: The length of the message
+//! is encoded in the remaining `15 - nonce.len()` bytes of the first block.
+
+use std::ops::RangeInclusive;
+
+use super::{cipher, constant_time_eq, key::RoundKeys128, AuthenticationError, Key128};
+
+/// Permitted tag lengths in bytes
+pub const TAG_LENGTHS: [usize; 7] = [4, 6, 8, 10, 12, 14, 16];
+
+/// Permitted nonce lengths in bytes
+pub const NONCE_LENGTHS: RangeInclusive<usize> = 7..=13;
+
+/// Returns the number of bytes used to encode the message length (called `L` in
+/// RFC 3610 and `q` in SP 800-38C)
+fn length_field_size(nonce: &[u8]) -> usize {
+    assert!(NONCE_LENGTHS.contains(&nonce.len()), "invalid nonce length");
+    #[expect(
+        clippy::arithmetic_side_effects,
+        reason = "nonce length is at most 13, checked above"
+    )]
+    {
+        15 - nonce.len()
+    }
+}
+
+/// Writes `value` big-endian into the last `size` bytes of `block`. Returns `None`
+/// if `value` does not fit into `size` bytes.
+fn encode_length(block: &mut [u8; 16], value: usize, size: usize) -> Option<()> {
+    let value = u64::try_from(value).expect("usize fits into u64");
+    let bytes = value.to_be_bytes();
+
+    let (overflow, encoded) = bytes.split_at(bytes.len().saturating_sub(size));
+    if overflow.iter().any(|b| *b != 0) {
+        return None;
+    }
+
+    block[16_usize.saturating_sub(encoded.len())..].copy_from_slice(encoded);
+    Some(())
+}
+
+/// The counter block `A_i`, consisting of a flags byte, the nonce and the counter
+fn counter_block(nonce: &[u8], counter: usize) -> Option<[u8; 16]> {
+    let size = length_field_size(nonce);
+
+    let mut block = [0; 16];
+    block[0] = u8::try_from(size.saturating_sub(1)).expect("size is at most 8");
+    block[1..=nonce.len()].copy_from_slice(nonce);
+    encode_length(&mut block, counter, size)?;
+    Some(block)
+}
+
+/// Formats the input to the CBC-MAC: `B_0`, followed by the encoded AAD and the
+/// plaintext, both zero-padded to the block size. Returns `None` if the plaintext is
+/// too long for the nonce length.
+fn format_input(nonce: &[u8], aad: &[u8], plaintext: &[u8], tag_len: usize) -> Option<Vec<u8>> {
+    fn pad(output: &mut Vec<u8>) {
+        let remainder = output.len() % 16;
+        if remainder != 0 {
+            output.resize(
+                output
+                    .len()
+                    .checked_add(16_usize.saturating_sub(remainder))
+                    .expect("input too large"),
+                0,
+            );
+        }
+    }
+
+    let size = length_field_size(nonce);
+
+    let mut b0 = [0; 16];
+    let adata_flag: u8 = if aad.is_empty() { 0 } else { 0x40 };
+    #[expect(clippy::integer_division, reason = "all tag lengths are even")]
+    let tag_flag = u8::try_from(tag_len.saturating_sub(2) / 2).expect("tag length is at most 16");
+    let length_flag = u8::try_from(size.saturating_sub(1)).expect("size is at most 8");
+    b0[0] = adata_flag | (tag_flag << 3_u8) | length_flag;
+    b0[1..=nonce.len()].copy_from_slice(nonce);
+    encode_length(&mut b0, plaintext.len(), size)?;
+
+    let mut output = Vec::from(b0);
+
+    if !aad.is_empty() {
+        let aad_len = u64::try_from(aad.len()).expect("usize fits into u64");
+        if aad_len < 0xff00 {
+            output.extend_from_slice(&aad_len.to_be_bytes()[6..]);
+        } else if let Ok(aad_len) = u32::try_from(aad_len) {
+            output.extend_from_slice(&[0xff, 0xfe]);
+            output.extend_from_slice(&aad_len.to_be_bytes());
+        } else {
+            output.extend_from_slice(&[0xff, 0xff]);
+            output.extend_from_slice(&aad_len.to_be_bytes());
+        }
+        output.extend_from_slice(aad);
+        pad(&mut output);
+    }
+
+    output.extend_from_slice(plaintext);
+    pad(&mut output);
+
+    Some(output)
+}
+
+fn cbc_mac(round_keys: &RoundKeys128, input: &[u8]) -> [u8; 16] {
+    input.chunks(16).fold([0; 16], |state, chunk| {
+        let mut block = state;
+        for (b, c) in block.iter_mut().zip(chunk.iter()) {
+            *b ^= c;
+        }
+        cipher(block, round_keys)
+    })
+}
+
+/// Counter mode encryption, starting with counter 1. Counter 0 is reserved for the tag.
+/// Returns `None` if the input is too long for the nonce length.
+fn ctr(round_keys: &RoundKeys128, nonce: &[u8], input: &[u8]) -> Option<Vec<u8>> {
+    let mut output = Vec::with_capacity(input.len());
+    for (i, chunk) in input.chunks(16).enumerate() {
+        let keystream = cipher(
+            counter_block(nonce, i.checked_add(1).expect("input too large"))?,
+            round_keys,
+        );
+        output.extend(chunk.iter().zip(keystream).map(|(c, k)| c ^ k));
+    }
+    Some(output)
+}
+
+fn tag(
+    round_keys: &RoundKeys128,
+    nonce: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+    tag_len: usize,
+) -> Option<Vec<u8>> {
+    let mac = cbc_mac(round_keys, &format_input(nonce, aad, plaintext, tag_len)?);
+    let s0 = cipher(counter_block(nonce, 0)?, round_keys);
+
+    Some(
+        mac.iter()
+            .zip(s0.iter())
+            .take(tag_len)
+            .map(|(m, s)| m ^ s)
+            .collect(),
+    )
+}
+
+/// Authenticates `aad` and `plaintext`, and encrypts `plaintext`.
+///
+/// Returns the ciphertext with a tag of `tag_len` bytes appended. `tag_len` has to be
+/// one of [`TAG_LENGTHS`], and the nonce length has to be in [`NONCE_LENGTHS`].
+pub fn seal(key: Key128, nonce: &[u8], aad: &[u8], plaintext: &[u8], tag_len: usize) -> Vec<u8> {
+    assert!(TAG_LENGTHS.contains(&tag_len), "invalid tag length");
+
+    let round_keys = key.expand();
+
+    let mut output =
+        ctr(&round_keys, nonce, plaintext).expect("message too long for the nonce length");
+    output.extend(
+        tag(&round_keys, nonce, aad, plaintext, tag_len)
+            .expect("message too long for the nonce length"),
+    );
+    output
+}
+
+/// Decrypts and verifies the output of [`seal()`].
+///
+/// In contrast to GCM, the tag is computed over the plaintext, so decryption has to
+/// happen first. The plaintext is only returned if the tag matches, though. A
+/// ciphertext that is too long for the nonce length fails like a wrong tag.
+pub fn open(
+    key: Key128,
+    nonce: &[u8],
+    aad: &[u8],
+    ciphertext: &[u8],
+    tag_len: usize,
+) -> Result<Vec<u8>, AuthenticationError> {
+    assert!(TAG_LENGTHS.contains(&tag_len), "invalid tag length");
+
+    let (ciphertext, received_tag) = ciphertext
+        .len()
+        .checked_sub(tag_len)
+        .map(|split| ciphertext.split_at(split))
+        .ok_or(AuthenticationError)?;
+
+    let round_keys = key.expand();
+
+    let plaintext = ctr(&round_keys, nonce, ciphertext).ok_or(AuthenticationError)?;
+    let expected_tag =
+        tag(&round_keys, nonce, aad, &plaintext, tag_len).ok_or(AuthenticationError)?;
+
+    if !constant_time_eq(&expected_tag, received_tag) {
+        return Err(AuthenticationError);
+    }
+
+    Ok(plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{aes::parse_key, hex::parse};
+
+    struct TestCase {
+        key: &'static str,
+        nonce: &'static str,
+        aad: &'static str,
+        plaintext: &'static str,
+        tag_len: usize,
+        output: &'static str,
+    }
+
+    /// Packet vectors #1, #2, #3, #7 and #10 from RFC 3610. The RFC lists the AAD
+    /// as part of the output, which is removed here.
+    const RFC_3610_PACKET_VECTORS: [TestCase; 5] = [
+        TestCase {
+            key: "c0c1c2c3c4c5c6c7c8c9cacbcccdcecf",
+            nonce: "00000003020100a0a1a2a3a4a5",
+            aad: "0001020304050607",
+            plaintext: "08090a0b0c0d0e0f101112131415161718191a1b1c1d1e",
+            tag_len: 8,
+            output: "588c979a61c663d2f066d0c2c0f989806d5f6b61dac38417e8d12cfdf926e0",
+        },
+        TestCase {
+            key: "c0c1c2c3c4c5c6c7c8c9cacbcccdcecf",
+            nonce: "00000004030201a0a1a2a3a4a5",
+            aad: "0001020304050607",
+            plaintext: "08090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+            tag_len: 8,
+            output: "72c91a36e135f8cf291ca894085c87e3cc15c439c9e43a3ba091d56e10400916",
+        },
+        TestCase {
+            key: "c0c1c2c3c4c5c6c7c8c9cacbcccdcecf",
+            nonce: "00000005040302a0a1a2a3a4a5",
+            aad: "0001020304050607",
+            plaintext: "08090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20",
+            tag_len: 8,
+            output: "51b1e5f44a197d1da46b0f8e2d282ae871e838bb64da8596574adaa76fbd9fb0c5",
+        },
+        TestCase {
+            key: "c0c1c2c3c4c5c6c7c8c9cacbcccdcecf",
+            nonce: "00000009080706a0a1a2a3a4a5",
+            aad: "0001020304050607",
+            plaintext: "08090a0b0c0d0e0f101112131415161718191a1b1c1d1e",
+            tag_len: 10,
+            output: "0135d1b2c95f41d5d1d4fec185d166b8094e999dfed96c048c56602c97acbb7490",
+        },
+        TestCase {
+            key: "c0c1c2c3c4c5c6c7c8c9cacbcccdcecf",
+            nonce: "0000000c0b0a09a0a1a2a3a4a5",
+            aad: "000102030405060708090a0b",
+            plaintext: "0c0d0e0f101112131415161718191a1b1c1d1e",
+            tag_len: 10,
+            output: "07342594157785152b074098330abb141b947b566aa9406b4d999988dd",
+        },
+    ];
+
+    /// Examples 1 to 3 from appendix C of SP 800-38C, which use different nonce
+    /// lengths than the RFC
+    const SP_800_38C_EXAMPLES: [TestCase; 3] = [
+        TestCase {
+            key: "404142434445464748494a4b4c4d4e4f",
+            nonce: "10111213141516",
+            aad: "0001020304050607",
+            plaintext: "20212223",
+            tag_len: 4,
+            output: "7162015b4dac255d",
+        },
+        TestCase {
+            key: "404142434445464748494a4b4c4d4e4f",
+            nonce: "1011121314151617",
+            aad: "000102030405060708090a0b0c0d0e0f",
+            plaintext: "202122232425262728292a2b2c2d2e2f",
+            tag_len: 6,
+            output: "d2a1f0e051ea5f62081a7792073d593d1fc64fbfaccd",
+        },
+        TestCase {
+            key: "404142434445464748494a4b4c4d4e4f",
+            nonce: "101112131415161718191a1b",
+            aad: "000102030405060708090a0b0c0d0e0f10111213",
+            plaintext: "202122232425262728292a2b2c2d2e2f3031323334353637",
+            tag_len: 8,
+            output: "e3b201a9f5b71a7a9b1ceaeccd97e70b6176aad9a4428aa5484392fbc1b09951",
+        },
+    ];
+
+    #[test]
+    fn test_seal() {
+        for case in RFC_3610_PACKET_VECTORS
+            .iter()
+            .chain(SP_800_38C_EXAMPLES.iter())
+        {
+            assert_eq!(
+                seal(
+                    parse_key(case.key),
+                    &parse(case.nonce),
+                    &parse(case.aad),
+                    &parse(case.plaintext),
+                    case.tag_len
+                ),
+                parse(case.output)
+            );
+        }
+    }
+
+    #[test]
+    fn test_open() {
+        for case in RFC_3610_PACKET_VECTORS
+            .iter()
+            .chain(SP_800_38C_EXAMPLES.iter())
+        {
+            assert_eq!(
+                open(
+                    parse_key(case.key),
+                    &parse(case.nonce),
+                    &parse(case.aad),
+                    &parse(case.output),
+                    case.tag_len
+                ),
+                Ok(parse(case.plaintext))
+            );
+        }
+    }
+
+    #[test]
+    fn test_open_rejects_modifications() {
+        let case = &RFC_3610_PACKET_VECTORS[0];
+        let sealed = parse(case.output);
+        let aad = parse(case.aad);
+
+        let open = |sealed: &[u8], aad: &[u8]| {
+            open(
+                parse_key(case.key),
+                &parse(case.nonce),
+                aad,
+                sealed,
+                case.tag_len,
+            )
+        };
+
+        for i in 0..sealed.len() {
+            let mut modified = sealed.clone();
+            modified[i] ^= 0x80;
+            assert_eq!(open(&modified, &aad), Err(AuthenticationError));
+        }
+
+        assert_eq!(open(&sealed, &aad[1..]), Err(AuthenticationError));
+        assert_eq!(open(&sealed[..7], &aad), Err(AuthenticationError));
+    }
+
+    #[test]
+    fn test_long_aad_encoding() {
+        let aad = vec![0xaa; 0xff00];
+        let input = format_input(&[0; 13], &aad, &[], 16).expect("message fits");
+
+        assert_eq!(input[16..22], [0xff, 0xfe, 0x00, 0x00, 0xff, 0x00]);
+        assert_eq!(input.len() % 16, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "message too long for the nonce length")]
+    fn test_message_length_limit() {
+        // a 13 byte nonce leaves 2 bytes to encode the message length
+        let plaintext = vec![0; 0x1_0000];
+        seal(
+            parse_key("c0c1c2c3c4c5c6c7c8c9cacbcccdcecf"),
+            &[0; 13],
+            &[],
+            &plaintext,
+            16,
+        );
+    }
+
+    #[test]
+    fn test_open_message_length_limit() {
+        // the length of a received message must not panic, but fail to authenticate
+        let ciphertext = vec![0; 0x1_0000 + 16];
+        assert_eq!(
+            open(
+                parse_key("c0c1c2c3c4c5c6c7c8c9cacbcccdcecf"),
+                &[0; 13],
+                &[],
+                &ciphertext,
+                16
+            ),
+            Err(AuthenticationError)
+        );
+
+        let ciphertext = vec![0; 0xffff + 16];
+        assert_eq!(
+            open(
+                parse_key("c0c1c2c3c4c5c6c7c8c9cacbcccdcecf"),
+                &[0; 13],
+                &[],
+                &ciphertext,
+                16
+            ),
+            Err(AuthenticationError)
+        );
+    }
+}
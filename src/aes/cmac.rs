@@ -0,0 +1,167 @@
+//! Cipher-based Message Authentication Code (CMAC)
+//!
+//! <https://www.rfc-editor.org/rfc/rfc4493>
+//! <https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-38b.pdf>
+
+use super::{cipher, constant_time_eq, gf128, key::RoundKeys128, AuthenticationError, Key128};
+
+/// Incremental CMAC computation.
+///
+/// CMAC is CBC-MAC with a zero IV, except that the last block is combined with one of
+/// two subkeys before encryption, depending on whether it is complete or padded. This
+/// prevents the extension forgeries that raw CBC-MAC is vulnerable to.
+#[derive(Clone)]
+pub struct Cmac {
+    round_keys: RoundKeys128,
+    state: [u8; 16],
+    k1: u128,
+    k2: u128,
+    /// The last block needs special treatment, so it is held back until more data
+    /// arrives
+    last: [u8; 16],
+    last_len: usize,
+}
+
+impl Cmac {
+    pub fn new(key: Key128) -> Self {
+        Self::from_round_keys(key.expand())
+    }
+
+    pub(super) fn from_round_keys(round_keys: RoundKeys128) -> Self {
+        // Derives the two subkeys `K1` and `K2` by doubling the encrypted zero block
+        let l = u128::from_be_bytes(cipher([0; 16], &round_keys));
+        let k1 = gf128::dbl(l);
+        let k2 = gf128::dbl(k1);
+
+        Self {
+            round_keys,
+            state: [0; 16],
+            k1,
+            k2,
+            last: [0; 16],
+            last_len: 0,
+        }
+    }
+
+    /// Adds a complete block to the CBC chain
+    fn process(&mut self, block: [u8; 16]) {
+        for (s, b) in self.state.iter_mut().zip(block.iter()) {
+            *s ^= b;
+        }
+        self.state = cipher(self.state, &self.round_keys);
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            if self.last_len == 16 {
+                self.process(self.last);
+                self.last_len = 0;
+            }
+
+            let (head, tail) =
+                data.split_at(16_usize.saturating_sub(self.last_len).min(data.len()));
+
+            let end = self
+                .last_len
+                .checked_add(head.len())
+                .expect("buffer holds at most 16 bytes");
+            self.last[self.last_len..end].copy_from_slice(head);
+            self.last_len = end;
+
+            data = tail;
+        }
+    }
+
+    pub fn finalize(mut self) -> [u8; 16] {
+        // An empty message is handled like a message with an incomplete last block
+        let last = if self.last_len == 16 {
+            gf128::add(u128::from_be_bytes(self.last), self.k1)
+        } else {
+            self.last[self.last_len..].fill(0);
+            self.last[self.last_len] = 0x80;
+            gf128::add(u128::from_be_bytes(self.last), self.k2)
+        };
+
+        self.process(last.to_be_bytes());
+        self.state
+    }
+
+    /// Compares the tag against `tag` in constant time
+    pub fn verify(self, tag: &[u8]) -> Result<(), AuthenticationError> {
+        if constant_time_eq(&self.finalize(), tag) {
+            Ok(())
+        } else {
+            Err(AuthenticationError)
+        }
+    }
+}
+
+/// Computes the CMAC of `message` in one go.
+pub fn cmac(key: Key128, message: &[u8]) -> [u8; 16] {
+    let mut mac = Cmac::new(key);
+    mac.update(message);
+    mac.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{aes::parse_key, hex::parse};
+
+    fn key() -> Key128 {
+        parse_key("2b7e151628aed2a6abf7158809cf4f3c")
+    }
+
+    const MESSAGE: &str = "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e5130c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710";
+
+    /// the four examples from RFC 4493, as length of the message and tag
+    const RFC_4493_VECTORS: [(usize, &str); 4] = [
+        (0, "bb1d6929e95937287fa37d129b756746"),
+        (16, "070a16b46b4d4144f79bdd9dd04a287c"),
+        (40, "dfa66747de9ae63030ca32611497c827"),
+        (64, "51f0bebf7e3b9d92fc49741779363cfe"),
+    ];
+
+    #[test]
+    fn test_cmac_from_rfc() {
+        let message = parse(MESSAGE);
+
+        for (len, expected) in RFC_4493_VECTORS {
+            assert_eq!(cmac(key(), &message[..len]).to_vec(), parse(expected));
+        }
+    }
+
+    #[test]
+    fn test_streaming() {
+        let message = parse(MESSAGE);
+
+        for (len, expected) in RFC_4493_VECTORS {
+            for chunk_size in 1..=len.max(1) {
+                let mut mac = Cmac::new(key());
+                for chunk in message[..len].chunks(chunk_size) {
+                    mac.update(chunk);
+                }
+                // empty updates must not change the result
+                mac.update(&[]);
+                assert_eq!(mac.finalize().to_vec(), parse(expected));
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let message = parse(MESSAGE);
+
+        for (len, expected) in RFC_4493_VECTORS {
+            let mut tag = parse(expected);
+
+            let mut mac = Cmac::new(key());
+            mac.update(&message[..len]);
+            assert_eq!(mac.clone().verify(&tag), Ok(()));
+            assert_eq!(mac.clone().verify(&tag[..15]), Err(AuthenticationError));
+
+            tag[0] ^= 0x80;
+            assert_eq!(mac.verify(&tag), Err(AuthenticationError));
+        }
+    }
+}
@@ -0,0 +1,274 @@
+#![expect(
+    clippy::little_endian_bytes,
+    reason = "the cryptopals counter format is little-endian"
+)]
+//! Counter mode (CTR)
+//!
+//! The block cipher encrypts a sequence of counter blocks, and the resulting keystream
+//! is added to the input with XOR. Encryption and decryption are the same operation.
+//!
+//! <https://nvlpubs.nist.gov/nistpubs/Legacy/SP/nistspecialpublication800-38a.pdf>
+//! <https://www.cryptopals.com/sets/3/challenges/18>
+//!
+//! Because the counter block of any position can be computed directly, the keystream
+//! can start at any byte offset. [`Stream`] uses this to provide random access to
+//! encrypted data via [`Seek`].
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use super::{cipher, key::RoundKeys128, Key128};
+
+/// How the counter block for a block index is formed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Counter {
+    /// A 64 bit nonce followed by a 64 bit block count, both little-endian. This is
+    /// the format used by cryptopals.
+    LittleEndian64 { nonce: u64 },
+    /// The whole block is a single big-endian number, starting at `initial` and
+    /// incremented modulo 2¹²⁸. This is the format of the examples in SP 800-38A.
+    BigEndian128 { initial: u128 },
+}
+
+impl Counter {
+    fn block(self, index: u64) -> [u8; 16] {
+        match self {
+            Self::LittleEndian64 { nonce } => {
+                let mut block = [0; 16];
+                block[..8].copy_from_slice(&nonce.to_le_bytes());
+                block[8..].copy_from_slice(&index.to_le_bytes());
+                block
+            }
+            Self::BigEndian128 { initial } => initial.wrapping_add(u128::from(index)).to_be_bytes(),
+        }
+    }
+}
+
+/// The keystream for a key and counter format
+pub struct Keystream {
+    round_keys: RoundKeys128,
+    counter: Counter,
+}
+
+impl Keystream {
+    pub fn new(key: Key128, counter: Counter) -> Self {
+        Self {
+            round_keys: key.expand(),
+            counter,
+        }
+    }
+
+    /// XORs `data` with the keystream, starting at byte `offset` of the keystream
+    pub fn apply(&self, offset: u64, mut data: &mut [u8]) {
+        #[expect(clippy::integer_division, reason = "rounding down is intended")]
+        let mut index = offset / 16;
+        let mut skip = usize::try_from(offset % 16).expect("remainder is smaller than 16");
+
+        while !data.is_empty() {
+            let keystream = cipher(self.counter.block(index), &self.round_keys);
+            let keystream = &keystream[skip..];
+
+            let (head, tail) = data.split_at_mut(keystream.len().min(data.len()));
+            for (d, k) in head.iter_mut().zip(keystream.iter()) {
+                *d ^= k;
+            }
+
+            data = tail;
+            skip = 0;
+            index = index.wrapping_add(1);
+        }
+    }
+}
+
+/// Encrypts `plaintext`
+pub fn encrypt(plaintext: &[u8], key: Key128, counter: Counter) -> Vec<u8> {
+    let mut output = plaintext.to_vec();
+    Keystream::new(key, counter).apply(0, &mut output);
+    output
+}
+
+/// Decrypts `ciphertext`, which is the same operation as [`encrypt()`]
+pub fn decrypt(ciphertext: &[u8], key: Key128, counter: Counter) -> Vec<u8> {
+    encrypt(ciphertext, key, counter)
+}
+
+/// Wraps a stream of ciphertext. Reading returns plaintext, writing encrypts.
+///
+/// The position of the inner stream has to match the position within the ciphertext,
+/// so it must be at the start of the ciphertext when it is wrapped. Seeking is
+/// passed through to the inner stream, and the keystream continues at the new
+/// position, so reading from the middle of a large file only decrypts the requested
+/// data.
+pub struct Stream<S> {
+    inner: S,
+    keystream: Keystream,
+    position: u64,
+}
+
+impl<S> Stream<S> {
+    pub fn new(inner: S, key: Key128, counter: Counter) -> Self {
+        Self {
+            inner,
+            keystream: Keystream::new(key, counter),
+            position: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn advance(&mut self, len: usize) {
+        self.position = self
+            .position
+            .checked_add(u64::try_from(len).expect("usize fits into u64"))
+            .expect("position overflows u64");
+    }
+}
+
+impl<S: Read> Read for Stream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.keystream.apply(self.position, &mut buf[..len]);
+        self.advance(len);
+        Ok(len)
+    }
+}
+
+impl<S: Write> Write for Stream<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut encrypted = buf.to_vec();
+        self.keystream.apply(self.position, &mut encrypted);
+
+        // only the bytes that were actually written advance the position, so the
+        // keystream stays in sync when the caller retries the rest
+        let len = self.inner.write(&encrypted)?;
+        self.advance(len);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<S: Seek> Seek for Stream<S> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.position = self.inner.seek(pos)?;
+        Ok(self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{aes::parse_key, base64, hex::parse};
+
+    fn key() -> Key128 {
+        Key128::from_bytes(*b"YELLOW SUBMARINE")
+    }
+
+    #[test]
+    /// taken from appendix F.5.1 of SP 800-38A
+    fn test_ctr_from_spec() {
+        let key = || parse_key("2b7e151628aed2a6abf7158809cf4f3c");
+        let counter = Counter::BigEndian128 {
+            initial: 0xf0f1_f2f3_f4f5_f6f7_f8f9_fafb_fcfd_feff,
+        };
+        let plaintext = parse("6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e5130c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710");
+        let ciphertext = parse("874d6191b620e3261bef6864990db6ce9806f66b7970fdff8617187bb9fffdff5ae4df3edbd5d35e5b4f09020db03eab1e031dda2fbe03d1792170a0f3009cee");
+
+        assert_eq!(encrypt(&plaintext, key(), counter), ciphertext);
+        assert_eq!(decrypt(&ciphertext, key(), counter), plaintext);
+    }
+
+    #[test]
+    fn test_ctr_cryptopals() {
+        let ciphertext = base64::decode_str(
+            "L77na/nrFsKvynd6HzOoG7GHTLXsTVu9qvY/2syLXzhPweyyMTJULu/6/kXX0KSvoOLSFQ==",
+        )
+        .expect("test data is valid base64");
+
+        assert_eq!(
+            decrypt(&ciphertext, key(), Counter::LittleEndian64 { nonce: 0 }),
+            b"Yo, VIP Let's kick it Ice, Ice, baby Ice, Ice, baby "
+        );
+    }
+
+    fn plaintext() -> Vec<u8> {
+        (0..5000_u32)
+            .map(|i| u8::try_from(i % 251).expect("value is smaller than 251"))
+            .collect()
+    }
+
+    const COUNTER: Counter = Counter::LittleEndian64 { nonce: 0x0123_4567 };
+
+    #[test]
+    fn test_keystream_at_offset() {
+        let ciphertext = encrypt(&plaintext(), key(), COUNTER);
+        let keystream = Keystream::new(key(), COUNTER);
+
+        for (start, end) in [
+            (0, 1),
+            (3, 16),
+            (15, 17),
+            (16, 32),
+            (100, 4999),
+            (4999, 5000),
+        ] {
+            let mut data = ciphertext[start..end].to_vec();
+            keystream.apply(u64::try_from(start).expect("offset is small"), &mut data);
+            assert_eq!(data, plaintext()[start..end]);
+        }
+    }
+
+    #[test]
+    fn test_seek_and_read() {
+        let ciphertext = encrypt(&plaintext(), key(), COUNTER);
+        let mut stream = Stream::new(io::Cursor::new(ciphertext), key(), COUNTER);
+
+        let mut buf = [0; 100];
+
+        stream
+            .seek(SeekFrom::Start(1234))
+            .expect("seeking in a cursor succeeds");
+        stream.read_exact(&mut buf).expect("enough data available");
+        assert_eq!(buf[..], plaintext()[1234..1334]);
+
+        // reading continues at the current position
+        stream.read_exact(&mut buf).expect("enough data available");
+        assert_eq!(buf[..], plaintext()[1334..1434]);
+
+        stream
+            .seek(SeekFrom::Current(-150))
+            .expect("seeking in a cursor succeeds");
+        stream.read_exact(&mut buf).expect("enough data available");
+        assert_eq!(buf[..], plaintext()[1284..1384]);
+
+        stream
+            .seek(SeekFrom::End(-7))
+            .expect("seeking in a cursor succeeds");
+        let mut rest = Vec::new();
+        stream.read_to_end(&mut rest).expect("reading succeeds");
+        assert_eq!(rest, plaintext()[4993..]);
+    }
+
+    #[test]
+    fn test_seek_and_write() {
+        let mut plaintext = plaintext();
+        let ciphertext = encrypt(&plaintext, key(), COUNTER);
+        let mut stream = Stream::new(io::Cursor::new(ciphertext), key(), COUNTER);
+
+        stream
+            .seek(SeekFrom::Start(2021))
+            .expect("seeking in a cursor succeeds");
+        stream
+            .write_all(b"overwritten in place")
+            .expect("writing to a cursor succeeds");
+
+        plaintext[2021..2041].copy_from_slice(b"overwritten in place");
+        assert_eq!(
+            stream.into_inner().into_inner(),
+            encrypt(&plaintext, key(), COUNTER)
+        );
+    }
+}
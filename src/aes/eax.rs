@@ -0,0 +1,225 @@
+//! EAX
+//!
+//! EAX combines counter mode with OMAC (which is [`super::cmac`]) in an
+//! encrypt-then-MAC construction. The nonce, the associated data and the ciphertext
+//! are each authenticated with a differently tweaked OMAC, and the three results are
+//! combined into the tag.
+//!
+//! <https://web.cs.ucdavis.edu/~rogaway/papers/eax.pdf>
+//!
+//! The nonce may be of any length. The output of [`seal()`] is the ciphertext with
+//! the tag appended.
+
+use std::ops::RangeInclusive;
+
+use super::{cipher, cmac::Cmac, constant_time_eq, key::RoundKeys128, AuthenticationError, Key128};
+
+/// Permitted tag lengths in bytes
+pub const TAG_LENGTHS: RangeInclusive<usize> = 1..=16;
+
+/// OMAC with tweak `t`, i.e. CMAC over `t` encoded as a full block, followed by
+/// `input`
+fn omac(mac: &Cmac, t: u8, input: &[u8]) -> [u8; 16] {
+    let mut tweak = [0; 16];
+    tweak[15] = t;
+
+    let mut mac = mac.clone();
+    mac.update(&tweak);
+    mac.update(input);
+    mac.finalize()
+}
+
+/// Counter mode with a 128 bit big-endian counter
+fn ctr(round_keys: &RoundKeys128, initial: [u8; 16], input: &[u8]) -> Vec<u8> {
+    let mut counter = u128::from_be_bytes(initial);
+
+    let mut output = Vec::with_capacity(input.len());
+    for chunk in input.chunks(16) {
+        let keystream = cipher(counter.to_be_bytes(), round_keys);
+        output.extend(chunk.iter().zip(keystream.iter()).map(|(c, k)| c ^ k));
+        counter = counter.wrapping_add(1);
+    }
+
+    output
+}
+
+fn tag(mac: &Cmac, n: [u8; 16], aad: &[u8], ciphertext: &[u8]) -> [u8; 16] {
+    let h = omac(mac, 1, aad);
+    let c = omac(mac, 2, ciphertext);
+
+    let mut tag = n;
+    for ((t, h), c) in tag.iter_mut().zip(h.iter()).zip(c.iter()) {
+        *t ^= h ^ c;
+    }
+    tag
+}
+
+/// Encrypts and authenticates `plaintext`, additionally authenticating `aad`.
+pub fn seal(key: Key128, nonce: &[u8], aad: &[u8], plaintext: &[u8], tag_len: usize) -> Vec<u8> {
+    assert!(TAG_LENGTHS.contains(&tag_len), "invalid tag length");
+
+    let round_keys = key.expand();
+    let mac = Cmac::from_round_keys(round_keys.clone());
+
+    let n = omac(&mac, 0, nonce);
+    let mut output = ctr(&round_keys, n, plaintext);
+
+    let tag = tag(&mac, n, aad, &output);
+    output.extend_from_slice(&tag[..tag_len]);
+    output
+}
+
+/// Decrypts and verifies the output of [`seal()`].
+pub fn open(
+    key: Key128,
+    nonce: &[u8],
+    aad: &[u8],
+    ciphertext: &[u8],
+    tag_len: usize,
+) -> Result<Vec<u8>, AuthenticationError> {
+    assert!(TAG_LENGTHS.contains(&tag_len), "invalid tag length");
+
+    let (ciphertext, received_tag) = ciphertext
+        .len()
+        .checked_sub(tag_len)
+        .map(|split| ciphertext.split_at(split))
+        .ok_or(AuthenticationError)?;
+
+    let round_keys = key.expand();
+    let mac = Cmac::from_round_keys(round_keys.clone());
+
+    let n = omac(&mac, 0, nonce);
+    let expected_tag = tag(&mac, n, aad, ciphertext);
+
+    if !constant_time_eq(&expected_tag[..tag_len], received_tag) {
+        return Err(AuthenticationError);
+    }
+
+    Ok(ctr(&round_keys, n, ciphertext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{aes::parse_key, hex::parse};
+
+    struct TestCase {
+        key: &'static str,
+        nonce: &'static str,
+        header: &'static str,
+        plaintext: &'static str,
+        ciphertext: &'static str,
+    }
+
+    /// The first five test vectors from appendix A of the EAX paper
+    const PAPER_VECTORS: [TestCase; 5] = [
+        TestCase {
+            key: "233952dee4d5ed5f9b9c6d6ff80ff478",
+            nonce: "62ec67f9c3a4a407fcb2a8c49031a8b3",
+            header: "6bfb914fd07eae6b",
+            plaintext: "",
+            ciphertext: "e037830e8389f27b025a2d6527e79d01",
+        },
+        TestCase {
+            key: "91945d3f4dcbee0bf45ef52255f095a4",
+            nonce: "becaf043b0a23d843194ba972c66debd",
+            header: "fa3bfd4806eb53fa",
+            plaintext: "f7fb",
+            ciphertext: "19dd5c4c9331049d0bdab0277408f67967e5",
+        },
+        TestCase {
+            key: "01f74ad64077f2e704c0f60ada3dd523",
+            nonce: "70c3db4f0d26368400a10ed05d2bff5e",
+            header: "234a3463c1264ac6",
+            plaintext: "1a47cb4933",
+            ciphertext: "d851d5bae03a59f238a23e39199dc9266626c40f80",
+        },
+        TestCase {
+            key: "d07cf6cbb7f313bdde66b727afd3c5e8",
+            nonce: "8408dfff3c1a2b1292dc199e46b7d617",
+            header: "33cce2eabff5a79d",
+            plaintext: "481c9e39b1",
+            ciphertext: "632a9d131ad4c168a4225d8e1ff755939974a7bede",
+        },
+        TestCase {
+            key: "35b6d0580005bbc12b0587124557d2c2",
+            nonce: "fdb6b06676eedc5c61d74276e1f8e816",
+            header: "aeb96eaebe2970e9",
+            plaintext: "40d0c07da5e4",
+            ciphertext: "071dfe16c675cb0677e536f73afe6a14b74ee49844dd",
+        },
+    ];
+
+    #[test]
+    fn test_seal_and_open_from_paper() {
+        for case in PAPER_VECTORS {
+            let nonce = parse(case.nonce);
+            let header = parse(case.header);
+            let plaintext = parse(case.plaintext);
+            let ciphertext = parse(case.ciphertext);
+
+            assert_eq!(
+                seal(parse_key(case.key), &nonce, &header, &plaintext, 16),
+                ciphertext
+            );
+            assert_eq!(
+                open(parse_key(case.key), &nonce, &header, &ciphertext, 16),
+                Ok(plaintext)
+            );
+        }
+    }
+
+    #[test]
+    fn test_truncated_tag() {
+        let case = &PAPER_VECTORS[4];
+        let nonce = parse(case.nonce);
+        let header = parse(case.header);
+        let plaintext = parse(case.plaintext);
+        let ciphertext = parse(case.ciphertext);
+
+        // truncation does not change the ciphertext or the tag prefix
+        let truncated = &ciphertext[..plaintext.len() + 8];
+        assert_eq!(
+            seal(parse_key(case.key), &nonce, &header, &plaintext, 8),
+            truncated
+        );
+        assert_eq!(
+            open(parse_key(case.key), &nonce, &header, truncated, 8),
+            Ok(plaintext)
+        );
+        assert_eq!(
+            open(parse_key(case.key), &nonce, &header, truncated, 16),
+            Err(AuthenticationError)
+        );
+    }
+
+    #[test]
+    fn test_open_rejects_modifications() {
+        let case = &PAPER_VECTORS[2];
+        let nonce = parse(case.nonce);
+        let header = parse(case.header);
+        let ciphertext = parse(case.ciphertext);
+
+        for i in 0..ciphertext.len() {
+            let mut modified = ciphertext.clone();
+            modified[i] ^= 0x01;
+            assert_eq!(
+                open(parse_key(case.key), &nonce, &header, &modified, 16),
+                Err(AuthenticationError)
+            );
+        }
+
+        assert_eq!(
+            open(parse_key(case.key), &nonce, &header[1..], &ciphertext, 16),
+            Err(AuthenticationError)
+        );
+        assert_eq!(
+            open(parse_key(case.key), &nonce[1..], &header, &ciphertext, 16),
+            Err(AuthenticationError)
+        );
+        assert_eq!(
+            open(parse_key(case.key), &nonce, &header, &ciphertext[..15], 16),
+            Err(AuthenticationError)
+        );
+    }
+}
@@ -0,0 +1,362 @@
+//! FF1
+//!
+//! A Feistel network with 10 rounds. The round function is a CBC-MAC over the
+//! parameters, the tweak and one half of the input, extended by encrypting
+//! consecutive counters if more output is needed than a single block. Tweaks can have
+//! any length.
+
+use super::{num::BigUint, validate, Alphabet};
+use crate::{
+    aes::{cbc_mac::CbcMac, Key128},
+    Error,
+};
+
+const ROUNDS: u8 = 10;
+
+/// The state shared by all rounds
+struct Feistel<'a> {
+    radix: u32,
+    tweak: &'a [u8],
+    /// the lengths of the two halves
+    u: usize,
+    v: usize,
+    /// the number of bytes of a half when interpreted as a number
+    b: usize,
+    /// the number of bytes of round function output
+    d: usize,
+    /// the MAC after processing the first block `P`, which is the same for all rounds
+    mac: CbcMac,
+}
+
+impl<'a> Feistel<'a> {
+    #[expect(
+        clippy::many_single_char_names,
+        reason = "the names follow the algorithm description in SP 800-38G"
+    )]
+    fn new(key: Key128, tweak: &'a [u8], radix: u32, input: &[u16]) -> Result<Self, Error> {
+        validate(radix, input)?;
+
+        let n = input.len();
+        let t = u32::try_from(tweak.len()).map_err(|_e| Error("tweak too long".to_owned()))?;
+        let n_bytes = u32::try_from(n)
+            .map_err(|_e| Error("input too long".to_owned()))?
+            .to_be_bytes();
+
+        #[expect(clippy::integer_division, reason = "rounding down is intended")]
+        let u = n / 2;
+        let v = n.checked_sub(u).expect("u is at most n");
+
+        let max_half =
+            vec![u16::try_from(radix.saturating_sub(1)).expect("radix is at most 2^16"); v];
+        let b = BigUint::from_numerals(&max_half, radix)
+            .bit_length()
+            .div_ceil(8);
+        let d = b
+            .div_ceil(4)
+            .checked_mul(4)
+            .and_then(|d| d.checked_add(4))
+            .expect("d does not overflow");
+
+        let radix_bytes = radix.to_be_bytes();
+        let mut p = vec![1, 2, 1];
+        p.extend_from_slice(&radix_bytes[1..]);
+        p.push(10);
+        p.push(u.to_be_bytes()[size_of::<usize>().saturating_sub(1)]);
+        p.extend_from_slice(&n_bytes);
+        p.extend_from_slice(&t.to_be_bytes());
+
+        let mut mac = CbcMac::new(key, [0; 16]);
+        mac.update(&p);
+
+        Ok(Self {
+            radix,
+            tweak,
+            u,
+            v,
+            b,
+            d,
+            mac,
+        })
+    }
+
+    /// The length of the half that is replaced in round `i`
+    const fn m(&self, i: u8) -> usize {
+        if i.is_multiple_of(2) {
+            self.u
+        } else {
+            self.v
+        }
+    }
+
+    /// The round function: `y` reduced to `m` numerals
+    #[expect(
+        clippy::many_single_char_names,
+        reason = "the names follow the algorithm description in SP 800-38G"
+    )]
+    fn round(&self, i: u8, half: &[u16]) -> Vec<u16> {
+        let mut q = self.tweak.to_vec();
+        // pads T || 0* || i || NUM(half) to a multiple of 16 bytes
+        let padding = self
+            .tweak
+            .len()
+            .wrapping_add(self.b)
+            .wrapping_add(1)
+            .wrapping_neg()
+            & 15;
+        q.resize(q.len().saturating_add(padding), 0);
+        q.push(i);
+        q.extend(BigUint::from_numerals(half, self.radix).to_be_bytes(self.b));
+
+        let mut mac = self.mac.clone();
+        mac.update(&q);
+        let r = mac.finalize();
+
+        let mut s = r.to_vec();
+        let mut j = 1_u128;
+        while s.len() < self.d {
+            let block: Vec<u8> = r.iter().zip(j.to_be_bytes()).map(|(r, j)| r ^ j).collect();
+            s.extend(
+                self.mac
+                    .encrypt_block(block.try_into().expect("block is exactly 16 bytes long")),
+            );
+            j = j.wrapping_add(1);
+        }
+        s.truncate(self.d);
+
+        BigUint::from_be_bytes(&s).into_numerals(self.radix, self.m(i))
+    }
+}
+
+/// `(a + b) mod radix^m`, for numerals of length `m`
+fn add(a: &[u16], b: &[u16], radix: u32) -> Vec<u16> {
+    let mut carry = 0;
+    let mut output: Vec<u16> = a
+        .iter()
+        .zip(b)
+        .rev()
+        .map(|(a, b)| {
+            #[expect(
+                clippy::arithmetic_side_effects,
+                reason = "numerals are smaller than the radix, which is at most 2^16"
+            )]
+            let mut sum = u32::from(*a) + u32::from(*b) + carry;
+            carry = u32::from(sum >= radix);
+            if carry == 1 {
+                sum = sum.saturating_sub(radix);
+            }
+            u16::try_from(sum).expect("sum is smaller than the radix")
+        })
+        .collect();
+    output.reverse();
+    output
+}
+
+/// `(a - b) mod radix^m`, for numerals of length `m`
+fn sub(a: &[u16], b: &[u16], radix: u32) -> Vec<u16> {
+    let mut borrow = 0;
+    let mut output: Vec<u16> = a
+        .iter()
+        .zip(b)
+        .rev()
+        .map(|(a, b)| {
+            #[expect(
+                clippy::arithmetic_side_effects,
+                reason = "numerals are smaller than the radix, which is at most 2^16"
+            )]
+            let subtrahend = u32::from(*b) + borrow;
+            let minuend = u32::from(*a);
+            borrow = u32::from(minuend < subtrahend);
+            let difference = if borrow == 1 {
+                minuend.saturating_add(radix).saturating_sub(subtrahend)
+            } else {
+                minuend.saturating_sub(subtrahend)
+            };
+            u16::try_from(difference).expect("difference is smaller than the radix")
+        })
+        .collect();
+    output.reverse();
+    output
+}
+
+/// Encrypts `plaintext`, a string of numerals in `0..radix`.
+pub fn encrypt(
+    key: Key128,
+    tweak: &[u8],
+    radix: u32,
+    plaintext: &[u16],
+) -> Result<Vec<u16>, Error> {
+    let feistel = Feistel::new(key, tweak, radix, plaintext)?;
+    let (a, b) = plaintext.split_at(feistel.u);
+    let (mut a, mut b) = (a.to_vec(), b.to_vec());
+
+    for i in 0..ROUNDS {
+        let c = add(&a, &feistel.round(i, &b), radix);
+        a = b;
+        b = c;
+    }
+
+    a.extend(b);
+    Ok(a)
+}
+
+/// Decrypts `ciphertext`, a string of numerals in `0..radix`.
+pub fn decrypt(
+    key: Key128,
+    tweak: &[u8],
+    radix: u32,
+    ciphertext: &[u16],
+) -> Result<Vec<u16>, Error> {
+    let feistel = Feistel::new(key, tweak, radix, ciphertext)?;
+    let (a, b) = ciphertext.split_at(feistel.u);
+    let (mut a, mut b) = (a.to_vec(), b.to_vec());
+
+    for i in (0..ROUNDS).rev() {
+        let c = sub(&b, &feistel.round(i, &a), radix);
+        b = a;
+        a = c;
+    }
+
+    a.extend(b);
+    Ok(a)
+}
+
+/// Encrypts `plaintext`, which may only contain characters of `alphabet`.
+pub fn encrypt_str(
+    key: Key128,
+    tweak: &[u8],
+    alphabet: &Alphabet,
+    plaintext: &str,
+) -> Result<String, Error> {
+    let numerals = alphabet.to_numerals(plaintext)?;
+    let encrypted = encrypt(key, tweak, alphabet.radix(), &numerals)?;
+    Ok(alphabet.from_numerals(&encrypted))
+}
+
+/// Decrypts `ciphertext`, which may only contain characters of `alphabet`.
+pub fn decrypt_str(
+    key: Key128,
+    tweak: &[u8],
+    alphabet: &Alphabet,
+    ciphertext: &str,
+) -> Result<String, Error> {
+    let numerals = alphabet.to_numerals(ciphertext)?;
+    let decrypted = decrypt(key, tweak, alphabet.radix(), &numerals)?;
+    Ok(alphabet.from_numerals(&decrypted))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        aes::{fpe::DIGITS, parse_key},
+        hex::parse,
+    };
+
+    fn key() -> Key128 {
+        parse_key("2b7e151628aed2a6abf7158809cf4f3c")
+    }
+
+    fn base36() -> Alphabet {
+        Alphabet::new("0123456789abcdefghijklmnopqrstuvwxyz").expect("alphabet is valid")
+    }
+
+    struct TestCase {
+        tweak: &'static str,
+        plaintext: &'static str,
+        ciphertext: &'static str,
+    }
+
+    #[test]
+    /// taken from the NIST FF1 samples 1 to 3 for AES-128
+    fn test_ff1_from_spec() {
+        let digits = Alphabet::new(DIGITS).expect("alphabet is valid");
+        let cases = [
+            (
+                &digits,
+                TestCase {
+                    tweak: "",
+                    plaintext: "0123456789",
+                    ciphertext: "2433477484",
+                },
+            ),
+            (
+                &digits,
+                TestCase {
+                    tweak: "39383736353433323130",
+                    plaintext: "0123456789",
+                    ciphertext: "6124200773",
+                },
+            ),
+            (
+                &base36(),
+                TestCase {
+                    tweak: "3737373770717273373737",
+                    plaintext: "0123456789abcdefghi",
+                    ciphertext: "a9tv40mll9kdu509eum",
+                },
+            ),
+        ];
+
+        for (alphabet, case) in cases {
+            let tweak = parse(case.tweak);
+            assert_eq!(
+                encrypt_str(key(), &tweak, alphabet, case.plaintext).expect("input is valid"),
+                case.ciphertext
+            );
+            assert_eq!(
+                decrypt_str(key(), &tweak, alphabet, case.ciphertext).expect("input is valid"),
+                case.plaintext
+            );
+        }
+    }
+
+    #[test]
+    fn test_credit_card_number() {
+        let digits = Alphabet::new(DIGITS).expect("alphabet is valid");
+        let number = "4111111111111111";
+
+        let encrypted = encrypt_str(key(), b"merchant", &digits, number).expect("input is valid");
+        assert_eq!(encrypted.len(), 16);
+        assert!(encrypted.chars().all(|c| c.is_ascii_digit()));
+        assert_ne!(encrypted, number);
+
+        // a different tweak gives a different ciphertext
+        assert_ne!(
+            encrypt_str(key(), b"other", &digits, number).expect("input is valid"),
+            encrypted
+        );
+
+        assert_eq!(
+            decrypt_str(key(), b"merchant", &digits, &encrypted).expect("input is valid"),
+            number
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        for radix in [2, 10, 26, 255, 256, 1000, 0x1_0000] {
+            for len in [20, 21, 64, 101] {
+                let plaintext: Vec<u16> = (0..len)
+                    .map(|i: u32| {
+                        u16::try_from(i.wrapping_mul(7919) % radix).expect("value is below radix")
+                    })
+                    .collect();
+                let encrypted =
+                    encrypt(key(), b"tweak", radix, &plaintext).expect("input is valid");
+                assert_eq!(encrypted.len(), plaintext.len());
+                assert_eq!(
+                    decrypt(key(), b"tweak", radix, &encrypted).expect("input is valid"),
+                    plaintext
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let digits = Alphabet::new(DIGITS).expect("alphabet is valid");
+        encrypt_str(key(), b"", &digits, "12345").expect_err("input is invalid");
+        encrypt_str(key(), b"", &digits, "12345a").expect_err("input is invalid");
+        encrypt(key(), b"", 10, &[1, 2, 3, 4, 5, 10]).expect_err("input is invalid");
+    }
+}
@@ -0,0 +1,135 @@
+//! Format-preserving encryption (FPE)
+//!
+//! FPE encrypts a string of numerals in some radix into another string of numerals of
+//! the same length and radix, e.g. a 16 digit credit card number into another 16 digit
+//! number. Both [`ff1`] and [`ff3_1`] are Feistel networks using AES as round function.
+//!
+//! <https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-38Gr1-draft.pdf>
+//!
+//! The functions operate on numerals, i.e. values in `0..radix`. [`Alphabet`] maps
+//! strings of characters to numerals and back.
+
+use std::ops::RangeInclusive;
+
+use crate::Error;
+
+pub mod ff1;
+pub mod ff3_1;
+mod num;
+
+/// Permitted values for the radix
+pub const RADIX_RANGE: RangeInclusive<u32> = 2..=0x1_0000;
+
+/// The minimum number of possible inputs, i.e. `radix^len`. Smaller domains can be
+/// enumerated by an attacker.
+const MIN_DOMAIN_SIZE: u32 = 1_000_000;
+
+/// The decimal digits, e.g. for credit card numbers
+pub const DIGITS: &str = "0123456789";
+
+/// Checks the radix, the numerals and the domain size
+fn validate(radix: u32, input: &[u16]) -> Result<(), Error> {
+    if !RADIX_RANGE.contains(&radix) {
+        return Err(Error(format!("invalid radix {radix}")));
+    }
+
+    if input.iter().any(|numeral| u32::from(*numeral) >= radix) {
+        return Err(Error(format!("numeral out of range for radix {radix}")));
+    }
+
+    let len = u32::try_from(input.len()).map_err(|_e| Error("input too long".to_owned()))?;
+    if len < 2
+        || radix
+            .checked_pow(len)
+            .is_some_and(|size| size < MIN_DOMAIN_SIZE)
+    {
+        return Err(Error(format!(
+            "input too short, the domain size needs to be at least {MIN_DOMAIN_SIZE}"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Maps characters to numerals and back. The radix is the number of characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alphabet(Vec<char>);
+
+impl Alphabet {
+    pub fn new(characters: &str) -> Result<Self, Error> {
+        let characters: Vec<char> = characters.chars().collect();
+
+        for (i, c) in characters.iter().enumerate() {
+            if characters[..i].contains(c) {
+                return Err(Error(format!("duplicate character {c} in alphabet")));
+            }
+        }
+
+        let radix =
+            u32::try_from(characters.len()).map_err(|_e| Error("alphabet too large".to_owned()))?;
+        if !RADIX_RANGE.contains(&radix) {
+            return Err(Error(format!("invalid alphabet size {radix}")));
+        }
+
+        Ok(Self(characters))
+    }
+
+    pub fn radix(&self) -> u32 {
+        u32::try_from(self.0.len()).expect("size is checked on construction")
+    }
+
+    pub fn to_numerals(&self, input: &str) -> Result<Vec<u16>, Error> {
+        input
+            .chars()
+            .map(|c| {
+                self.0
+                    .iter()
+                    .position(|a| *a == c)
+                    .map(|position| u16::try_from(position).expect("radix is at most 2^16"))
+                    .ok_or_else(|| Error(format!("character {c} not in alphabet")))
+            })
+            .collect()
+    }
+
+    pub fn from_numerals(&self, numerals: &[u16]) -> String {
+        numerals
+            .iter()
+            .map(|numeral| {
+                *self
+                    .0
+                    .get(usize::from(*numeral))
+                    .expect("numeral is smaller than the radix")
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alphabet() {
+        let alphabet = Alphabet::new("abc").expect("alphabet is valid");
+        assert_eq!(alphabet.radix(), 3);
+        assert_eq!(
+            alphabet.to_numerals("cab").expect("input is valid"),
+            [2, 0, 1]
+        );
+        assert_eq!(alphabet.from_numerals(&[2, 0, 1]), "cab");
+
+        alphabet.to_numerals("abd").expect_err("input is invalid");
+        Alphabet::new("aba").expect_err("input is invalid");
+        Alphabet::new("a").expect_err("input is invalid");
+    }
+
+    #[test]
+    fn test_validate() {
+        validate(10, &[0; 6]).expect("input is valid");
+        validate(10, &[0; 5]).expect_err("input is invalid");
+        validate(1 << 16, &[0; 2]).expect("input is valid");
+        validate(1 << 16, &[0; 1]).expect_err("input is invalid");
+        validate(10, &[0, 0, 0, 0, 0, 10]).expect_err("input is invalid");
+        validate(1, &[0; 100]).expect_err("input is invalid");
+    }
+}
@@ -0,0 +1,146 @@
+#![expect(
+    clippy::arithmetic_side_effects,
+    clippy::integer_division,
+    reason = "limb arithmetic is done in u64, where products of two limbs plus carry cannot overflow"
+)]
+//! Just enough arbitrary-precision arithmetic for FF1, whose intermediate values grow
+//! with the input length
+
+/// An unsigned integer, stored as little-endian 32 bit limbs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct BigUint(Vec<u32>);
+
+fn low(value: u64) -> u32 {
+    u32::try_from(value & u64::from(u32::MAX)).expect("value is masked to 32 bits")
+}
+
+fn high(value: u64) -> u32 {
+    u32::try_from(value >> 32_u8).expect("value is shifted to 32 bits")
+}
+
+impl BigUint {
+    /// `self * factor + summand`
+    fn mul_add(&mut self, factor: u32, summand: u32) {
+        let mut carry = u64::from(summand);
+        for limb in &mut self.0 {
+            let value = u64::from(*limb) * u64::from(factor) + carry;
+            *limb = low(value);
+            carry = u64::from(high(value));
+        }
+        if carry != 0 {
+            self.0.push(low(carry));
+        }
+    }
+
+    /// Divides by `divisor` in place and returns the remainder
+    fn div_rem(&mut self, divisor: u32) -> u32 {
+        let divisor = u64::from(divisor);
+        let mut remainder = 0_u64;
+        for limb in self.0.iter_mut().rev() {
+            let value = (remainder << 32_u8) | u64::from(*limb);
+            *limb = low(value / divisor);
+            remainder = value % divisor;
+        }
+        low(remainder)
+    }
+
+    /// `NUM_radix(X)`: interprets the numerals as a number, most significant first
+    pub(super) fn from_numerals(numerals: &[u16], radix: u32) -> Self {
+        let mut value = Self(Vec::new());
+        for numeral in numerals {
+            value.mul_add(radix, u32::from(*numeral));
+        }
+        value
+    }
+
+    /// `NUM(X)`: interprets the bytes as a big-endian number
+    pub(super) fn from_be_bytes(bytes: &[u8]) -> Self {
+        let mut value = Self(Vec::new());
+        for byte in bytes {
+            value.mul_add(256, u32::from(*byte));
+        }
+        value
+    }
+
+    /// `STR^m_radix(X mod radix^m)`: the lowest `m` numerals, most significant first
+    pub(super) fn into_numerals(mut self, radix: u32, m: usize) -> Vec<u16> {
+        let mut numerals: Vec<u16> = (0..m)
+            .map(|_| u16::try_from(self.div_rem(radix)).expect("radix is at most 2^16"))
+            .collect();
+        numerals.reverse();
+        numerals
+    }
+
+    /// `[X]^len`: the big-endian representation in exactly `len` bytes
+    pub(super) fn to_be_bytes(&self, len: usize) -> Vec<u8> {
+        let bytes: Vec<u8> = self
+            .0
+            .iter()
+            .rev()
+            .flat_map(|limb| limb.to_be_bytes())
+            .collect();
+
+        let leading = bytes.len().saturating_sub(len);
+        assert!(
+            bytes[..leading].iter().all(|b| *b == 0),
+            "value does not fit into {len} bytes"
+        );
+
+        let mut output = vec![0; len.saturating_sub(bytes.len())];
+        output.extend_from_slice(&bytes[leading..]);
+        output
+    }
+
+    pub(super) fn bit_length(&self) -> usize {
+        self.0.iter().rposition(|limb| *limb != 0).map_or(0, |i| {
+            i * 32 + usize::try_from(32 - self.0[i].leading_zeros()).expect("u32 fits into usize")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numerals() {
+        let value = BigUint::from_numerals(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 0], 10);
+        assert_eq!(value.to_be_bytes(4), 1_234_567_890_u32.to_be_bytes());
+        assert_eq!(
+            value.into_numerals(10, 12),
+            [0, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0]
+        );
+
+        let value = BigUint::from_numerals(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 0], 10);
+        assert_eq!(value.into_numerals(10, 3), [8, 9, 0]);
+    }
+
+    #[test]
+    fn test_bytes() {
+        let bytes: Vec<u8> = (1..=20).collect();
+        let value = BigUint::from_be_bytes(&bytes);
+        assert_eq!(value.to_be_bytes(20), bytes);
+        assert_eq!(value.to_be_bytes(23)[3..], bytes);
+        assert_eq!(value.bit_length(), 153);
+
+        assert_eq!(BigUint::from_be_bytes(&[0, 0, 1]).to_be_bytes(1), [1]);
+        assert_eq!(BigUint::from_be_bytes(&[]).bit_length(), 0);
+    }
+
+    #[test]
+    fn test_large_radix() {
+        let numerals = [0xffff, 0, 1, 0xfffe, 12345];
+        let value = BigUint::from_numerals(&numerals, 1 << 16);
+        assert_eq!(
+            value.to_be_bytes(10),
+            [0xff, 0xff, 0x00, 0x00, 0x00, 0x01, 0xff, 0xfe, 0x30, 0x39]
+        );
+        assert_eq!(value.into_numerals(1 << 16, 5), numerals);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not fit")]
+    fn test_too_large() {
+        BigUint::from_be_bytes(&[1, 0]).to_be_bytes(1);
+    }
+}
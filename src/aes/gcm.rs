@@ -0,0 +1,324 @@
+//! Galois/Counter Mode (GCM)
+//!
+//! GCM combines counter mode encryption with GHASH, a polynomial MAC over GF(2¹²⁸)
+//! (see [`super::gf128`]).
+//!
+//! <https://csrc.nist.rip/groups/ST/toolkit/BCM/documents/proposedmodes/gcm/gcm-spec.pdf>
+//! <https://nvlpubs.nist.gov/nistpubs/Legacy/SP/nistspecialpublication800-38d.pdf>
+//!
+//! The output of [`seal()`] is the ciphertext with the tag appended, which is also
+//! what [`open()`] expects.
+
+use super::{cipher, constant_time_eq, gf128, key::RoundKeys128, AuthenticationError, Key128};
+
+/// Tag lengths (in bytes) permitted by SP 800-38D. 32 and 64 bit tags are only
+/// meant for special applications, but are supported nevertheless.
+pub const TAG_LENGTHS: [usize; 7] = [4, 8, 12, 13, 14, 15, 16];
+
+/// Increments the rightmost 32 bits of the block, modulo 2³²
+fn inc32(block: [u8; 16]) -> [u8; 16] {
+    let mut block = block;
+    let counter = u32::from_be_bytes(
+        block[12..16]
+            .try_into()
+            .expect("slice is exactly 4 bytes long"),
+    );
+    block[12..16].copy_from_slice(&counter.wrapping_add(1).to_be_bytes());
+    block
+}
+
+/// Length of the input in bits, as required for the length block of GHASH
+fn bit_length(input: &[u8]) -> u128 {
+    u128::from(
+        u64::try_from(input.len())
+            .ok()
+            .and_then(|len| len.checked_mul(8))
+            .expect("input too large for GCM"),
+    )
+}
+
+/// Feeds `input` into the running GHASH value `y`. If the input is not a multiple
+/// of the block size, it is padded with zeroes.
+fn ghash_update(h: u128, y: u128, input: &[u8]) -> u128 {
+    input.chunks(16).fold(y, |y, chunk| {
+        let mut block = [0; 16];
+        block[..chunk.len()].copy_from_slice(chunk);
+        gf128::mult(gf128::add(y, u128::from_be_bytes(block)), h)
+    })
+}
+
+fn ghash(h: u128, aad: &[u8], ciphertext: &[u8]) -> u128 {
+    let y = ghash_update(h, 0, aad);
+    let y = ghash_update(h, y, ciphertext);
+
+    let length_block = (bit_length(aad) << 64_u8) | bit_length(ciphertext);
+    gf128::mult(gf128::add(y, length_block), h)
+}
+
+/// Derives the initial counter block J₀ from the IV. 96 bit IVs are used directly,
+/// all others are hashed.
+fn pre_counter_block(h: u128, iv: &[u8]) -> [u8; 16] {
+    if iv.len() == 12 {
+        let mut block = [0; 16];
+        block[..12].copy_from_slice(iv);
+        block[15] = 1;
+        block
+    } else {
+        let y = ghash_update(h, 0, iv);
+        gf128::mult(gf128::add(y, bit_length(iv)), h).to_be_bytes()
+    }
+}
+
+/// Counter mode encryption, using [`inc32()`] to derive the counter blocks
+fn gctr(round_keys: &RoundKeys128, initial_counter_block: [u8; 16], input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len());
+    let mut counter_block = initial_counter_block;
+
+    for chunk in input.chunks(16) {
+        let keystream = cipher(counter_block, round_keys);
+        output.extend(chunk.iter().zip(keystream.iter()).map(|(c, k)| c ^ k));
+        counter_block = inc32(counter_block);
+    }
+
+    output
+}
+
+/// Computes the full 16 byte tag. Callers truncate it to the requested length.
+fn tag(
+    round_keys: &RoundKeys128,
+    h: u128,
+    pre_counter_block: [u8; 16],
+    aad: &[u8],
+    ciphertext: &[u8],
+) -> [u8; 16] {
+    let s = ghash(h, aad, ciphertext);
+    let encrypted_pre_counter_block = u128::from_be_bytes(cipher(pre_counter_block, round_keys));
+    gf128::add(s, encrypted_pre_counter_block).to_be_bytes()
+}
+
+/// Encrypts and authenticates `plaintext`, additionally authenticating `aad`.
+///
+/// Returns the ciphertext with a tag of `tag_len` bytes appended. `tag_len` has to be
+/// one of [`TAG_LENGTHS`], and the IV must not be empty.
+pub fn seal(key: Key128, iv: &[u8], aad: &[u8], plaintext: &[u8], tag_len: usize) -> Vec<u8> {
+    assert!(TAG_LENGTHS.contains(&tag_len), "invalid tag length");
+    assert!(!iv.is_empty(), "iv cannot be empty");
+
+    let round_keys = key.expand();
+    let h = u128::from_be_bytes(cipher([0; 16], &round_keys));
+
+    let pre_counter_block = pre_counter_block(h, iv);
+
+    let mut output = gctr(&round_keys, inc32(pre_counter_block), plaintext);
+    let tag = tag(&round_keys, h, pre_counter_block, aad, &output);
+    output.extend_from_slice(&tag[..tag_len]);
+
+    output
+}
+
+/// Verifies and decrypts the output of [`seal()`].
+///
+/// The tag is checked before any decryption takes place, so no plaintext is released
+/// for forged ciphertexts.
+pub fn open(
+    key: Key128,
+    iv: &[u8],
+    aad: &[u8],
+    ciphertext: &[u8],
+    tag_len: usize,
+) -> Result<Vec<u8>, AuthenticationError> {
+    assert!(TAG_LENGTHS.contains(&tag_len), "invalid tag length");
+    assert!(!iv.is_empty(), "iv cannot be empty");
+
+    let (ciphertext, received_tag) = ciphertext
+        .len()
+        .checked_sub(tag_len)
+        .map(|split| ciphertext.split_at(split))
+        .ok_or(AuthenticationError)?;
+
+    let round_keys = key.expand();
+    let h = u128::from_be_bytes(cipher([0; 16], &round_keys));
+
+    let pre_counter_block = pre_counter_block(h, iv);
+
+    let expected_tag = tag(&round_keys, h, pre_counter_block, aad, ciphertext);
+    if !constant_time_eq(&expected_tag[..tag_len], received_tag) {
+        return Err(AuthenticationError);
+    }
+
+    Ok(gctr(&round_keys, inc32(pre_counter_block), ciphertext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{aes::parse_key, hex::parse};
+
+    struct TestCase {
+        key: &'static str,
+        plaintext: &'static str,
+        aad: &'static str,
+        iv: &'static str,
+        ciphertext: &'static str,
+        tag: &'static str,
+    }
+
+    /// Test cases 1 to 6 from the GCM spec, i.e. all cases using a 128 bit key
+    const SPEC_TEST_CASES: [TestCase; 6] = [
+        TestCase {
+            key: "00000000000000000000000000000000",
+            plaintext: "",
+            aad: "",
+            iv: "000000000000000000000000",
+            ciphertext: "",
+            tag: "58e2fccefa7e3061367f1d57a4e7455a",
+        },
+        TestCase {
+            key: "00000000000000000000000000000000",
+            plaintext: "00000000000000000000000000000000",
+            aad: "",
+            iv: "000000000000000000000000",
+            ciphertext: "0388dace60b6a392f328c2b971b2fe78",
+            tag: "ab6e47d42cec13bdf53a67b21257bddf",
+        },
+        TestCase {
+            key: "feffe9928665731c6d6a8f9467308308",
+            plaintext: "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b391aafd255",
+            aad: "",
+            iv: "cafebabefacedbaddecaf888",
+            ciphertext: "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091473f5985",
+            tag: "4d5c2af327cd64a62cf35abd2ba6fab4",
+        },
+        TestCase {
+            key: "feffe9928665731c6d6a8f9467308308",
+            plaintext: "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+            aad: "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+            iv: "cafebabefacedbaddecaf888",
+            ciphertext: "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091",
+            tag: "5bc94fbc3221a5db94fae95ae7121a47",
+        },
+        TestCase {
+            key: "feffe9928665731c6d6a8f9467308308",
+            plaintext: "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+            aad: "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+            iv: "cafebabefacedbad",
+            ciphertext: "61353b4c2806934a777ff51fa22a4755699b2a714fcdc6f83766e5f97b6c742373806900e49f24b22b097544d4896b424989b5e1ebac0f07c23f4598",
+            tag: "3612d2e79e3b0785561be14aaca2fccb",
+        },
+        TestCase {
+            key: "feffe9928665731c6d6a8f9467308308",
+            plaintext: "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+            aad: "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+            iv: "9313225df88406e555909c5aff5269aa6a7a9538534f7da1e4c303d2a318a728c3c0c95156809539fcf0e2429a6b525416aedbf5a0de6a57a637b39b",
+            ciphertext: "8ce24998625615b603a033aca13fb894be9112a5c3a211a8ba262a3cca7e2ca701e4a9a4fba43c90ccdcb281d48c7c6fd62875d2aca417034c34aee5",
+            tag: "619cc5aefffe0bfa462af43c1699d050",
+        },
+    ];
+
+    #[test]
+    fn test_inc32() {
+        let mut block = [0xff; 16];
+        block[12] = 0x00;
+        let mut expected = [0xff; 16];
+        expected[12] = 0x01;
+        expected[13..16].copy_from_slice(&[0; 3]);
+
+        assert_eq!(inc32(block), expected);
+        assert_eq!(inc32([0xff; 16])[..12], [0xff; 12]);
+        assert_eq!(inc32([0xff; 16])[12..], [0; 4]);
+    }
+
+    #[test]
+    fn test_seal_from_spec() {
+        for case in SPEC_TEST_CASES {
+            let mut expected = parse(case.ciphertext);
+            expected.extend(parse(case.tag));
+
+            assert_eq!(
+                seal(
+                    parse_key(case.key),
+                    &parse(case.iv),
+                    &parse(case.aad),
+                    &parse(case.plaintext),
+                    16
+                ),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_open_from_spec() {
+        for case in SPEC_TEST_CASES {
+            let mut input = parse(case.ciphertext);
+            input.extend(parse(case.tag));
+
+            assert_eq!(
+                open(
+                    parse_key(case.key),
+                    &parse(case.iv),
+                    &parse(case.aad),
+                    &input,
+                    16
+                ),
+                Ok(parse(case.plaintext))
+            );
+        }
+    }
+
+    #[test]
+    fn test_truncated_tags() {
+        let case = &SPEC_TEST_CASES[3];
+        let full_tag = parse(case.tag);
+
+        for tag_len in TAG_LENGTHS {
+            let sealed = seal(
+                parse_key(case.key),
+                &parse(case.iv),
+                &parse(case.aad),
+                &parse(case.plaintext),
+                tag_len,
+            );
+
+            let (ciphertext, tag) = sealed.split_at(sealed.len() - tag_len);
+            assert_eq!(ciphertext, parse(case.ciphertext));
+            assert_eq!(tag, &full_tag[..tag_len]);
+
+            assert_eq!(
+                open(
+                    parse_key(case.key),
+                    &parse(case.iv),
+                    &parse(case.aad),
+                    &sealed,
+                    tag_len
+                ),
+                Ok(parse(case.plaintext))
+            );
+        }
+    }
+
+    #[test]
+    fn test_open_rejects_modifications() {
+        let case = &SPEC_TEST_CASES[3];
+        let mut sealed = parse(case.ciphertext);
+        sealed.extend(parse(case.tag));
+
+        let open =
+            |sealed: &[u8], aad: &[u8]| open(parse_key(case.key), &parse(case.iv), aad, sealed, 16);
+
+        let aad = parse(case.aad);
+        assert!(open(&sealed, &aad).is_ok(), "unmodified input must verify");
+
+        for i in [0, 20, sealed.len() - 16, sealed.len() - 1] {
+            let mut modified = sealed.clone();
+            modified[i] ^= 0x01;
+            assert_eq!(open(&modified, &aad), Err(AuthenticationError));
+        }
+
+        let mut modified_aad = aad.clone();
+        modified_aad[0] ^= 0x01;
+        assert_eq!(open(&sealed, &modified_aad), Err(AuthenticationError));
+
+        assert_eq!(open(&sealed[..15], &aad), Err(AuthenticationError));
+    }
+}
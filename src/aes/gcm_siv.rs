@@ -0,0 +1,272 @@
+#![expect(
+    clippy::little_endian_bytes,
+    reason = "POLYVAL and the counter of GCM-SIV are defined little-endian"
+)]
+//! AES-GCM-SIV
+//!
+//! GCM-SIV derives fresh keys from the key-generating key for each nonce, and uses the
+//! tag (computed with POLYVAL over the plaintext) as the initial counter block. Like
+//! [`super::siv`], reusing a nonce only reveals whether the same input was encrypted
+//! twice, while with [`super::gcm`], it reveals the XOR of the plaintexts and allows
+//! recovering the authentication key.
+//!
+//! <https://www.rfc-editor.org/rfc/rfc8452>
+//!
+//! The output of [`seal()`] is the ciphertext with the 16 byte tag appended.
+
+use super::{cipher, constant_time_eq, gf128, key::RoundKeys128, AuthenticationError, Key128};
+
+/// POLYVAL, the little-endian counterpart of GHASH.
+///
+/// Instead of implementing multiplication in the POLYVAL field, this uses the
+/// relationship to GHASH given in appendix A of the RFC: Reversing the byte order of
+/// all inputs and multiplying `H` by x yields the GHASH computation. Parsing a block
+/// little-endian is equivalent to reversing and parsing it big-endian.
+fn polyval(h: [u8; 16], input: &[u8]) -> [u8; 16] {
+    let h = gf128::mult_x(u128::from_le_bytes(h));
+
+    input
+        .chunks(16)
+        .fold(0, |y, chunk| {
+            let mut block = [0; 16];
+            block[..chunk.len()].copy_from_slice(chunk);
+            gf128::mult(gf128::add(y, u128::from_le_bytes(block)), h)
+        })
+        .to_le_bytes()
+}
+
+/// Derives the message authentication key and the message encryption key for
+/// the given nonce
+fn derive_keys(key_generating_key: &RoundKeys128, nonce: &[u8; 12]) -> ([u8; 16], Key128) {
+    let mut derived = [0; 32];
+
+    for (i, half) in (0_u32..).zip(derived.chunks_mut(8)) {
+        let mut block = [0; 16];
+        block[..4].copy_from_slice(&i.to_le_bytes());
+        block[4..].copy_from_slice(nonce);
+
+        half.copy_from_slice(&cipher(block, key_generating_key)[..8]);
+    }
+
+    let (authentication_key, encryption_key) = derived.split_at(16);
+    (
+        authentication_key
+            .try_into()
+            .expect("derived key material is 32 bytes"),
+        Key128::from_bytes(
+            encryption_key
+                .try_into()
+                .expect("derived key material is 32 bytes"),
+        ),
+    )
+}
+
+fn tag(
+    authentication_key: [u8; 16],
+    encryption_key: &RoundKeys128,
+    nonce: &[u8; 12],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> [u8; 16] {
+    fn bit_length(input: &[u8]) -> u64 {
+        u64::try_from(input.len())
+            .ok()
+            .and_then(|len| len.checked_mul(8))
+            .expect("input too large for GCM-SIV")
+    }
+
+    fn pad(output: &mut Vec<u8>) {
+        let padding = output.len().wrapping_neg() % 16;
+        output.extend(std::iter::repeat_n(0, padding));
+    }
+
+    let mut input = aad.to_vec();
+    pad(&mut input);
+    input.extend_from_slice(plaintext);
+    pad(&mut input);
+    input.extend_from_slice(&bit_length(aad).to_le_bytes());
+    input.extend_from_slice(&bit_length(plaintext).to_le_bytes());
+
+    let mut s = polyval(authentication_key, &input);
+    for (s, n) in s.iter_mut().zip(nonce.iter()) {
+        *s ^= n;
+    }
+    s[15] &= 0x7f;
+
+    cipher(s, encryption_key)
+}
+
+/// Counter mode, starting at the tag with the MSB set. Only the first 32 bits are
+/// used as a little-endian counter.
+fn ctr(encryption_key: &RoundKeys128, tag: [u8; 16], input: &[u8]) -> Vec<u8> {
+    let mut counter_block = tag;
+    counter_block[15] |= 0x80;
+
+    let mut output = Vec::with_capacity(input.len());
+    for chunk in input.chunks(16) {
+        let keystream = cipher(counter_block, encryption_key);
+        output.extend(chunk.iter().zip(keystream.iter()).map(|(c, k)| c ^ k));
+
+        let counter = u32::from_le_bytes(
+            counter_block[..4]
+                .try_into()
+                .expect("slice is exactly 4 bytes long"),
+        );
+        counter_block[..4].copy_from_slice(&counter.wrapping_add(1).to_le_bytes());
+    }
+
+    output
+}
+
+/// Encrypts and authenticates `plaintext`, additionally authenticating `aad`.
+pub fn seal(key: Key128, nonce: &[u8; 12], aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+    let (authentication_key, encryption_key) = derive_keys(&key.expand(), nonce);
+    let encryption_key = encryption_key.expand();
+
+    let tag = tag(authentication_key, &encryption_key, nonce, aad, plaintext);
+
+    let mut output = ctr(&encryption_key, tag, plaintext);
+    output.extend_from_slice(&tag);
+    output
+}
+
+/// Decrypts and verifies the output of [`seal()`].
+pub fn open(
+    key: Key128,
+    nonce: &[u8; 12],
+    aad: &[u8],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, AuthenticationError> {
+    let (ciphertext, received_tag) = ciphertext
+        .split_last_chunk::<16>()
+        .ok_or(AuthenticationError)?;
+
+    let (authentication_key, encryption_key) = derive_keys(&key.expand(), nonce);
+    let encryption_key = encryption_key.expand();
+
+    let plaintext = ctr(&encryption_key, *received_tag, ciphertext);
+    let expected_tag = tag(authentication_key, &encryption_key, nonce, aad, &plaintext);
+
+    if !constant_time_eq(&expected_tag, received_tag) {
+        return Err(AuthenticationError);
+    }
+
+    Ok(plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        aes::{gcm, parse_key},
+        hex::parse,
+        xor,
+    };
+
+    fn key() -> Key128 {
+        parse_key("01000000000000000000000000000000")
+    }
+
+    const NONCE: [u8; 12] = [0x03, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+    #[test]
+    /// taken from the worked example in appendix A of the RFC
+    fn test_polyval() {
+        let h = parse("25629347589242761d31f826ba4b757b")
+            .try_into()
+            .expect("test block has 16 bytes");
+        let input = parse("4f4f95668c83dfb6401762bb2d01a262d1a24ddd2721d006bbe45f20d3c9f362");
+
+        assert_eq!(
+            polyval(h, &input).to_vec(),
+            parse("f7a3b47b846119fae5b7866cf5e5b77e")
+        );
+    }
+
+    #[test]
+    /// `AEAD_AES_128_GCM_SIV` vectors from appendix C.1 of the RFC
+    fn test_seal_and_open_from_rfc() {
+        for (aad, plaintext, output) in [
+            ("", "", "dc20e2d83f25705bb49e439eca56de25"),
+            (
+                "",
+                "0100000000000000",
+                "b5d839330ac7b786578782fff6013b815b287c22493a364c",
+            ),
+            (
+                "",
+                "010000000000000000000000",
+                "7323ea61d05932260047d942a4978db357391a0bc4fdec8b0d106639",
+            ),
+            (
+                "",
+                "01000000000000000000000000000000",
+                "743f7c8077ab25f8624e2e948579cf77303aaf90f6fe21199c6068577437a0c4",
+            ),
+            (
+                "01",
+                "0200000000000000",
+                "1e6daba35669f4273b0a1a2560969cdf790d99759abd1508",
+            ),
+        ] {
+            assert_eq!(
+                seal(key(), &NONCE, &parse(aad), &parse(plaintext)),
+                parse(output)
+            );
+            assert_eq!(
+                open(key(), &NONCE, &parse(aad), &parse(output)),
+                Ok(parse(plaintext))
+            );
+        }
+    }
+
+    #[test]
+    fn test_open_rejects_modifications() {
+        let output = parse("1e6daba35669f4273b0a1a2560969cdf790d99759abd1508");
+
+        for i in 0..output.len() {
+            let mut modified = output.clone();
+            modified[i] ^= 0x01;
+            assert_eq!(
+                open(key(), &NONCE, &[0x01], &modified),
+                Err(AuthenticationError)
+            );
+        }
+
+        assert_eq!(
+            open(key(), &NONCE, &[0x02], &output),
+            Err(AuthenticationError)
+        );
+        assert_eq!(
+            open(key(), &NONCE, &[0x01], &output[..15]),
+            Err(AuthenticationError)
+        );
+    }
+
+    #[test]
+    /// Demonstrates the difference to GCM when a nonce is reused
+    fn test_nonce_reuse() {
+        let p1 = b"attack at dawn!!";
+        let p2 = b"retreat at dusk!";
+
+        // With GCM, the keystream repeats, so the XOR of the ciphertexts is the XOR
+        // of the plaintexts
+        let c1 = gcm::seal(key(), &NONCE, &[], p1, 16);
+        let c2 = gcm::seal(key(), &NONCE, &[], p2, 16);
+        assert_eq!(
+            xor::xor_matching(&c1[..16], &c2[..16]),
+            xor::xor_matching(p1, p2)
+        );
+
+        // With GCM-SIV, the keystream depends on the plaintext
+        let c1 = seal(key(), &NONCE, &[], p1);
+        let c2 = seal(key(), &NONCE, &[], p2);
+        assert_ne!(
+            xor::xor_matching(&c1[..16], &c2[..16]),
+            xor::xor_matching(p1, p2)
+        );
+
+        // Only encrypting the same input twice can be detected
+        assert_eq!(seal(key(), &NONCE, &[], p1), c1);
+    }
+}
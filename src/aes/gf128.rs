@@ -0,0 +1,118 @@
+//! Operations in the galois field GF(2¹²⁸)
+//!
+//! GCM uses the following irreducible polynomial for multiplication:
+//!
+//! `x¹²⁸ + x⁷ + x² + x + 1`
+//!
+//! Elements are represented as `u128`, parsed big-endian from a 16 byte block. Note
+//! that GCM uses a "reflected" bit order: The MSB of the first byte is the coefficient
+//! of x⁰, and the LSB of the last byte is the coefficient of x¹²⁷. Multiplying by x is
+//! therefore a *right* shift.
+//!
+//! <https://nvlpubs.nist.gov/nistpubs/Legacy/SP/nistspecialpublication800-38d.pdf>
+//!
+//! Other modes (CMAC, SIV) use the same polynomial, but with the natural bit order,
+//! see [`dbl()`].
+
+/// The reduction constant `R = 11100001 || 0¹²⁰` from the spec. It is the
+/// irreducible polynomial without the x¹²⁸ term, in reflected bit order.
+const R: u128 = 0xe1 << 120_u8;
+
+/// Addition is defined as addition of the polynomial's coefficients modulo 2.
+///
+/// This is equivalent to a simple XOR.
+pub fn add(a: u128, b: u128) -> u128 {
+    a ^ b
+}
+
+/// Multiplication, following algorithm 1 of the GCM spec.
+///
+/// This works like [`super::gf::mult()`]: For each non-zero term in `x`, the
+/// corresponding multiple of `y` (i.e. `y` times a power of x) is added to the result.
+///
+/// As the result is used to authenticate secret data, there are no branches that
+/// depend on the operands. Instead, the bits are expanded into all-zero or all-one
+/// masks.
+pub fn mult(x: u128, y: u128) -> u128 {
+    let mut result: u128 = 0;
+    let mut v = y;
+
+    // Start with the MSB, which is the x⁰ term
+    for i in (0..128_u32).rev() {
+        let x_bit = (x >> i) & 1;
+        result = add(result, v & 0_u128.wrapping_sub(x_bit));
+
+        // Multiplying by x shifts out the x¹²⁷ term, which needs to be reduced
+        v = mult_x(v);
+    }
+
+    result
+}
+
+/// Multiplication by x, i.e. [`mult()`] with `y` set to `0x4000…0000`
+pub fn mult_x(v: u128) -> u128 {
+    let overflow = v & 1;
+    add(v >> 1_u8, R & 0_u128.wrapping_sub(overflow))
+}
+
+/// Doubling as used by CMAC and SIV (called `dbl` there)
+///
+/// This is multiplication by x in the natural bit order: The MSB is the coefficient of
+/// x¹²⁷, so a *left* shift multiplies by x, and the reduction constant is `0x87`.
+pub fn dbl(v: u128) -> u128 {
+    let overflow = v >> 127_u8;
+    add(v << 1_u8, 0x87 & 0_u128.wrapping_sub(overflow))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The multiplicative identity, i.e. the polynomial `1`
+    const ONE: u128 = 1 << 127;
+
+    #[test]
+    fn test_mult_identity() {
+        let a = 0x66e9_4bd4_ef8a_2c3b_884c_fa59_ca34_2b2e;
+
+        assert_eq!(mult(a, ONE), a);
+        assert_eq!(mult(ONE, a), a);
+        assert_eq!(mult(a, 0), 0);
+    }
+
+    #[test]
+    fn test_mult_commutative() {
+        let a = 0x66e9_4bd4_ef8a_2c3b_884c_fa59_ca34_2b2e;
+        let b = 0x0388_dace_60b6_a392_f328_c2b9_71b2_fe78;
+
+        assert_eq!(mult(a, b), mult(b, a));
+    }
+
+    #[test]
+    /// taken from test case 2 of the GCM spec
+    fn test_mult_from_spec() {
+        let h = 0x66e9_4bd4_ef8a_2c3b_884c_fa59_ca34_2b2e;
+        let c = 0x0388_dace_60b6_a392_f328_c2b9_71b2_fe78;
+
+        assert_eq!(mult(c, h), 0x5e2e_c746_9170_6288_2c85_b068_5353_deb7);
+    }
+
+    #[test]
+    fn test_mult_x() {
+        let a = 0x66e9_4bd4_ef8a_2c3b_884c_fa59_ca34_2b2e;
+
+        assert_eq!(mult_x(a), mult(a, ONE >> 1_u8));
+        assert_eq!(mult_x(1), R);
+    }
+
+    #[test]
+    /// taken from the subkey generation example of RFC 4493
+    fn test_dbl() {
+        let l = 0x7df7_6b0c_1ab8_99b3_3e42_f047_b91b_546f;
+        let k1 = 0xfbee_d618_3571_3366_7c85_e08f_7236_a8de;
+        let k2 = 0xf7dd_ac30_6ae2_66cc_f90b_c11e_e46d_513b;
+
+        assert_eq!(dbl(l), k1);
+        assert_eq!(dbl(k1), k2);
+    }
+}
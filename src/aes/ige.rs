@@ -0,0 +1,115 @@
+//! Infinite Garble Extension (IGE)
+//!
+//! Each ciphertext block depends on both the previous ciphertext block and the
+//! previous plaintext block: `C_i = E(P_i ⊕ C_{i-1}) ⊕ P_{i-1}`. This is used by
+//! Telegram's `MTProto`.
+//!
+//! <https://www.links.org/files/openssl-ige.pdf>
+//! <https://core.telegram.org/mtproto/description>
+//!
+//! The IV consists of two blocks, like in OpenSSL: the first one takes the place of
+//! `C_0`, the second one of `P_0`. The input needs to be block-aligned.
+
+use super::{cbc::xor_block, cipher, inv_cipher, Key128};
+
+fn split_iv(iv: [u8; 32]) -> ([u8; 16], [u8; 16]) {
+    let (c, p) = iv.split_at(16);
+    (
+        c.try_into().expect("split at 16 bytes"),
+        p.try_into().expect("split at 16 bytes"),
+    )
+}
+
+/// Encrypts block-aligned `plaintext`.
+pub fn encrypt(plaintext: &[u8], key: Key128, iv: [u8; 32]) -> Vec<u8> {
+    let round_keys = key.expand();
+    let (mut previous_ciphertext, mut previous_plaintext) = split_iv(iv);
+
+    let mut output = Vec::with_capacity(plaintext.len());
+
+    for chunk in plaintext.chunks(16) {
+        let chunk = chunk
+            .try_into()
+            .expect("input length needs to be a multiple of 16");
+        previous_ciphertext = xor_block(
+            cipher(xor_block(chunk, previous_ciphertext), &round_keys),
+            previous_plaintext,
+        );
+        previous_plaintext = chunk;
+        output.extend_from_slice(&previous_ciphertext);
+    }
+
+    output
+}
+
+/// Decrypts block-aligned `ciphertext`.
+pub fn decrypt(ciphertext: &[u8], key: Key128, iv: [u8; 32]) -> Vec<u8> {
+    let round_keys = key.expand();
+    let (mut previous_ciphertext, mut previous_plaintext) = split_iv(iv);
+
+    let mut output = Vec::with_capacity(ciphertext.len());
+
+    for chunk in ciphertext.chunks(16) {
+        let chunk = chunk
+            .try_into()
+            .expect("input length needs to be a multiple of 16");
+        previous_plaintext = xor_block(
+            inv_cipher(xor_block(chunk, previous_plaintext), &round_keys),
+            previous_ciphertext,
+        );
+        previous_ciphertext = chunk;
+        output.extend_from_slice(&previous_plaintext);
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{aes::parse_key, hex::parse};
+
+    #[test]
+    /// the test vectors from the IGE paper, also used in OpenSSL's test suite
+    fn test_ige_from_paper() {
+        for (key, iv, plaintext, ciphertext) in [
+            (
+                "000102030405060708090a0b0c0d0e0f",
+                "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "1a8519a6557be652e9da8e43da4ef4453cf456b4ca488aa383c79c98b34797cb",
+            ),
+            (
+                "5468697320697320616e20696d706c65",
+                "6d656e746174696f6e206f6620494745206d6f646520666f72204f70656e5353",
+                "99706487a1cde613bc6de0b6f24b1c7aa448c8b9c3403e3467a8cad89340f53b",
+                "4c2e204c6574277320686f70652042656e20676f74206974207269676874210a",
+            ),
+        ] {
+            let key = || parse_key(key);
+            let iv = parse(iv).try_into().expect("test iv has 32 bytes");
+            let plaintext = parse(plaintext);
+            let ciphertext = parse(ciphertext);
+
+            assert_eq!(encrypt(&plaintext, key(), iv), ciphertext);
+            assert_eq!(decrypt(&ciphertext, key(), iv), plaintext);
+        }
+    }
+
+    #[test]
+    fn test_errors_propagate() {
+        // Unlike CBC, a modified ciphertext block garbles all following blocks
+        let key = || Key128::from_bytes(*b"YELLOW SUBMARINE");
+        let iv = [0x42; 32];
+        let plaintext = [0; 64];
+
+        let mut ciphertext = encrypt(&plaintext, key(), iv);
+        ciphertext[20] ^= 0x01;
+        let decrypted = decrypt(&ciphertext, key(), iv);
+
+        assert_eq!(decrypted[..16], plaintext[..16]);
+        for block in decrypted[16..].chunks(16) {
+            assert_ne!(block, &plaintext[..16]);
+        }
+    }
+}
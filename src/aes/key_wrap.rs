@@ -0,0 +1,336 @@
+//! AES key wrap, with and without padding
+//!
+//! Key wrapping encrypts key material with a key-encryption key (KEK). An integrity
+//! check value is embedded into the output, so unwrapping with the wrong KEK or
+//! unwrapping modified data is detected.
+//!
+//! <https://www.rfc-editor.org/rfc/rfc3394>
+//! <https://www.rfc-editor.org/rfc/rfc5649>
+//! <https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-38F.pdf>
+
+use std::{error, fmt};
+
+use super::{cipher, inv_cipher, key::RoundKeys128, Key128};
+
+/// The default initial value from RFC 3394
+const DEFAULT_IV: [u8; 8] = [0xa6; 8];
+
+/// The constant part of the alternative initial value from RFC 5649. The remaining
+/// four bytes contain the length of the key data.
+const ALTERNATIVE_IV_PREFIX: [u8; 4] = [0xa6, 0x59, 0x59, 0xa6];
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum UnwrapError {
+    /// The wrapped data is too short or not a multiple of 8 bytes
+    InvalidLength,
+    /// The integrity check failed, i.e. the KEK is wrong or the data was modified
+    IntegrityCheckFailed,
+}
+
+impl error::Error for UnwrapError {}
+
+impl fmt::Display for UnwrapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::InvalidLength => write!(f, "invalid length of wrapped key"),
+            Self::IntegrityCheckFailed => write!(f, "integrity check of wrapped key failed"),
+        }
+    }
+}
+
+fn concat(a: [u8; 8], r: [u8; 8]) -> [u8; 16] {
+    let mut block = [0; 16];
+    block[..8].copy_from_slice(&a);
+    block[8..].copy_from_slice(&r);
+    block
+}
+
+fn split(block: [u8; 16]) -> ([u8; 8], [u8; 8]) {
+    let (a, r) = block.split_at(8);
+    (
+        a.try_into().expect("split at 8 bytes"),
+        r.try_into().expect("split at 8 bytes"),
+    )
+}
+
+fn xor_step(a: [u8; 8], t: u64) -> [u8; 8] {
+    (u64::from_be_bytes(a) ^ t).to_be_bytes()
+}
+
+fn to_semiblocks(input: &[u8]) -> Vec<[u8; 8]> {
+    input
+        .chunks(8)
+        .map(|chunk| chunk.try_into().expect("input is a multiple of 8 bytes"))
+        .collect()
+}
+
+/// The wrapping function W from SP 800-38F, in the index-based form of RFC 3394
+fn wrap_semiblocks(round_keys: &RoundKeys128, iv: [u8; 8], input: &[u8]) -> Vec<u8> {
+    let mut a = iv;
+    let mut r = to_semiblocks(input);
+
+    let n = u64::try_from(r.len()).expect("usize fits into u64");
+
+    for j in 0..6_u64 {
+        for (i, r) in (1_u64..).zip(r.iter_mut()) {
+            let (msb, lsb) = split(cipher(concat(a, *r), round_keys));
+
+            let t = n
+                .checked_mul(j)
+                .and_then(|t| t.checked_add(i))
+                .expect("input too large");
+            a = xor_step(msb, t);
+            *r = lsb;
+        }
+    }
+
+    let mut output = a.to_vec();
+    output.extend(r.iter().flatten());
+    output
+}
+
+/// The unwrapping function W⁻¹ from SP 800-38F. Returns the recovered initial value
+/// along with the key data, which have to be checked by the caller.
+fn unwrap_semiblocks(round_keys: &RoundKeys128, input: &[u8]) -> ([u8; 8], Vec<u8>) {
+    let mut r = to_semiblocks(input);
+    let mut a = r.remove(0);
+
+    let n = u64::try_from(r.len()).expect("usize fits into u64");
+
+    for j in (0..6_u64).rev() {
+        for (i, r) in r.iter_mut().enumerate().rev() {
+            let t = u64::try_from(i)
+                .ok()
+                .and_then(|i| n.checked_mul(j)?.checked_add(i)?.checked_add(1))
+                .expect("input too large");
+
+            let (msb, lsb) = split(inv_cipher(concat(xor_step(a, t), *r), round_keys));
+            a = msb;
+            *r = lsb;
+        }
+    }
+
+    (a, r.into_iter().flatten().collect())
+}
+
+/// Wraps `key_data` as specified in RFC 3394.
+///
+/// The key data has to be a multiple of 8 bytes, and at least 16 bytes long. For other
+/// lengths, use [`wrap_with_padding()`].
+pub fn wrap(kek: Key128, key_data: &[u8]) -> Vec<u8> {
+    assert!(key_data.len() >= 16, "key data must be at least 16 bytes");
+    assert!(
+        key_data.len().is_multiple_of(8),
+        "key data length needs to be a multiple of 8"
+    );
+
+    wrap_semiblocks(&kek.expand(), DEFAULT_IV, key_data)
+}
+
+/// Unwraps the output of [`wrap()`], verifying the integrity check value.
+pub fn unwrap(kek: Key128, wrapped: &[u8]) -> Result<Vec<u8>, UnwrapError> {
+    if wrapped.len() < 24 || !wrapped.len().is_multiple_of(8) {
+        return Err(UnwrapError::InvalidLength);
+    }
+
+    let (iv, key_data) = unwrap_semiblocks(&kek.expand(), wrapped);
+
+    if !super::constant_time_eq(&iv, &DEFAULT_IV) {
+        return Err(UnwrapError::IntegrityCheckFailed);
+    }
+
+    Ok(key_data)
+}
+
+/// Wraps `key_data` of any non-zero length as specified in RFC 5649.
+///
+/// The length of the key data is embedded into the initial value, and the key data is
+/// padded with zeroes to a multiple of 8 bytes.
+pub fn wrap_with_padding(kek: Key128, key_data: &[u8]) -> Vec<u8> {
+    assert!(!key_data.is_empty(), "key data cannot be empty");
+
+    let mut iv = [0; 8];
+    iv[..4].copy_from_slice(&ALTERNATIVE_IV_PREFIX);
+    iv[4..].copy_from_slice(
+        &u32::try_from(key_data.len())
+            .expect("key data too large")
+            .to_be_bytes(),
+    );
+
+    let mut padded = key_data.to_vec();
+    padded.resize(
+        key_data
+            .len()
+            .checked_next_multiple_of(8)
+            .expect("key data too large"),
+        0,
+    );
+
+    let round_keys = kek.expand();
+
+    // A single semiblock is encrypted together with the initial value as one block
+    if let Ok(semiblock) = <[u8; 8]>::try_from(padded.as_slice()) {
+        cipher(concat(iv, semiblock), &round_keys).to_vec()
+    } else {
+        wrap_semiblocks(&round_keys, iv, &padded)
+    }
+}
+
+/// Unwraps the output of [`wrap_with_padding()`], verifying the integrity check value,
+/// the embedded length and the padding.
+pub fn unwrap_with_padding(kek: Key128, wrapped: &[u8]) -> Result<Vec<u8>, UnwrapError> {
+    if wrapped.len() < 16 || !wrapped.len().is_multiple_of(8) {
+        return Err(UnwrapError::InvalidLength);
+    }
+
+    let round_keys = kek.expand();
+
+    let (iv, mut key_data) = if let Ok(block) = <[u8; 16]>::try_from(wrapped) {
+        let (iv, semiblock) = split(inv_cipher(block, &round_keys));
+        (iv, semiblock.to_vec())
+    } else {
+        unwrap_semiblocks(&round_keys, wrapped)
+    };
+
+    let (prefix, length) = iv.split_at(4);
+    let length = usize::try_from(u32::from_be_bytes(
+        length.try_into().expect("split at 4 bytes"),
+    ))
+    .expect("u32 fits into usize");
+
+    let padding_len = key_data.len().checked_sub(length);
+
+    let valid = super::constant_time_eq(prefix, &ALTERNATIVE_IV_PREFIX)
+        && padding_len.is_some_and(|padding_len| padding_len < 8)
+        && key_data[length.min(key_data.len())..]
+            .iter()
+            .all(|b| *b == 0);
+
+    if !valid {
+        return Err(UnwrapError::IntegrityCheckFailed);
+    }
+
+    key_data.truncate(length);
+    Ok(key_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{aes::parse_key, hex::parse};
+
+    fn kek() -> Key128 {
+        parse_key("000102030405060708090a0b0c0d0e0f")
+    }
+
+    /// Key data and wrapped output for a 128 bit KEK. The vectors in RFC 5649 all use
+    /// a 192 bit KEK, so these were generated with an independent implementation
+    /// (pyca/cryptography), reusing the key data of the RFC.
+    const PADDED_VECTORS: [(&str, &str); 3] = [
+        (
+            "c37b7e6492584340bed12207808941155068f738",
+            "e1f7176ecbd75d42e82b24f989a2816c209c6ef2d1aa94d2a3e60284900d03a2",
+        ),
+        ("466f7250617369", "be80535e12e9394c8f8df26bd9528a35"),
+        (
+            "00112233445566778899aabbccddeeff",
+            "2cef0c9e30de26016c230cb78bc60d51b1fe083ba0c79cd5",
+        ),
+    ];
+
+    #[test]
+    /// taken from section 4.1 of RFC 3394
+    fn test_wrap_from_rfc() {
+        let key_data = parse("00112233445566778899aabbccddeeff");
+        let wrapped = parse("1fa68b0a8112b447aef34bd8fb5a7b829d3e862371d2cfe5");
+
+        assert_eq!(wrap(kek(), &key_data), wrapped);
+        assert_eq!(unwrap(kek(), &wrapped), Ok(key_data));
+    }
+
+    #[test]
+    fn test_unwrap_rejects_modifications() {
+        let wrapped = parse("1fa68b0a8112b447aef34bd8fb5a7b829d3e862371d2cfe5");
+
+        for i in 0..wrapped.len() {
+            let mut modified = wrapped.clone();
+            modified[i] ^= 0x01;
+            assert_eq!(
+                unwrap(kek(), &modified),
+                Err(UnwrapError::IntegrityCheckFailed)
+            );
+        }
+
+        let wrong_kek = Key128::from_bytes([0; 16]);
+        assert_eq!(
+            unwrap(wrong_kek, &wrapped),
+            Err(UnwrapError::IntegrityCheckFailed)
+        );
+
+        assert_eq!(
+            unwrap(kek(), &wrapped[..16]),
+            Err(UnwrapError::InvalidLength)
+        );
+        assert_eq!(
+            unwrap(kek(), &wrapped[..23]),
+            Err(UnwrapError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_wrap_with_padding() {
+        for (key_data, wrapped) in PADDED_VECTORS {
+            assert_eq!(wrap_with_padding(kek(), &parse(key_data)), parse(wrapped));
+            assert_eq!(
+                unwrap_with_padding(kek(), &parse(wrapped)),
+                Ok(parse(key_data))
+            );
+        }
+    }
+
+    #[test]
+    fn test_unwrap_with_padding_rejects_modifications() {
+        for (_, wrapped) in PADDED_VECTORS {
+            let wrapped = parse(wrapped);
+
+            for i in 0..wrapped.len() {
+                let mut modified = wrapped.clone();
+                modified[i] ^= 0x01;
+                assert_eq!(
+                    unwrap_with_padding(kek(), &modified),
+                    Err(UnwrapError::IntegrityCheckFailed)
+                );
+            }
+        }
+
+        // regular key wrap output carries a different integrity check value
+        assert_eq!(
+            unwrap_with_padding(
+                kek(),
+                &parse("1fa68b0a8112b447aef34bd8fb5a7b829d3e862371d2cfe5")
+            ),
+            Err(UnwrapError::IntegrityCheckFailed)
+        );
+
+        assert_eq!(
+            unwrap_with_padding(kek(), &[0; 8]),
+            Err(UnwrapError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_padding_is_verified() {
+        // wrap 8 bytes of data, but claim that only 7 bytes are used. The trailing
+        // non-zero byte must then be rejected as invalid padding.
+        let round_keys = kek().expand();
+        let mut iv = [0; 8];
+        iv[..4].copy_from_slice(&ALTERNATIVE_IV_PREFIX);
+        iv[4..].copy_from_slice(&7_u32.to_be_bytes());
+        let wrapped = cipher(concat(iv, *b"01234567"), &round_keys);
+
+        assert_eq!(
+            unwrap_with_padding(kek(), &wrapped),
+            Err(UnwrapError::IntegrityCheckFailed)
+        );
+    }
+}
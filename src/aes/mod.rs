@@ -11,7 +11,29 @@
 //! <https://de.wikipedia.org/wiki/Advanced_Encryption_Standard>
 //! <https://www.cryptopals.com/sets/1/challenges/7>
 
-pub use key::Key128;
+use std::{error, fmt};
+
+pub use key::{AnyKey, Key128, Key192, Key256};
+
+pub mod cbc;
+pub mod cbc_mac;
+pub mod ccm;
+pub mod cmac;
+pub mod ctr;
+pub mod eax;
+pub mod fpe;
+pub mod gcm;
+pub mod gcm_siv;
+pub mod gf128;
+pub mod ige;
+pub mod key_wrap;
+pub mod ocb;
+pub mod openssl;
+pub mod parallel;
+pub mod pcbc;
+pub mod siv;
+pub mod stream;
+pub mod xts;
 
 struct Sbox([u8; 256]);
 
@@ -628,7 +650,7 @@ mod key {
 
             $(
                 #[cfg_attr(test, derive(Debug))]
-                #[derive(PartialEq, Eq)]
+                #[derive(Clone, PartialEq, Eq)]
                 pub(super) struct [<RoundKeys $size>]([RoundKey; const { match $size {
                     128_usize => 11_usize,
                     192_usize => 13_usize,
@@ -637,10 +659,6 @@ mod key {
                 }}]);
 
                 impl [<RoundKeys $size>] {
-                    pub(super) const fn len(&self) -> usize {
-                        self.0.len()
-                    }
-
                     #[cfg(test)]
                     pub(super) fn from_keys(value: [RoundKey; const { match $size {
                         128_usize => 11_usize,
@@ -660,12 +678,69 @@ mod key {
                         &self.0[index]
                     }
                 }
+
+                impl RoundKeys for [<RoundKeys $size>] {
+                    fn len(&self) -> usize {
+                        self.0.len()
+                    }
+                }
+
+                impl From<[<Key $size>]> for AnyKey {
+                    fn from(key: [<Key $size>]) -> Self {
+                        Self::[<Aes $size>](key)
+                    }
+                }
             )+
+
+            /// A key of any of the AES key sizes
+            #[cfg_attr(test, derive(Debug))]
+            #[derive(PartialEq, Eq)]
+            pub enum AnyKey {
+                $([<Aes $size>]([<Key $size>]),)+
+            }
+
+            impl AnyKey {
+                pub(super) fn expand(self) -> AnyRoundKeys {
+                    match self {
+                        $(Self::[<Aes $size>](key) => AnyRoundKeys::[<Aes $size>](key.expand()),)+
+                    }
+                }
+            }
+
+            #[derive(Clone)]
+            pub(super) enum AnyRoundKeys {
+                $([<Aes $size>]([<RoundKeys $size>]),)+
+            }
+
+            impl Index<usize> for AnyRoundKeys {
+                type Output = RoundKey;
+
+                fn index(&self, index: usize) -> &Self::Output {
+                    match *self {
+                        $(Self::[<Aes $size>](ref round_keys) => &round_keys[index],)+
+                    }
+                }
+            }
+
+            impl RoundKeys for AnyRoundKeys {
+                fn len(&self) -> usize {
+                    match *self {
+                        $(Self::[<Aes $size>](ref round_keys) => round_keys.len(),)+
+                    }
+                }
+            }
         }
     };
 }
 
-    impl_keys!(128);
+    /// The expanded key of any size, as used by [`super::cipher()`] and
+    /// [`super::inv_cipher()`]
+    pub(super) trait RoundKeys: Index<usize, Output = RoundKey> {
+        /// The number of round keys, which is one more than the number of rounds
+        fn len(&self) -> usize;
+    }
+
+    impl_keys!(128, 192, 256);
 
     #[cfg_attr(test, derive(Debug))]
     #[derive(Clone, Copy, PartialEq, Eq)]
@@ -725,6 +800,13 @@ mod key {
     }
 
     impl Key128 {
+        /// The key with its bytes in reverse order, as used by FF3-1
+        pub(super) fn reversed(self) -> Self {
+            let mut bytes = self.0;
+            bytes.reverse();
+            Self(bytes)
+        }
+
         pub(super) fn expand(self) -> RoundKeys128 {
             let mut rounds: [RoundKey; 11] = [const { RoundKey([0_u8; 16]) }; 11];
 
@@ -778,6 +860,64 @@ mod key {
         }
     }
 
+    impl Key192 {
+        pub(super) fn expand(self) -> RoundKeys192 {
+            RoundKeys192(expand_words(&self.0))
+        }
+    }
+
+    impl Key256 {
+        pub(super) fn expand(self) -> RoundKeys256 {
+            RoundKeys256(expand_words(&self.0))
+        }
+    }
+
+    /// The key expansion as specified in section 5.2 of the spec, which works on words
+    /// instead of whole round keys, so it applies to all key sizes
+    fn expand_words<const N: usize>(key: &[u8]) -> [RoundKey; N] {
+        let mut words: Vec<[u8; 4]> = key
+            .chunks_exact(4)
+            .map(|word| word.try_into().expect("chunks have 4 bytes"))
+            .collect();
+        let key_words = words.len();
+
+        while words.len() < N.saturating_mul(4) {
+            let i = words.len();
+            let mut word = *words.last().expect("key is not empty");
+
+            match i.checked_rem(key_words) {
+                Some(0) => {
+                    rot_word(&mut word);
+                    sub_word(&mut word);
+                    let round = i.checked_div(key_words).expect("key is not empty");
+                    rcon(
+                        &mut word,
+                        *ROUND_CONSTANTS
+                            .get(round.wrapping_sub(1))
+                            .expect("at most 10 round constants are needed"),
+                    );
+                }
+                // 256 bit keys get an additional substitution in the middle
+                Some(4) if key_words > 6 => sub_word(&mut word),
+                _ => (),
+            }
+
+            let previous = words
+                .get(i.wrapping_sub(key_words))
+                .expect("the key fills the first words");
+            words.push(gf::add_word(word, *previous));
+        }
+
+        let mut columns = words.into_iter().map(Column);
+        std::array::from_fn(|_| {
+            let mut round_key = RoundKey([0; 16]);
+            for index in 0..4 {
+                round_key.set_column(index, columns.next().expect("4 words per round key"));
+            }
+            round_key
+        })
+    }
+
     #[cfg(test)]
     mod tests {
         use super::*;
@@ -856,10 +996,153 @@ mod key {
                 ])
             );
         }
+
+        #[test]
+        /// taken from appendix A.2 of the spec
+        fn test_expand_192() {
+            let key = Key192::from_bytes([
+                0x8e, 0x73, 0xb0, 0xf7, 0xda, 0x0e, 0x64, 0x52, 0xc8, 0x10, 0xf3, 0x2b, 0x80, 0x90,
+                0x79, 0xe5, 0x62, 0xf8, 0xea, 0xd2, 0x52, 0x2c, 0x6b, 0x7b,
+            ]);
+
+            assert_eq!(
+                key.expand(),
+                RoundKeys192::from_keys([
+                    RoundKey([
+                        0x8e, 0x73, 0xb0, 0xf7, 0xda, 0x0e, 0x64, 0x52, 0xc8, 0x10, 0xf3, 0x2b,
+                        0x80, 0x90, 0x79, 0xe5,
+                    ]),
+                    RoundKey([
+                        0x62, 0xf8, 0xea, 0xd2, 0x52, 0x2c, 0x6b, 0x7b, 0xfe, 0x0c, 0x91, 0xf7,
+                        0x24, 0x02, 0xf5, 0xa5,
+                    ]),
+                    RoundKey([
+                        0xec, 0x12, 0x06, 0x8e, 0x6c, 0x82, 0x7f, 0x6b, 0x0e, 0x7a, 0x95, 0xb9,
+                        0x5c, 0x56, 0xfe, 0xc2,
+                    ]),
+                    RoundKey([
+                        0x4d, 0xb7, 0xb4, 0xbd, 0x69, 0xb5, 0x41, 0x18, 0x85, 0xa7, 0x47, 0x96,
+                        0xe9, 0x25, 0x38, 0xfd,
+                    ]),
+                    RoundKey([
+                        0xe7, 0x5f, 0xad, 0x44, 0xbb, 0x09, 0x53, 0x86, 0x48, 0x5a, 0xf0, 0x57,
+                        0x21, 0xef, 0xb1, 0x4f,
+                    ]),
+                    RoundKey([
+                        0xa4, 0x48, 0xf6, 0xd9, 0x4d, 0x6d, 0xce, 0x24, 0xaa, 0x32, 0x63, 0x60,
+                        0x11, 0x3b, 0x30, 0xe6,
+                    ]),
+                    RoundKey([
+                        0xa2, 0x5e, 0x7e, 0xd5, 0x83, 0xb1, 0xcf, 0x9a, 0x27, 0xf9, 0x39, 0x43,
+                        0x6a, 0x94, 0xf7, 0x67,
+                    ]),
+                    RoundKey([
+                        0xc0, 0xa6, 0x94, 0x07, 0xd1, 0x9d, 0xa4, 0xe1, 0xec, 0x17, 0x86, 0xeb,
+                        0x6f, 0xa6, 0x49, 0x71,
+                    ]),
+                    RoundKey([
+                        0x48, 0x5f, 0x70, 0x32, 0x22, 0xcb, 0x87, 0x55, 0xe2, 0x6d, 0x13, 0x52,
+                        0x33, 0xf0, 0xb7, 0xb3,
+                    ]),
+                    RoundKey([
+                        0x40, 0xbe, 0xeb, 0x28, 0x2f, 0x18, 0xa2, 0x59, 0x67, 0x47, 0xd2, 0x6b,
+                        0x45, 0x8c, 0x55, 0x3e,
+                    ]),
+                    RoundKey([
+                        0xa7, 0xe1, 0x46, 0x6c, 0x94, 0x11, 0xf1, 0xdf, 0x82, 0x1f, 0x75, 0x0a,
+                        0xad, 0x07, 0xd7, 0x53,
+                    ]),
+                    RoundKey([
+                        0xca, 0x40, 0x05, 0x38, 0x8f, 0xcc, 0x50, 0x06, 0x28, 0x2d, 0x16, 0x6a,
+                        0xbc, 0x3c, 0xe7, 0xb5,
+                    ]),
+                    RoundKey([
+                        0xe9, 0x8b, 0xa0, 0x6f, 0x44, 0x8c, 0x77, 0x3c, 0x8e, 0xcc, 0x72, 0x04,
+                        0x01, 0x00, 0x22, 0x02,
+                    ]),
+                ])
+            );
+        }
+
+        #[test]
+        /// taken from appendix A.3 of the spec
+        fn test_expand_256() {
+            let key = Key256::from_bytes([
+                0x60, 0x3d, 0xeb, 0x10, 0x15, 0xca, 0x71, 0xbe, 0x2b, 0x73, 0xae, 0xf0, 0x85, 0x7d,
+                0x77, 0x81, 0x1f, 0x35, 0x2c, 0x07, 0x3b, 0x61, 0x08, 0xd7, 0x2d, 0x98, 0x10, 0xa3,
+                0x09, 0x14, 0xdf, 0xf4,
+            ]);
+
+            assert_eq!(
+                key.expand(),
+                RoundKeys256::from_keys([
+                    RoundKey([
+                        0x60, 0x3d, 0xeb, 0x10, 0x15, 0xca, 0x71, 0xbe, 0x2b, 0x73, 0xae, 0xf0,
+                        0x85, 0x7d, 0x77, 0x81,
+                    ]),
+                    RoundKey([
+                        0x1f, 0x35, 0x2c, 0x07, 0x3b, 0x61, 0x08, 0xd7, 0x2d, 0x98, 0x10, 0xa3,
+                        0x09, 0x14, 0xdf, 0xf4,
+                    ]),
+                    RoundKey([
+                        0x9b, 0xa3, 0x54, 0x11, 0x8e, 0x69, 0x25, 0xaf, 0xa5, 0x1a, 0x8b, 0x5f,
+                        0x20, 0x67, 0xfc, 0xde,
+                    ]),
+                    RoundKey([
+                        0xa8, 0xb0, 0x9c, 0x1a, 0x93, 0xd1, 0x94, 0xcd, 0xbe, 0x49, 0x84, 0x6e,
+                        0xb7, 0x5d, 0x5b, 0x9a,
+                    ]),
+                    RoundKey([
+                        0xd5, 0x9a, 0xec, 0xb8, 0x5b, 0xf3, 0xc9, 0x17, 0xfe, 0xe9, 0x42, 0x48,
+                        0xde, 0x8e, 0xbe, 0x96,
+                    ]),
+                    RoundKey([
+                        0xb5, 0xa9, 0x32, 0x8a, 0x26, 0x78, 0xa6, 0x47, 0x98, 0x31, 0x22, 0x29,
+                        0x2f, 0x6c, 0x79, 0xb3,
+                    ]),
+                    RoundKey([
+                        0x81, 0x2c, 0x81, 0xad, 0xda, 0xdf, 0x48, 0xba, 0x24, 0x36, 0x0a, 0xf2,
+                        0xfa, 0xb8, 0xb4, 0x64,
+                    ]),
+                    RoundKey([
+                        0x98, 0xc5, 0xbf, 0xc9, 0xbe, 0xbd, 0x19, 0x8e, 0x26, 0x8c, 0x3b, 0xa7,
+                        0x09, 0xe0, 0x42, 0x14,
+                    ]),
+                    RoundKey([
+                        0x68, 0x00, 0x7b, 0xac, 0xb2, 0xdf, 0x33, 0x16, 0x96, 0xe9, 0x39, 0xe4,
+                        0x6c, 0x51, 0x8d, 0x80,
+                    ]),
+                    RoundKey([
+                        0xc8, 0x14, 0xe2, 0x04, 0x76, 0xa9, 0xfb, 0x8a, 0x50, 0x25, 0xc0, 0x2d,
+                        0x59, 0xc5, 0x82, 0x39,
+                    ]),
+                    RoundKey([
+                        0xde, 0x13, 0x69, 0x67, 0x6c, 0xcc, 0x5a, 0x71, 0xfa, 0x25, 0x63, 0x95,
+                        0x96, 0x74, 0xee, 0x15,
+                    ]),
+                    RoundKey([
+                        0x58, 0x86, 0xca, 0x5d, 0x2e, 0x2f, 0x31, 0xd7, 0x7e, 0x0a, 0xf1, 0xfa,
+                        0x27, 0xcf, 0x73, 0xc3,
+                    ]),
+                    RoundKey([
+                        0x74, 0x9c, 0x47, 0xab, 0x18, 0x50, 0x1d, 0xda, 0xe2, 0x75, 0x7e, 0x4f,
+                        0x74, 0x01, 0x90, 0x5a,
+                    ]),
+                    RoundKey([
+                        0xca, 0xfa, 0xaa, 0xe3, 0xe4, 0xd5, 0x9b, 0x34, 0x9a, 0xdf, 0x6a, 0xce,
+                        0xbd, 0x10, 0x19, 0x0d,
+                    ]),
+                    RoundKey([
+                        0xfe, 0x48, 0x90, 0xd1, 0xe6, 0x18, 0x8d, 0x0b, 0x04, 0x6d, 0xf3, 0x44,
+                        0x70, 0x6c, 0x63, 0x1e,
+                    ]),
+                ])
+            );
+        }
     }
 }
 
-fn cipher(input: [u8; 16], round_keys: &key::RoundKeys128) -> [u8; 16] {
+fn cipher<K: key::RoundKeys>(input: [u8; 16], round_keys: &K) -> [u8; 16] {
     let mut state = state::State::from_bytes(input);
 
     state.add_round_key(&round_keys[0]);
@@ -886,7 +1169,7 @@ fn cipher(input: [u8; 16], round_keys: &key::RoundKeys128) -> [u8; 16] {
     state.into_array()
 }
 
-fn inv_cipher(input: [u8; 16], round_keys: &key::RoundKeys128) -> [u8; 16] {
+fn inv_cipher<K: key::RoundKeys>(input: [u8; 16], round_keys: &K) -> [u8; 16] {
     let mut state = state::State::from_bytes(input);
 
     #[expect(
@@ -921,6 +1204,56 @@ pub fn decrypt_block(ciphertext: [u8; 16], key: Key128) -> [u8; 16] {
     inv_cipher(ciphertext, &key.expand())
 }
 
+/// Returned by authenticated modes when a ciphertext does not carry a valid tag.
+///
+/// It intentionally carries no further information, so as not to act as an oracle.
+#[derive(Debug, PartialEq, Eq)]
+pub struct AuthenticationError;
+
+impl error::Error for AuthenticationError {}
+
+impl fmt::Display for AuthenticationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "authentication failed")
+    }
+}
+
+/// Compares two byte slices in time depending only on their lengths, not on
+/// their contents. Used for tag verification.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b.iter()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Parses a hex test key
+#[cfg(test)]
+pub(crate) fn parse_key(input: &str) -> Key128 {
+    Key128::from_bytes(
+        crate::hex::parse(input)
+            .try_into()
+            .expect("test key has 16 bytes"),
+    )
+}
+
+pub fn encrypt_ecb(plaintext: &[u8], key: Key128) -> Vec<u8> {
+    let mut output = Vec::with_capacity(plaintext.len());
+
+    let round_keys = key.expand();
+
+    for chunk in plaintext.chunks(16) {
+        let chunk = chunk
+            .try_into()
+            .expect("input length needs to be a multiple of 16");
+        let encrypted = cipher(chunk, &round_keys);
+        output.extend_from_slice(&encrypted);
+    }
+
+    output
+}
+
 pub fn decrypt_ecb(ciphertext: &[u8], key: Key128) -> Vec<u8> {
     let mut output = Vec::with_capacity(ciphertext.len());
 
@@ -1004,6 +1337,53 @@ mod tests {
         assert_eq!(cipher(cleartext, &key.expand()), expected_ciphertext);
     }
 
+    #[test]
+    /// taken from appendix C.2 of the spec
+    fn test_cipher_192_from_spec() {
+        let cleartext: [u8; 16] = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ];
+
+        let round_keys = key::Key192::from_bytes([
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17,
+        ])
+        .expand();
+
+        let expected_ciphertext: [u8; 16] = [
+            0xdd, 0xa9, 0x7c, 0xa4, 0x86, 0x4c, 0xdf, 0xe0, 0x6e, 0xaf, 0x70, 0xa0, 0xec, 0x0d,
+            0x71, 0x91,
+        ];
+
+        assert_eq!(cipher(cleartext, &round_keys), expected_ciphertext);
+        assert_eq!(inv_cipher(expected_ciphertext, &round_keys), cleartext);
+    }
+
+    #[test]
+    /// taken from appendix C.3 of the spec
+    fn test_cipher_256_from_spec() {
+        let cleartext: [u8; 16] = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ];
+
+        let round_keys = key::Key256::from_bytes([
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b,
+            0x1c, 0x1d, 0x1e, 0x1f,
+        ])
+        .expand();
+
+        let expected_ciphertext: [u8; 16] = [
+            0x8e, 0xa2, 0xb7, 0xca, 0x51, 0x67, 0x45, 0xbf, 0xea, 0xfc, 0x49, 0x90, 0x4b, 0x49,
+            0x60, 0x89,
+        ];
+
+        assert_eq!(cipher(cleartext, &round_keys), expected_ciphertext);
+        assert_eq!(inv_cipher(expected_ciphertext, &round_keys), cleartext);
+    }
+
     #[test]
     fn test_encrypt_block() {
         let cleartext: [u8; 16] = "SUPER TOP SECRET".as_bytes().try_into().unwrap();
@@ -1027,4 +1407,23 @@ mod tests {
 
         assert_eq!(inv_cipher(ciphertext, &key.expand()), cleartext);
     }
+
+    #[test]
+    fn test_ecb() {
+        let plaintext = b"SUPER TOP SECRETSUPER TOP SECRET";
+        let ciphertext = [
+            0x4a, 0x5b, 0xe2, 0x51, 0x8e, 0x40, 0xa3, 0x7b, 0xdb, 0x4e, 0xb5, 0x2e, 0x83, 0xc1,
+            0x48, 0x05, 0x4a, 0x5b, 0xe2, 0x51, 0x8e, 0x40, 0xa3, 0x7b, 0xdb, 0x4e, 0xb5, 0x2e,
+            0x83, 0xc1, 0x48, 0x05,
+        ];
+
+        assert_eq!(
+            encrypt_ecb(plaintext, Key128::from_bytes(*b"YELLOW SUBMARINE")),
+            ciphertext
+        );
+        assert_eq!(
+            decrypt_ecb(&ciphertext, Key128::from_bytes(*b"YELLOW SUBMARINE")),
+            plaintext
+        );
+    }
 }
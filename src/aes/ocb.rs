@@ -0,0 +1,355 @@
+//! Offset Codebook Mode (OCB3)
+//!
+//! OCB encrypts each block with the block cipher, masked with an offset that changes
+//! from block to block. Authentication comes almost for free: The tag is the
+//! encryption of the XOR of all plaintext blocks, combined with a hash of the
+//! associated data.
+//!
+//! <https://www.rfc-editor.org/rfc/rfc7253>
+//!
+//! The output of [`seal()`] is the ciphertext with the tag appended.
+
+use std::ops::RangeInclusive;
+
+use super::{
+    cipher, constant_time_eq, gf128, inv_cipher, key::RoundKeys128, AuthenticationError, Key128,
+};
+
+/// Permitted tag lengths in bytes
+pub const TAG_LENGTHS: RangeInclusive<usize> = 1..=16;
+
+/// Permitted nonce lengths in bytes
+pub const NONCE_LENGTHS: RangeInclusive<usize> = 0..=15;
+
+#[derive(Clone, Copy)]
+enum Direction {
+    Encrypt,
+    Decrypt,
+}
+
+/// The expanded key together with the precomputed values `L_*`, `L_$` and `L_i`
+struct Context {
+    round_keys: RoundKeys128,
+    l_star: u128,
+    l_dollar: u128,
+    /// `L_i` for every possible number of trailing zeros of a block index, which is
+    /// at most 64 bits wide
+    l: [u128; 64],
+}
+
+impl Context {
+    fn new(key: Key128) -> Self {
+        let round_keys = key.expand();
+
+        let l_star = u128::from_be_bytes(cipher([0; 16], &round_keys));
+        let l_dollar = gf128::dbl(l_star);
+
+        let mut l = [0; 64];
+        let mut previous = l_dollar;
+        for l in &mut l {
+            *l = gf128::dbl(previous);
+            previous = *l;
+        }
+
+        Self {
+            round_keys,
+            l_star,
+            l_dollar,
+            l,
+        }
+    }
+
+    fn encipher(&self, input: u128) -> u128 {
+        u128::from_be_bytes(cipher(input.to_be_bytes(), &self.round_keys))
+    }
+
+    fn decipher(&self, input: u128) -> u128 {
+        u128::from_be_bytes(inv_cipher(input.to_be_bytes(), &self.round_keys))
+    }
+
+    /// `L_ntz(i)`, the value added to the offset for the block with index `i`
+    /// (starting at 1)
+    fn l_for_index(&self, i: usize) -> u128 {
+        self.l[usize::try_from(i.trailing_zeros()).expect("u32 fits into usize")]
+    }
+
+    /// The initial offset, derived from the nonce and the tag length
+    fn initial_offset(&self, nonce: &[u8], tag_len: usize) -> u128 {
+        assert!(NONCE_LENGTHS.contains(&nonce.len()), "invalid nonce length");
+
+        let mut block = [0; 16];
+        // the tag length in bits modulo 128 is encoded in the first 7 bits
+        block[0] = u8::try_from(tag_len.checked_mul(8).expect("tag length is small") % 128)
+            .expect("value is smaller than 128")
+            << 1_u8;
+        block[15_usize.saturating_sub(nonce.len())] |= 0x01;
+        block[16_usize.saturating_sub(nonce.len())..].copy_from_slice(nonce);
+
+        let bottom = u32::from(block[15] & 0x3f);
+        block[15] &= 0xc0;
+
+        let ktop = self.encipher(u128::from_be_bytes(block));
+
+        // Stretch = Ktop || (Ktop[1..64] xor Ktop[9..72]), of which 128 bits starting
+        // at bit `bottom` are used
+        let stretch = u128::from(
+            u64::try_from((ktop >> 64_u8) ^ ((ktop >> 56_u8) & u128::from(u64::MAX)))
+                .expect("value is masked to 64 bits"),
+        );
+
+        ktop.checked_shl(bottom).expect("bottom is smaller than 64")
+            | stretch
+                .checked_shr(64_u32.saturating_sub(bottom))
+                .expect("shift is at most 64")
+    }
+
+    /// `HASH(K, A)`
+    fn hash(&self, aad: &[u8]) -> u128 {
+        let mut sum = 0;
+        let mut offset = 0;
+
+        let mut chunks = aad.chunks_exact(16);
+        for (i, chunk) in (1_usize..).zip(&mut chunks) {
+            offset = gf128::add(offset, self.l_for_index(i));
+            let block = u128::from_be_bytes(chunk.try_into().expect("chunk is 16 bytes long"));
+            sum = gf128::add(sum, self.encipher(gf128::add(block, offset)));
+        }
+
+        let remainder = chunks.remainder();
+        if !remainder.is_empty() {
+            offset = gf128::add(offset, self.l_star);
+            sum = gf128::add(sum, self.encipher(gf128::add(pad_block(remainder), offset)));
+        }
+
+        sum
+    }
+
+    /// Encrypts or decrypts `input`, returning the output along with the full tag
+    fn process(
+        &self,
+        direction: Direction,
+        nonce: &[u8],
+        aad: &[u8],
+        input: &[u8],
+        tag_len: usize,
+    ) -> (Vec<u8>, [u8; 16]) {
+        let mut offset = self.initial_offset(nonce, tag_len);
+        let mut checksum = 0;
+
+        let mut output = Vec::with_capacity(input.len());
+
+        let mut chunks = input.chunks_exact(16);
+        for (i, chunk) in (1_usize..).zip(&mut chunks) {
+            offset = gf128::add(offset, self.l_for_index(i));
+            let block = u128::from_be_bytes(chunk.try_into().expect("chunk is 16 bytes long"));
+
+            let (plaintext, result) = match direction {
+                Direction::Encrypt => (
+                    block,
+                    gf128::add(offset, self.encipher(gf128::add(block, offset))),
+                ),
+                Direction::Decrypt => {
+                    let plaintext = gf128::add(offset, self.decipher(gf128::add(block, offset)));
+                    (plaintext, plaintext)
+                }
+            };
+
+            checksum = gf128::add(checksum, plaintext);
+            output.extend_from_slice(&result.to_be_bytes());
+        }
+
+        let remainder = chunks.remainder();
+        if !remainder.is_empty() {
+            offset = gf128::add(offset, self.l_star);
+            let pad = self.encipher(offset).to_be_bytes();
+
+            let start = output.len();
+            output.extend(remainder.iter().zip(pad.iter()).map(|(b, p)| b ^ p));
+
+            let plaintext = match direction {
+                Direction::Encrypt => remainder,
+                Direction::Decrypt => &output[start..],
+            };
+            checksum = gf128::add(checksum, pad_block(plaintext));
+        }
+
+        let tag = gf128::add(
+            self.encipher(gf128::add(gf128::add(checksum, offset), self.l_dollar)),
+            self.hash(aad),
+        );
+
+        (output, tag.to_be_bytes())
+    }
+}
+
+/// Pads an incomplete block with a single one bit, followed by zeroes
+fn pad_block(input: &[u8]) -> u128 {
+    let mut block = [0; 16];
+    block[..input.len()].copy_from_slice(input);
+    block[input.len()] = 0x80;
+    u128::from_be_bytes(block)
+}
+
+/// Encrypts and authenticates `plaintext`, additionally authenticating `aad`.
+pub fn seal(key: Key128, nonce: &[u8], aad: &[u8], plaintext: &[u8], tag_len: usize) -> Vec<u8> {
+    assert!(TAG_LENGTHS.contains(&tag_len), "invalid tag length");
+
+    let (mut output, tag) =
+        Context::new(key).process(Direction::Encrypt, nonce, aad, plaintext, tag_len);
+
+    output.extend_from_slice(&tag[..tag_len]);
+    output
+}
+
+/// Decrypts and verifies the output of [`seal()`].
+pub fn open(
+    key: Key128,
+    nonce: &[u8],
+    aad: &[u8],
+    ciphertext: &[u8],
+    tag_len: usize,
+) -> Result<Vec<u8>, AuthenticationError> {
+    assert!(TAG_LENGTHS.contains(&tag_len), "invalid tag length");
+
+    let (ciphertext, received_tag) = ciphertext
+        .len()
+        .checked_sub(tag_len)
+        .map(|split| ciphertext.split_at(split))
+        .ok_or(AuthenticationError)?;
+
+    let (plaintext, expected_tag) =
+        Context::new(key).process(Direction::Decrypt, nonce, aad, ciphertext, tag_len);
+
+    if !constant_time_eq(&expected_tag[..tag_len], received_tag) {
+        return Err(AuthenticationError);
+    }
+
+    Ok(plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{aes::parse_key, hex::parse};
+
+    const KEY: &str = "000102030405060708090a0b0c0d0e0f";
+
+    /// Nonce, associated data, plaintext and ciphertext of the sample results in
+    /// appendix A of the RFC
+    const RFC_VECTORS: [(&str, &str, &str, &str); 6] = [
+        (
+            "bbaa99887766554433221100",
+            "",
+            "",
+            "785407bfffc8ad9edcc5520ac9111ee6",
+        ),
+        (
+            "bbaa99887766554433221101",
+            "0001020304050607",
+            "0001020304050607",
+            "6820b3657b6f615a5725bda0d3b4eb3a257c9af1f8f03009",
+        ),
+        (
+            "bbaa99887766554433221102",
+            "0001020304050607",
+            "",
+            "81017f8203f081277152fade694a0a00",
+        ),
+        (
+            "bbaa99887766554433221103",
+            "",
+            "0001020304050607",
+            "45dd69f8f5aae72414054cd1f35d82760b2cd00d2f99bfa9",
+        ),
+        (
+            "bbaa99887766554433221104",
+            "000102030405060708090a0b0c0d0e0f",
+            "000102030405060708090a0b0c0d0e0f",
+            "571d535b60b277188be5147170a9a22c3ad7a4ff3835b8c5701c1ccec8fc3358",
+        ),
+        (
+            "bbaa9988776655443322110a",
+            "000102030405060708090a0b0c0d0e0f1011121314151617",
+            "000102030405060708090a0b0c0d0e0f1011121314151617",
+            "bd6f6c496201c69296c11efd138a467ae6a91cd06cabdb6ccb001032563a152cdaa97cc0c01e9ddd",
+        ),
+    ];
+
+    #[test]
+    fn test_seal_and_open_from_rfc() {
+        for (nonce, aad, plaintext, ciphertext) in RFC_VECTORS {
+            let nonce = parse(nonce);
+            let aad = parse(aad);
+            let plaintext = parse(plaintext);
+            let ciphertext = parse(ciphertext);
+
+            assert_eq!(
+                seal(parse_key(KEY), &nonce, &aad, &plaintext, 16),
+                ciphertext
+            );
+            assert_eq!(
+                open(parse_key(KEY), &nonce, &aad, &ciphertext, 16),
+                Ok(plaintext)
+            );
+        }
+    }
+
+    #[test]
+    /// the sample with a 96 bit tag from appendix A of the RFC
+    fn test_short_tag_from_rfc() {
+        let key = || parse_key("0f0e0d0c0b0a09080706050403020100");
+        let nonce = parse("bbaa9988776655443322110d");
+        let input = parse(
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f2021222324252627",
+        );
+        let ciphertext = parse("1792a4e31e0755fb03e31b22116e6c2ddf9efd6e33d536f1a0124b0a55bae884ed93481529c76b6ad0c515f4d1cdd4fdac4f02aa");
+
+        assert_eq!(seal(key(), &nonce, &input, &input, 12), ciphertext);
+        assert_eq!(open(key(), &nonce, &input, &ciphertext, 12), Ok(input));
+    }
+
+    #[test]
+    fn test_tag_length_changes_output() {
+        // the tag length is part of the initial offset, so a truncated tag is not a
+        // prefix of the full tag
+        let nonce = parse("bbaa99887766554433221103");
+        let full = seal(parse_key(KEY), &nonce, &[], b"01234567", 16);
+        let short = seal(parse_key(KEY), &nonce, &[], b"01234567", 8);
+
+        assert_ne!(full[..16], short);
+        assert_eq!(
+            open(parse_key(KEY), &nonce, &[], &full[..16], 8),
+            Err(AuthenticationError)
+        );
+    }
+
+    #[test]
+    fn test_open_rejects_modifications() {
+        let (nonce, aad, _, ciphertext) = RFC_VECTORS[5];
+        let nonce = parse(nonce);
+        let aad = parse(aad);
+        let ciphertext = parse(ciphertext);
+
+        for i in 0..ciphertext.len() {
+            let mut modified = ciphertext.clone();
+            modified[i] ^= 0x01;
+            assert_eq!(
+                open(parse_key(KEY), &nonce, &aad, &modified, 16),
+                Err(AuthenticationError)
+            );
+        }
+
+        assert_eq!(
+            open(parse_key(KEY), &nonce, &aad[1..], &ciphertext, 16),
+            Err(AuthenticationError)
+        );
+        assert_eq!(
+            open(parse_key(KEY), &nonce[1..], &aad, &ciphertext, 16),
+            Err(AuthenticationError)
+        );
+        assert_eq!(
+            open(parse_key(KEY), &nonce, &aad, &ciphertext[..15], 16),
+            Err(AuthenticationError)
+        );
+    }
+}
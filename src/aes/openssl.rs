@@ -0,0 +1,387 @@
+//! The file format of `openssl enc`
+//!
+//! When encrypting with a password, `openssl enc` writes the magic `Salted__` and an
+//! 8 byte random salt, followed by the ciphertext. Key and IV are derived from the
+//! password and the salt, either with PBKDF2 (`-pbkdf2` or `-iter`) or with the legacy
+//! `EVP_BytesToKey` (the default). With `-nosalt`, the header is omitted and the
+//! derivation uses no salt. The plaintext is always padded with PKCS#7.
+//!
+//! <https://docs.openssl.org/master/man1/openssl-enc/>
+
+use std::io::{self, Read, Write};
+
+use super::{
+    stream::{Decryptor, Encryptor, Mode, Padding},
+    AnyKey, Key128, Key192, Key256,
+};
+use crate::{
+    hash::{md5::Md5, sha256::Sha256},
+    kdf,
+};
+
+/// The start of a salted file
+pub const MAGIC: [u8; 8] = *b"Salted__";
+
+/// The iteration count used by `openssl enc -pbkdf2` if `-iter` is not given
+pub const DEFAULT_ITERATIONS: u32 = 10_000;
+
+/// The digest used by the key derivation, selected with `-md`. OpenSSL uses SHA-256
+/// since version 1.1.0, and MD5 before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Digest {
+    Md5,
+    Sha256,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyDerivation {
+    /// `EVP_BytesToKey` with a single iteration
+    BytesToKey(Digest),
+    Pbkdf2 {
+        digest: Digest,
+        iterations: u32,
+    },
+}
+
+impl KeyDerivation {
+    fn derive(self, password: &[u8], salt: Option<&[u8; 8]>, len: usize) -> Vec<u8> {
+        match self {
+            Self::BytesToKey(Digest::Md5) => kdf::evp_bytes_to_key::<Md5>(password, salt, 1, len),
+            Self::BytesToKey(Digest::Sha256) => {
+                kdf::evp_bytes_to_key::<Sha256>(password, salt, 1, len)
+            }
+            Self::Pbkdf2 { digest, iterations } => {
+                let salt = salt.map_or(&[][..], |salt| salt);
+                match digest {
+                    Digest::Md5 => kdf::pbkdf2::<Md5>(password, salt, iterations, len),
+                    Digest::Sha256 => kdf::pbkdf2::<Sha256>(password, salt, iterations, len),
+                }
+            }
+        }
+    }
+}
+
+/// The ciphers of `openssl enc` that are supported here
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cipher {
+    /// `-aes-128-ecb`
+    Aes128Ecb,
+    /// `-aes-128-cbc`
+    Aes128Cbc,
+    /// `-aes-192-ecb`
+    Aes192Ecb,
+    /// `-aes-192-cbc`
+    Aes192Cbc,
+    /// `-aes-256-ecb`
+    Aes256Ecb,
+    /// `-aes-256-cbc`
+    Aes256Cbc,
+}
+
+impl Cipher {
+    const fn key_len(self) -> usize {
+        match self {
+            Self::Aes128Ecb | Self::Aes128Cbc => 16,
+            Self::Aes192Ecb | Self::Aes192Cbc => 24,
+            Self::Aes256Ecb | Self::Aes256Cbc => 32,
+        }
+    }
+
+    const fn iv_len(self) -> usize {
+        match self {
+            Self::Aes128Ecb | Self::Aes192Ecb | Self::Aes256Ecb => 0,
+            Self::Aes128Cbc | Self::Aes192Cbc | Self::Aes256Cbc => 16,
+        }
+    }
+}
+
+/// Derives the key and the mode with its IV from `password`
+pub fn derive(
+    password: &[u8],
+    salt: Option<&[u8; 8]>,
+    cipher: Cipher,
+    key_derivation: KeyDerivation,
+) -> (AnyKey, Mode) {
+    let derived = key_derivation.derive(
+        password,
+        salt,
+        cipher.key_len().saturating_add(cipher.iv_len()),
+    );
+    let (key, iv) = derived.split_at(cipher.key_len());
+
+    let key = match cipher.key_len() {
+        16 => Key128::from_bytes(key.try_into().expect("key has 16 bytes")).into(),
+        24 => Key192::from_bytes(key.try_into().expect("key has 24 bytes")).into(),
+        _ => Key256::from_bytes(key.try_into().expect("key has 32 bytes")).into(),
+    };
+    let mode = match cipher.iv_len() {
+        0 => Mode::Ecb,
+        _ => Mode::Cbc(iv.try_into().expect("iv has 16 bytes")),
+    };
+
+    (key, mode)
+}
+
+/// Writes the header with `salt` if there is one, and returns an [`Encryptor`] for
+/// the rest of the file
+pub fn encryptor<W: Write>(
+    mut inner: W,
+    password: &[u8],
+    salt: Option<[u8; 8]>,
+    cipher: Cipher,
+    key_derivation: KeyDerivation,
+) -> io::Result<Encryptor<W>> {
+    if let Some(ref salt) = salt {
+        inner.write_all(&MAGIC)?;
+        inner.write_all(salt)?;
+    }
+
+    let (key, mode) = derive(password, salt.as_ref(), cipher, key_derivation);
+    Ok(Encryptor::new(inner, key, mode, Padding::Pkcs7))
+}
+
+/// Reads the header if the file is `salted`, and returns a [`Decryptor`] for the rest
+/// of the file
+pub fn decryptor<R: Read>(
+    mut inner: R,
+    password: &[u8],
+    salted: bool,
+    cipher: Cipher,
+    key_derivation: KeyDerivation,
+) -> io::Result<Decryptor<R>> {
+    let salt = if salted {
+        let mut header = [0; 16];
+        inner.read_exact(&mut header)?;
+
+        let (magic, salt) = header.split_at(8);
+        if magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "missing Salted__ header",
+            ));
+        }
+        Some(salt.try_into().expect("split at 8 bytes"))
+    } else {
+        None
+    };
+
+    let (key, mode) = derive(password, salt.as_ref(), cipher, key_derivation);
+    Ok(Decryptor::new(inner, key, mode, Padding::Pkcs7))
+}
+
+/// Encrypts `plaintext` like `openssl enc -e`
+pub fn encrypt(
+    plaintext: &[u8],
+    password: &[u8],
+    salt: Option<[u8; 8]>,
+    cipher: Cipher,
+    key_derivation: KeyDerivation,
+) -> Vec<u8> {
+    let mut encryptor = encryptor(Vec::new(), password, salt, cipher, key_derivation)
+        .expect("writing to a vec cannot fail");
+    encryptor
+        .write_all(plaintext)
+        .expect("writing to a vec cannot fail");
+    encryptor.finish().expect("writing to a vec cannot fail")
+}
+
+/// Decrypts `data` like `openssl enc -d`
+pub fn decrypt(
+    data: &[u8],
+    password: &[u8],
+    salted: bool,
+    cipher: Cipher,
+    key_derivation: KeyDerivation,
+) -> io::Result<Vec<u8>> {
+    let mut decryptor = decryptor(data, password, salted, cipher, key_derivation)?;
+    let mut plaintext = Vec::new();
+    decryptor.read_to_end(&mut plaintext)?;
+    Ok(plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base64;
+
+    const SALT: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+    const PBKDF2: KeyDerivation = KeyDerivation::Pbkdf2 {
+        digest: Digest::Sha256,
+        iterations: DEFAULT_ITERATIONS,
+    };
+
+    fn decode(input: &str) -> Vec<u8> {
+        base64::decode_str(input).expect("test data is valid base64")
+    }
+
+    struct TestCase {
+        cipher: Cipher,
+        key_derivation: KeyDerivation,
+        salt: Option<[u8; 8]>,
+        /// the output of `openssl enc -a` with the matching options
+        ciphertext: &'static str,
+    }
+
+    const PLAINTEXT: &[u8] = b"Ice, Ice, baby, too cold, too cold\n";
+
+    #[test]
+    /// generated with `openssl enc -e -a -pass pass:password -S 0102030405060708`, which
+    /// omits the header when the salt is given explicitly
+    fn test_openssl_compatibility() {
+        let cases = [
+            TestCase {
+                cipher: Cipher::Aes128Cbc,
+                key_derivation: PBKDF2,
+                salt: Some(SALT),
+                ciphertext: "jvS7mWkTG940D9bKxVHTmaXwWjzVGZUa0kzAseLPG9VCKh0uaRALZfd6Ydt1OmKH",
+            },
+            TestCase {
+                cipher: Cipher::Aes128Cbc,
+                key_derivation: KeyDerivation::Pbkdf2 {
+                    digest: Digest::Md5,
+                    iterations: 1000,
+                },
+                salt: Some(SALT),
+                ciphertext: "YFAiMohzz2Uk/T/pW9I4Kv1+JqTbOayxy2mLVOezoght8mulb5TMAdYXLSAdR57N",
+            },
+            TestCase {
+                cipher: Cipher::Aes128Cbc,
+                key_derivation: KeyDerivation::BytesToKey(Digest::Sha256),
+                salt: Some(SALT),
+                ciphertext: "BpnwEDysFHiAo6TgQQruATBdkrrQXuxFHfshaEtXuWEZ0CD/QIgV+KbPH1y+6r5L",
+            },
+            TestCase {
+                cipher: Cipher::Aes128Cbc,
+                key_derivation: KeyDerivation::BytesToKey(Digest::Md5),
+                salt: Some(SALT),
+                ciphertext: "a1/b8KH7dCz8x6ShkyxAKCmViM3rLFD/9qOjMIB+Hqn52quRHNhnhId7TC3MPi36",
+            },
+            TestCase {
+                cipher: Cipher::Aes128Ecb,
+                key_derivation: PBKDF2,
+                salt: Some(SALT),
+                ciphertext: "PefRTpDqewmdUF8dTLLfmdXpEmCRRMQJHdidBRFkjHTzMonaFyNbNz9lXoJcEIGP",
+            },
+            TestCase {
+                cipher: Cipher::Aes128Cbc,
+                key_derivation: PBKDF2,
+                salt: None,
+                ciphertext: "h9H8KED1JOOQakUTcgjPXcBqHrjWoE7n/qrrtE+L/q6OuQ/XfEaOup5YjY2hNyqT",
+            },
+            TestCase {
+                cipher: Cipher::Aes192Cbc,
+                key_derivation: PBKDF2,
+                salt: Some(SALT),
+                ciphertext: "niGro6Zxyw6KZ4DQ1wMyH5Orm1+nljiKs+0y+m0P4gxancQscjEWwChbw0oNy+gu",
+            },
+            TestCase {
+                cipher: Cipher::Aes192Ecb,
+                key_derivation: KeyDerivation::BytesToKey(Digest::Md5),
+                salt: Some(SALT),
+                ciphertext: "njhMPq+aZ0ECZgztN2oiuEqx3yz9rQmmq+WrkBCIGwepQjqTsMZvXxYCvCJZXz4I",
+            },
+            TestCase {
+                cipher: Cipher::Aes256Cbc,
+                key_derivation: PBKDF2,
+                salt: Some(SALT),
+                ciphertext: "AVXpakkhuVPQCBjqEB+YuT3D6pui13LOIFJrjPz3+60dzoEV1NexHnPwd9fx71GZ",
+            },
+            TestCase {
+                cipher: Cipher::Aes256Cbc,
+                key_derivation: KeyDerivation::BytesToKey(Digest::Sha256),
+                salt: Some(SALT),
+                ciphertext: "gyHlB4lfp8RmAhtS44HnSO0S1hHZjRGzO9x9QNErYyaSsqZE6FtqwcbXrzWR9Jh4",
+            },
+            TestCase {
+                cipher: Cipher::Aes256Ecb,
+                key_derivation: PBKDF2,
+                salt: Some(SALT),
+                ciphertext: "DiW9t3MmYWbLEP+X9ayvmZm7uRpaX2Pdah0U6Sadu1QAn4/Ablwn0CnOQAp8JMza",
+            },
+        ];
+
+        for case in cases {
+            let mut ciphertext = decode(case.ciphertext);
+            if let Some(salt) = case.salt {
+                ciphertext.splice(0..0, MAGIC.into_iter().chain(salt));
+            }
+
+            assert_eq!(
+                encrypt(
+                    PLAINTEXT,
+                    b"password",
+                    case.salt,
+                    case.cipher,
+                    case.key_derivation
+                ),
+                ciphertext
+            );
+            assert_eq!(
+                decrypt(
+                    &ciphertext,
+                    b"password",
+                    case.salt.is_some(),
+                    case.cipher,
+                    case.key_derivation
+                )
+                .expect("ciphertext is valid"),
+                PLAINTEXT
+            );
+        }
+    }
+
+    #[test]
+    /// generated with `openssl enc -e -a -aes-128-cbc -pbkdf2 -pass pass:password`, and
+    /// the same with `-aes-256-cbc`
+    fn test_decrypt_openssl_file() {
+        let files = [
+            (
+                Cipher::Aes128Cbc,
+                "U2FsdGVkX1/4TJcE6uJHH6RIQvhXev4kou4amJcYfp4v0vvyHKKXLmcapiomcfPKwyWjX5GrBmC4Wh3TNo7wmA==",
+            ),
+            (
+                Cipher::Aes256Cbc,
+                "U2FsdGVkX1/leEg6VYXJB7ri6bIP9hsdJfEAcWt8pOZkwMcH2ad2O4Lym/phQSTsHOYxSdrYBLsJ+xhnXWou5w==",
+            ),
+        ];
+
+        for (cipher, file) in files {
+            assert_eq!(
+                decrypt(&decode(file), b"password", true, cipher, PBKDF2)
+                    .expect("ciphertext is valid"),
+                PLAINTEXT
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let ciphertext = encrypt(
+            PLAINTEXT,
+            b"password",
+            Some(SALT),
+            Cipher::Aes128Cbc,
+            PBKDF2,
+        );
+
+        let error = decrypt(
+            ciphertext.get(8..).expect("ciphertext has a header"),
+            b"password",
+            true,
+            Cipher::Aes128Cbc,
+            PBKDF2,
+        )
+        .expect_err("header is missing");
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let error = decrypt(&MAGIC, b"password", true, Cipher::Aes128Cbc, PBKDF2)
+            .expect_err("salt is missing");
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+
+        // a wrong password almost always results in invalid padding
+        let error = decrypt(&ciphertext, b"wrong", true, Cipher::Aes128Cbc, PBKDF2)
+            .expect_err("password is wrong");
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
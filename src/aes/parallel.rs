@@ -0,0 +1,168 @@
+//! Multi-threaded ECB and CTR
+//!
+//! Every block of ECB and CTR can be processed independently, so large inputs can be
+//! split across threads. The input is divided into chunks of [`Options::chunk_size`]
+//! bytes, which the threads take from a shared queue until all are done. The output
+//! is identical to the sequential functions [`super::decrypt_ecb()`] and
+//! [`super::ctr::encrypt()`].
+
+use super::{
+    ctr::{Counter, Keystream},
+    inv_cipher, Key128,
+};
+use crate::parallel;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// The number of threads to use, including the calling thread
+    pub threads: usize,
+    /// The number of bytes processed by a thread at once. Needs to be a multiple of 16.
+    pub chunk_size: usize,
+}
+
+impl Default for Options {
+    /// Uses all available cores, with chunks of 64 KiB
+    fn default() -> Self {
+        Self {
+            threads: parallel::available_threads(),
+            chunk_size: 64 * 1024,
+        }
+    }
+}
+
+/// Runs `process` for every chunk of `data` on `options.threads` threads. `process`
+/// receives the index of the chunk and the chunk itself.
+fn for_each_chunk<F>(data: &mut [u8], options: Options, process: F)
+where
+    F: Fn(usize, &mut [u8]) + Sync,
+{
+    assert!(
+        options.chunk_size != 0 && options.chunk_size.is_multiple_of(16),
+        "chunk size needs to be a non-zero multiple of 16"
+    );
+
+    parallel::for_each(
+        data.chunks_mut(options.chunk_size).enumerate(),
+        options.threads,
+        |(index, chunk)| process(index, chunk),
+    );
+}
+
+/// Decrypts block-aligned `ciphertext` in ECB mode.
+pub fn decrypt_ecb(ciphertext: &[u8], key: Key128, options: Options) -> Vec<u8> {
+    assert!(
+        ciphertext.len().is_multiple_of(16),
+        "input length needs to be a multiple of 16"
+    );
+
+    let round_keys = key.expand();
+    let mut output = ciphertext.to_vec();
+
+    for_each_chunk(&mut output, options, |_, chunk| {
+        for block in chunk.chunks_exact_mut(16) {
+            let decrypted = inv_cipher(
+                (*block).try_into().expect("chunk is exactly 16 bytes long"),
+                &round_keys,
+            );
+            block.copy_from_slice(&decrypted);
+        }
+    });
+
+    output
+}
+
+/// Encrypts or decrypts `input` in CTR mode.
+pub fn ctr(input: &[u8], key: Key128, counter: Counter, options: Options) -> Vec<u8> {
+    let keystream = Keystream::new(key, counter);
+    let mut output = input.to_vec();
+
+    for_each_chunk(&mut output, options, |index, chunk| {
+        let offset = index
+            .checked_mul(options.chunk_size)
+            .and_then(|offset| u64::try_from(offset).ok())
+            .expect("offset fits into u64");
+        keystream.apply(offset, chunk);
+    });
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes;
+
+    fn key() -> Key128 {
+        Key128::from_bytes(*b"YELLOW SUBMARINE")
+    }
+
+    fn input(len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|i| u8::try_from(i % 251).expect("value is smaller than 251"))
+            .collect()
+    }
+
+    const OPTIONS: [Options; 5] = [
+        Options {
+            threads: 1,
+            chunk_size: 16,
+        },
+        Options {
+            threads: 2,
+            chunk_size: 16,
+        },
+        Options {
+            threads: 3,
+            chunk_size: 48,
+        },
+        Options {
+            threads: 8,
+            chunk_size: 4096,
+        },
+        Options {
+            threads: 16,
+            chunk_size: 160,
+        },
+    ];
+
+    #[test]
+    fn test_ecb_matches_sequential() {
+        for len in [0, 16, 32, 1024, 100_000] {
+            let input = input(len);
+            let expected = aes::decrypt_ecb(&input, key());
+
+            for options in OPTIONS {
+                assert_eq!(decrypt_ecb(&input, key(), options), expected);
+            }
+            assert_eq!(decrypt_ecb(&input, key(), Options::default()), expected);
+        }
+    }
+
+    #[test]
+    fn test_ctr_matches_sequential() {
+        let counter = Counter::LittleEndian64 { nonce: 42 };
+
+        for len in [0, 1, 15, 17, 1000, 100_003] {
+            let input = input(len);
+            let expected = aes::ctr::encrypt(&input, key(), counter);
+
+            for options in OPTIONS {
+                assert_eq!(ctr(&input, key(), counter, options), expected);
+            }
+            assert_eq!(ctr(&input, key(), counter, Options::default()), expected);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "multiple of 16")]
+    fn test_invalid_chunk_size() {
+        decrypt_ecb(
+            &input(64),
+            key(),
+            Options {
+                threads: 2,
+                chunk_size: 20,
+            },
+        );
+    }
+}
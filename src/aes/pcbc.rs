@@ -0,0 +1,122 @@
+//! Propagating Cipher Block Chaining (PCBC)
+//!
+//! Like CBC, but both the previous plaintext and ciphertext block are mixed into the
+//! next block: `C_i = E(P_i ⊕ P_{i-1} ⊕ C_{i-1})`. This was used in Kerberos v4 and
+//! WASTE.
+//!
+//! <https://en.wikipedia.org/wiki/Block_cipher_mode_of_operation#Propagating_cipher_block_chaining_(PCBC)>
+//!
+//! The idea was that a modification garbles all following blocks, so it can be
+//! detected with a checksum at the end. But swapping two adjacent ciphertext blocks
+//! leaves `P_i ⊕ C_i` of the pair unchanged, so only the two swapped blocks decrypt
+//! to garbage. This was used to attack Kerberos v4.
+//!
+//! The input needs to be block-aligned.
+
+use super::{cbc::xor_block, cipher, inv_cipher, Key128};
+
+/// Encrypts block-aligned `plaintext`.
+pub fn encrypt(plaintext: &[u8], key: Key128, iv: [u8; 16]) -> Vec<u8> {
+    let round_keys = key.expand();
+    let mut output = Vec::with_capacity(plaintext.len());
+
+    // P_{i-1} ⊕ C_{i-1}, initialized with the IV
+    let mut previous = iv;
+
+    for chunk in plaintext.chunks(16) {
+        let chunk = chunk
+            .try_into()
+            .expect("input length needs to be a multiple of 16");
+        let encrypted = cipher(xor_block(chunk, previous), &round_keys);
+        previous = xor_block(chunk, encrypted);
+        output.extend_from_slice(&encrypted);
+    }
+
+    output
+}
+
+/// Decrypts block-aligned `ciphertext`.
+pub fn decrypt(ciphertext: &[u8], key: Key128, iv: [u8; 16]) -> Vec<u8> {
+    let round_keys = key.expand();
+    let mut output = Vec::with_capacity(ciphertext.len());
+
+    let mut previous = iv;
+
+    for chunk in ciphertext.chunks(16) {
+        let chunk = chunk
+            .try_into()
+            .expect("input length needs to be a multiple of 16");
+        let decrypted = xor_block(inv_cipher(chunk, &round_keys), previous);
+        previous = xor_block(decrypted, chunk);
+        output.extend_from_slice(&decrypted);
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        aes::{cbc, parse_key},
+        hex::parse,
+    };
+
+    fn key() -> Key128 {
+        parse_key("2b7e151628aed2a6abf7158809cf4f3c")
+    }
+
+    const IV: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f,
+    ];
+
+    #[test]
+    /// There are no official test vectors, so this uses the input of the CBC example
+    /// from SP 800-38A, with the output of an independent implementation
+    /// (pyca/cryptography in ECB mode)
+    fn test_pcbc() {
+        let plaintext = parse("6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e5130c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710");
+        let ciphertext = parse("7649abac8119b246cee98e9b12e9197d9e8baff12ad5270a0d1eef93d70379945700b39803779fa35a3c600a49a163c033ae199f27379f21be6dd57d295cc87d");
+
+        assert_eq!(encrypt(&plaintext, key(), IV), ciphertext);
+        assert_eq!(decrypt(&ciphertext, key(), IV), plaintext);
+
+        // the first block is identical to CBC
+        assert_eq!(ciphertext[..16], cbc::encrypt(&plaintext, key(), IV)[..16]);
+    }
+
+    #[test]
+    fn test_modification_propagates() {
+        let plaintext = b"Pay 100 dollars to Alice. Thanks and kind regards, Bob. ........";
+
+        let mut ciphertext = encrypt(plaintext, key(), IV);
+        ciphertext[16] ^= 0x01;
+        let decrypted = decrypt(&ciphertext, key(), IV);
+
+        assert_eq!(decrypted[..16], plaintext[..16]);
+        for (decrypted, plaintext) in decrypted.chunks(16).zip(plaintext.chunks(16)).skip(1) {
+            assert_ne!(decrypted, plaintext);
+        }
+    }
+
+    #[test]
+    fn test_block_swap() {
+        let plaintext = b"Pay 100 dollars to Alice. Thanks and kind regards, Bob. ........";
+
+        let mut ciphertext = encrypt(plaintext, key(), IV);
+
+        // swap the second and third block
+        let (second, third) = ciphertext[16..48].split_at_mut(16);
+        second.swap_with_slice(third);
+
+        let decrypted = decrypt(&ciphertext, key(), IV);
+
+        // only the swapped blocks are garbled, the rest of the message, including any
+        // trailing checksum, decrypts correctly
+        assert_eq!(decrypted[..16], plaintext[..16]);
+        assert_ne!(decrypted[16..32], plaintext[16..32]);
+        assert_ne!(decrypted[32..48], plaintext[32..48]);
+        assert_eq!(decrypted[48..], plaintext[48..]);
+    }
+}
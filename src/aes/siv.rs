@@ -0,0 +1,197 @@
+//! Synthetic Initialization Vector (SIV)
+//!
+//! SIV derives the IV for counter mode from the input itself, using S2V, a CMAC-based
+//! PRF over a vector of strings. Reusing a nonce (or using none at all) then only
+//! reveals whether the same input was encrypted twice.
+//!
+//! <https://www.rfc-editor.org/rfc/rfc5297>
+//!
+//! The associated data is a list of components, each of which is authenticated
+//! separately. For nonce-based usage, the nonce is passed as the last component. The
+//! output of [`seal()`] is the synthetic IV followed by the ciphertext.
+
+use super::{
+    cipher, cmac::Cmac, constant_time_eq, gf128, key::RoundKeys128, AuthenticationError, Key128,
+};
+
+/// The maximum number of associated data components. S2V accepts at most 127
+/// strings, one of which is the plaintext.
+pub const MAX_COMPONENTS: usize = 126;
+
+fn s2v(mac_key: Key128, components: &[&[u8]], plaintext: &[u8]) -> [u8; 16] {
+    assert!(
+        components.len() <= MAX_COMPONENTS,
+        "too many associated data components"
+    );
+
+    // All invocations use the same key, so the subkeys are only derived once
+    let mac = Cmac::new(mac_key);
+    let cmac = |input: &[u8]| {
+        let mut mac = mac.clone();
+        mac.update(input);
+        mac.finalize()
+    };
+
+    let d = u128::from_be_bytes(cmac(&[0; 16]));
+
+    let d = components.iter().fold(d, |d, component| {
+        gf128::add(gf128::dbl(d), u128::from_be_bytes(cmac(component)))
+    });
+
+    let t = if plaintext.len() >= 16 {
+        // xorend: xor d onto the last 16 bytes of the plaintext
+        let mut t = plaintext.to_vec();
+        let tail_start = t.len().saturating_sub(16);
+        for (b, d) in t[tail_start..].iter_mut().zip(d.to_be_bytes()) {
+            *b ^= d;
+        }
+        t
+    } else {
+        let mut padded = [0; 16];
+        padded[..plaintext.len()].copy_from_slice(plaintext);
+        padded[plaintext.len()] = 0x80;
+        gf128::add(gf128::dbl(d), u128::from_be_bytes(padded))
+            .to_be_bytes()
+            .to_vec()
+    };
+
+    cmac(&t)
+}
+
+/// Counter mode, starting at the synthetic IV with the 31st and 63rd bit (counted
+/// from the right) cleared. The counter is incremented modulo 2¹²⁸.
+fn ctr(round_keys: &RoundKeys128, iv: [u8; 16], input: &[u8]) -> Vec<u8> {
+    let mut counter = iv;
+    counter[8] &= 0x7f;
+    counter[12] &= 0x7f;
+    let mut counter = u128::from_be_bytes(counter);
+
+    let mut output = Vec::with_capacity(input.len());
+    for chunk in input.chunks(16) {
+        let keystream = cipher(counter.to_be_bytes(), round_keys);
+        output.extend(chunk.iter().zip(keystream.iter()).map(|(c, k)| c ^ k));
+        counter = counter.wrapping_add(1);
+    }
+
+    output
+}
+
+/// Encrypts `plaintext`, authenticating both the plaintext and all components of the
+/// associated data.
+///
+/// The key consists of two halves: `mac_key` is used for S2V, `ctr_key` for encryption.
+pub fn seal(mac_key: Key128, ctr_key: Key128, components: &[&[u8]], plaintext: &[u8]) -> Vec<u8> {
+    let iv = s2v(mac_key, components, plaintext);
+
+    let mut output = iv.to_vec();
+    output.extend(ctr(&ctr_key.expand(), iv, plaintext));
+    output
+}
+
+/// Decrypts and verifies the output of [`seal()`].
+pub fn open(
+    mac_key: Key128,
+    ctr_key: Key128,
+    components: &[&[u8]],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, AuthenticationError> {
+    let (iv, ciphertext) = ciphertext
+        .split_first_chunk::<16>()
+        .ok_or(AuthenticationError)?;
+
+    let plaintext = ctr(&ctr_key.expand(), *iv, ciphertext);
+    let expected_iv = s2v(mac_key, components, &plaintext);
+
+    if !constant_time_eq(&expected_iv, iv) {
+        return Err(AuthenticationError);
+    }
+
+    Ok(plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex::parse;
+
+    fn keys(input: &str) -> (Key128, Key128) {
+        let input = parse(input);
+        let (mac_key, ctr_key) = input.split_at(16);
+        (
+            Key128::from_bytes(mac_key.try_into().expect("test key has 32 bytes")),
+            Key128::from_bytes(ctr_key.try_into().expect("test key has 32 bytes")),
+        )
+    }
+
+    #[test]
+    /// example A.1 from RFC 5297
+    fn test_deterministic_from_rfc() {
+        let key = "fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff";
+        let ad = parse("101112131415161718191a1b1c1d1e1f2021222324252627");
+        let plaintext = parse("112233445566778899aabbccddee");
+        let output = parse("85632d07c6e8f37f950acd320a2ecc9340c02b9690c4dc04daef7f6afe5c");
+
+        let (mac_key, ctr_key) = keys(key);
+        assert_eq!(seal(mac_key, ctr_key, &[&ad], &plaintext), output);
+
+        let (mac_key, ctr_key) = keys(key);
+        assert_eq!(open(mac_key, ctr_key, &[&ad], &output), Ok(plaintext));
+    }
+
+    #[test]
+    /// example A.2 from RFC 5297
+    fn test_nonce_based_from_rfc() {
+        let key = "7f7e7d7c7b7a79787776757473727170404142434445464748494a4b4c4d4e4f";
+        let ad1 = parse(
+            "00112233445566778899aabbccddeeffdeaddadadeaddadaffeeddccbbaa99887766554433221100",
+        );
+        let ad2 = parse("102030405060708090a0");
+        let nonce = parse("09f911029d74e35bd84156c5635688c0");
+        let plaintext = parse("7468697320697320736f6d6520706c61696e7465787420746f20656e6372797074207573696e67205349562d414553");
+        let output = parse("7bdb6e3b432667eb06f4d14bff2fbd0fcb900f2fddbe404326601965c889bf17dba77ceb094fa663b7a3f748ba8af829ea64ad544a272e9c485b62a3fd5c0d");
+
+        let (mac_key, ctr_key) = keys(key);
+        assert_eq!(
+            seal(mac_key, ctr_key, &[&ad1, &ad2, &nonce], &plaintext),
+            output
+        );
+
+        let (mac_key, ctr_key) = keys(key);
+        assert_eq!(
+            open(mac_key, ctr_key, &[&ad1, &ad2, &nonce], &output),
+            Ok(plaintext)
+        );
+    }
+
+    #[test]
+    fn test_open_rejects_modifications() {
+        let key = "fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff";
+        let ad = parse("101112131415161718191a1b1c1d1e1f2021222324252627");
+        let output = parse("85632d07c6e8f37f950acd320a2ecc9340c02b9690c4dc04daef7f6afe5c");
+
+        for i in 0..output.len() {
+            let mut modified = output.clone();
+            modified[i] ^= 0x01;
+
+            let (mac_key, ctr_key) = keys(key);
+            assert_eq!(
+                open(mac_key, ctr_key, &[&ad], &modified),
+                Err(AuthenticationError)
+            );
+        }
+
+        // the components are authenticated separately, so moving data from one to
+        // the other must be detected
+        let (mac_key, ctr_key) = keys(key);
+        assert_eq!(
+            open(mac_key, ctr_key, &[&ad[..8], &ad[8..]], &output),
+            Err(AuthenticationError)
+        );
+
+        let (mac_key, ctr_key) = keys(key);
+        assert_eq!(
+            open(mac_key, ctr_key, &[&ad], &output[..15]),
+            Err(AuthenticationError)
+        );
+    }
+}
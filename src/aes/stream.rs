@@ -0,0 +1,499 @@
+//! Streaming encryption and decryption
+//!
+//! [`Encryptor`] wraps a [`Write`]r and encrypts everything written to it,
+//! [`Decryptor`] wraps a [`Read`]er and decrypts everything read from it. Only a
+//! few blocks are buffered at a time, so inputs of any size can be processed in
+//! constant memory.
+//!
+//! The output matches the slice-based functions of the respective modes, e.g.
+//! [`super::cbc::encrypt()`] applied to the padded input.
+
+use std::io::{self, Read, Write};
+
+use super::{cbc::xor_block, cipher, inv_cipher, key::AnyRoundKeys, AnyKey};
+use crate::pkcs7;
+
+/// The block cipher modes that can be streamed, along with their IVs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Ecb,
+    Cbc([u8; 16]),
+    Pcbc([u8; 16]),
+    /// The IV consists of two blocks, see [`super::ige`]
+    Ige([u8; 32]),
+}
+
+/// How the input is padded to a multiple of the block size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Padding {
+    Pkcs7,
+    /// The input must already be block-aligned
+    None,
+}
+
+/// The chaining values carried from one block to the next
+enum Chain {
+    Ecb,
+    Cbc([u8; 16]),
+    Pcbc([u8; 16]),
+    Ige {
+        ciphertext: [u8; 16],
+        plaintext: [u8; 16],
+    },
+}
+
+impl Chain {
+    fn new(mode: Mode) -> Self {
+        match mode {
+            Mode::Ecb => Self::Ecb,
+            Mode::Cbc(iv) => Self::Cbc(iv),
+            Mode::Pcbc(iv) => Self::Pcbc(iv),
+            Mode::Ige(iv) => {
+                let (ciphertext, plaintext) = iv.split_at(16);
+                Self::Ige {
+                    ciphertext: ciphertext.try_into().expect("split at 16 bytes"),
+                    plaintext: plaintext.try_into().expect("split at 16 bytes"),
+                }
+            }
+        }
+    }
+
+    fn encrypt(&mut self, round_keys: &AnyRoundKeys, block: [u8; 16]) -> [u8; 16] {
+        match *self {
+            Self::Ecb => cipher(block, round_keys),
+            Self::Cbc(ref mut previous) => {
+                *previous = cipher(xor_block(block, *previous), round_keys);
+                *previous
+            }
+            Self::Pcbc(ref mut previous) => {
+                let encrypted = cipher(xor_block(block, *previous), round_keys);
+                *previous = xor_block(block, encrypted);
+                encrypted
+            }
+            Self::Ige {
+                ref mut ciphertext,
+                ref mut plaintext,
+            } => {
+                *ciphertext = xor_block(
+                    cipher(xor_block(block, *ciphertext), round_keys),
+                    *plaintext,
+                );
+                *plaintext = block;
+                *ciphertext
+            }
+        }
+    }
+
+    fn decrypt(&mut self, round_keys: &AnyRoundKeys, block: [u8; 16]) -> [u8; 16] {
+        match *self {
+            Self::Ecb => inv_cipher(block, round_keys),
+            Self::Cbc(ref mut previous) => {
+                let decrypted = xor_block(inv_cipher(block, round_keys), *previous);
+                *previous = block;
+                decrypted
+            }
+            Self::Pcbc(ref mut previous) => {
+                let decrypted = xor_block(inv_cipher(block, round_keys), *previous);
+                *previous = xor_block(decrypted, block);
+                decrypted
+            }
+            Self::Ige {
+                ref mut ciphertext,
+                ref mut plaintext,
+            } => {
+                *plaintext = xor_block(
+                    inv_cipher(xor_block(block, *plaintext), round_keys),
+                    *ciphertext,
+                );
+                *ciphertext = block;
+                *plaintext
+            }
+        }
+    }
+}
+
+/// Collects input until a full block is available
+struct BlockBuffer {
+    block: [u8; 16],
+    len: usize,
+}
+
+impl BlockBuffer {
+    const fn new() -> Self {
+        Self {
+            block: [0; 16],
+            len: 0,
+        }
+    }
+
+    /// Consumes bytes from `input` until the block is full. Returns the block if it
+    /// is full, together with the remaining input.
+    fn fill<'a>(&mut self, input: &'a [u8]) -> (Option<[u8; 16]>, &'a [u8]) {
+        let (head, tail) = input.split_at(16_usize.saturating_sub(self.len).min(input.len()));
+
+        let end = self
+            .len
+            .checked_add(head.len())
+            .expect("buffer holds at most 16 bytes");
+        self.block[self.len..end].copy_from_slice(head);
+        self.len = end;
+
+        if self.len == 16 {
+            self.len = 0;
+            (Some(self.block), tail)
+        } else {
+            (None, tail)
+        }
+    }
+
+    fn buffered(&self) -> &[u8] {
+        &self.block[..self.len]
+    }
+}
+
+/// Encrypts all data written to it and writes the ciphertext to the inner writer.
+///
+/// [`Encryptor::finish()`] has to be called after the last write, to add the padding
+/// and write the final block.
+pub struct Encryptor<W: Write> {
+    inner: W,
+    round_keys: AnyRoundKeys,
+    chain: Chain,
+    padding: Padding,
+    buffer: BlockBuffer,
+}
+
+impl<W: Write> Encryptor<W> {
+    /// Starts encrypting with `key`, which may be of any size
+    pub fn new(inner: W, key: impl Into<AnyKey>, mode: Mode, padding: Padding) -> Self {
+        Self {
+            inner,
+            round_keys: key.into().expand(),
+            chain: Chain::new(mode),
+            padding,
+            buffer: BlockBuffer::new(),
+        }
+    }
+
+    /// Writes the final block and returns the inner writer.
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`] if no padding is used and the input
+    /// was not block-aligned.
+    pub fn finish(mut self) -> io::Result<W> {
+        match self.padding {
+            Padding::Pkcs7 => {
+                let mut last = self.buffer.buffered().to_vec();
+                last.extend(pkcs7::padding(last.len(), 16));
+
+                let block = last.try_into().expect("padded block has 16 bytes");
+                self.inner
+                    .write_all(&self.chain.encrypt(&self.round_keys, block))?;
+            }
+            Padding::None => {
+                if !self.buffer.buffered().is_empty() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "input length needs to be a multiple of 16",
+                    ));
+                }
+            }
+        }
+
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for Encryptor<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut output = Vec::with_capacity(buf.len().saturating_add(16));
+
+        let mut input = buf;
+        while !input.is_empty() {
+            let (block, rest) = self.buffer.fill(input);
+            if let Some(block) = block {
+                output.extend_from_slice(&self.chain.encrypt(&self.round_keys, block));
+            }
+            input = rest;
+        }
+
+        self.inner.write_all(&output)?;
+        Ok(buf.len())
+    }
+
+    /// Flushes the inner writer. Data of an incomplete block stays buffered until
+    /// more data or [`Encryptor::finish()`] completes it.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// The number of bytes a [`Decryptor`] requests from the inner reader at once
+const CHUNK_SIZE: usize = 8192;
+
+/// Reads ciphertext from the inner reader and returns the decrypted data.
+///
+/// With padding, the last decrypted block is held back until the end of the input is
+/// reached, so the padding can be removed.
+pub struct Decryptor<R: Read> {
+    inner: R,
+    round_keys: AnyRoundKeys,
+    chain: Chain,
+    padding: Padding,
+    buffer: BlockBuffer,
+    /// The last decrypted block, which may contain padding
+    pending: Option<[u8; 16]>,
+    /// Decrypted data that is ready to be returned, starting at `position`
+    output: Vec<u8>,
+    position: usize,
+    eof: bool,
+}
+
+impl<R: Read> Decryptor<R> {
+    /// Starts decrypting with `key`, which may be of any size
+    pub fn new(inner: R, key: impl Into<AnyKey>, mode: Mode, padding: Padding) -> Self {
+        Self {
+            inner,
+            round_keys: key.into().expand(),
+            chain: Chain::new(mode),
+            padding,
+            buffer: BlockBuffer::new(),
+            pending: None,
+            output: Vec::new(),
+            position: 0,
+            eof: false,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Reads and decrypts the next chunk of input into `self.output`
+    fn decrypt_chunk(&mut self) -> io::Result<()> {
+        let mut chunk = [0; CHUNK_SIZE];
+        let len = self.inner.read(&mut chunk)?;
+
+        self.output.clear();
+        self.position = 0;
+
+        if len == 0 {
+            self.eof = true;
+            return self.finish();
+        }
+
+        let mut input = &chunk[..len];
+        while !input.is_empty() {
+            let (block, rest) = self.buffer.fill(input);
+            if let Some(block) = block {
+                let decrypted = self.chain.decrypt(&self.round_keys, block);
+                match self.padding {
+                    Padding::Pkcs7 => {
+                        if let Some(previous) = self.pending.replace(decrypted) {
+                            self.output.extend_from_slice(&previous);
+                        }
+                    }
+                    Padding::None => self.output.extend_from_slice(&decrypted),
+                }
+            }
+            input = rest;
+        }
+
+        Ok(())
+    }
+
+    /// Checks the end of the input and removes the padding
+    fn finish(&mut self) -> io::Result<()> {
+        if !self.buffer.buffered().is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "ciphertext length needs to be a multiple of 16",
+            ));
+        }
+
+        if self.padding == Padding::Pkcs7 {
+            let last = self.pending.take().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::UnexpectedEof, "ciphertext is empty")
+            })?;
+
+            let unpadded = pkcs7::unpad(&last, 16)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            self.output.extend_from_slice(unpadded);
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Decryptor<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let available = &self.output[self.position..];
+            if !available.is_empty() {
+                let len = available.len().min(buf.len());
+                buf[..len].copy_from_slice(&available[..len]);
+                self.position = self.position.saturating_add(len);
+                return Ok(len);
+            }
+
+            if self.eof || buf.is_empty() {
+                return Ok(0);
+            }
+
+            self.decrypt_chunk()?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes::{cbc, decrypt_ecb, ige, pcbc, Key128};
+
+    fn key() -> Key128 {
+        Key128::from_bytes(*b"YELLOW SUBMARINE")
+    }
+
+    const MODES: [Mode; 4] = [
+        Mode::Ecb,
+        Mode::Cbc([0x01; 16]),
+        Mode::Pcbc([0x02; 16]),
+        Mode::Ige([0x03; 32]),
+    ];
+
+    fn input(len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|i| u8::try_from(i % 251).expect("value is smaller than 251"))
+            .collect()
+    }
+
+    fn encrypt_all(mode: Mode, padding: Padding, input: &[u8], chunk_size: usize) -> Vec<u8> {
+        let mut encryptor = Encryptor::new(Vec::new(), key(), mode, padding);
+        for chunk in input.chunks(chunk_size) {
+            encryptor
+                .write_all(chunk)
+                .expect("writing to a vec succeeds");
+        }
+        encryptor.finish().expect("input is valid")
+    }
+
+    fn decrypt_all(mode: Mode, padding: Padding, input: &[u8]) -> io::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        Decryptor::new(input, key(), mode, padding).read_to_end(&mut output)?;
+        Ok(output)
+    }
+
+    #[test]
+    fn test_matches_slice_functions() {
+        let padded = pkcs7::pad(&input(100), 16);
+        let ciphertext = |mode| encrypt_all(mode, Padding::Pkcs7, &input(100), 7);
+
+        assert_eq!(decrypt_ecb(&ciphertext(Mode::Ecb), key()), padded);
+        assert_eq!(
+            ciphertext(Mode::Cbc([0x01; 16])),
+            cbc::encrypt(&padded, key(), [0x01; 16])
+        );
+        assert_eq!(
+            ciphertext(Mode::Pcbc([0x02; 16])),
+            pcbc::encrypt(&padded, key(), [0x02; 16])
+        );
+        assert_eq!(
+            ciphertext(Mode::Ige([0x03; 32])),
+            ige::encrypt(&padded, key(), [0x03; 32])
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        for mode in MODES {
+            for len in [0, 1, 15, 16, 17, 32, 1000, 20000] {
+                for chunk_size in [1, 5, 16, 4096] {
+                    let ciphertext = encrypt_all(mode, Padding::Pkcs7, &input(len), chunk_size);
+                    assert_eq!(
+                        ciphertext.len(),
+                        pkcs7::pad(&input(len), 16).len(),
+                        "a full block of padding is added to aligned input"
+                    );
+                    assert_eq!(
+                        decrypt_all(mode, Padding::Pkcs7, &ciphertext).expect("padding is valid"),
+                        input(len)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_small_reads() {
+        let ciphertext = encrypt_all(Mode::Cbc([0; 16]), Padding::Pkcs7, &input(50), 50);
+
+        let mut decryptor = Decryptor::new(
+            ciphertext.as_slice(),
+            key(),
+            Mode::Cbc([0; 16]),
+            Padding::Pkcs7,
+        );
+        let mut output = Vec::new();
+        let mut buf = [0; 3];
+        loop {
+            let len = decryptor.read(&mut buf).expect("padding is valid");
+            if len == 0 {
+                break;
+            }
+            output.extend_from_slice(&buf[..len]);
+        }
+
+        assert_eq!(output, input(50));
+    }
+
+    #[test]
+    fn test_without_padding() {
+        for mode in MODES {
+            let ciphertext = encrypt_all(mode, Padding::None, &input(64), 10);
+            assert_eq!(ciphertext.len(), 64);
+            assert_eq!(
+                decrypt_all(mode, Padding::None, &ciphertext).expect("input is aligned"),
+                input(64)
+            );
+        }
+
+        let mut encryptor = Encryptor::new(Vec::new(), key(), Mode::Ecb, Padding::None);
+        encryptor
+            .write_all(&input(20))
+            .expect("writing to a vec succeeds");
+        assert_eq!(
+            encryptor.finish().map_err(|e| e.kind()),
+            Err(io::ErrorKind::InvalidInput)
+        );
+    }
+
+    #[test]
+    fn test_invalid_ciphertext() {
+        let ciphertext = encrypt_all(Mode::Cbc([0; 16]), Padding::Pkcs7, &input(40), 40);
+
+        assert_eq!(
+            decrypt_all(Mode::Cbc([0; 16]), Padding::Pkcs7, &ciphertext[..40])
+                .map_err(|e| e.kind()),
+            Err(io::ErrorKind::UnexpectedEof)
+        );
+        assert_eq!(
+            decrypt_all(Mode::Cbc([0; 16]), Padding::Pkcs7, &[]).map_err(|e| e.kind()),
+            Err(io::ErrorKind::UnexpectedEof)
+        );
+
+        // decrypting with the wrong key garbles the padding
+        let ciphertext = encrypt_all(Mode::Ecb, Padding::Pkcs7, b"attack at dawn", 16);
+        let mut output = Vec::new();
+        let result = Decryptor::new(
+            ciphertext.as_slice(),
+            Key128::from_bytes(*b"PURPLE SUBMARINE"),
+            Mode::Ecb,
+            Padding::Pkcs7,
+        )
+        .read_to_end(&mut output);
+        assert_eq!(
+            result.map_err(|e| e.kind()),
+            Err(io::ErrorKind::InvalidData)
+        );
+    }
+}
@@ -0,0 +1,352 @@
+#![expect(
+    clippy::little_endian_bytes,
+    reason = "XTS defines the tweak as a little-endian number"
+)]
+//! XEX-based tweaked-codebook mode with ciphertext stealing (XTS)
+//!
+//! XTS is used for disk encryption: Each sector (called "data unit" in the standard)
+//! is encrypted independently, with a tweak derived from its sector number. Sectors
+//! that are not a multiple of the block size are handled with ciphertext stealing,
+//! so the ciphertext is always as long as the plaintext.
+//!
+//! <https://ieeexplore.ieee.org/document/4493450>
+//! <https://nvlpubs.nist.gov/nistpubs/Legacy/SP/nistspecialpublication800-38e.pdf>
+//!
+//! The key consists of two halves: `data_key` encrypts the data, `tweak_key` encrypts
+//! the sector number.
+
+use super::{cipher, gf128, inv_cipher, key::RoundKeys128, Key128};
+
+#[derive(Clone, Copy)]
+enum Direction {
+    Encrypt,
+    Decrypt,
+}
+
+/// Processes a single block with the tweak `t`, i.e. `E(P ⊕ T) ⊕ T`
+fn process_block(
+    data_keys: &RoundKeys128,
+    direction: Direction,
+    t: u128,
+    input: [u8; 16],
+) -> [u8; 16] {
+    let input = gf128::add(u128::from_le_bytes(input), t).to_le_bytes();
+
+    let output = match direction {
+        Direction::Encrypt => cipher(input, data_keys),
+        Direction::Decrypt => inv_cipher(input, data_keys),
+    };
+
+    gf128::add(u128::from_le_bytes(output), t).to_le_bytes()
+}
+
+fn process_sector(
+    data_keys: &RoundKeys128,
+    tweak_keys: &RoundKeys128,
+    direction: Direction,
+    sector: u128,
+    input: &[u8],
+) -> Vec<u8> {
+    assert!(input.len() >= 16, "sectors must be at least 16 bytes long");
+
+    // the tweak for block j is E(sector) · αʲ, where multiplying by α is a doubling
+    let mut t = u128::from_le_bytes(cipher(sector.to_le_bytes(), tweak_keys));
+
+    let mut output = Vec::with_capacity(input.len());
+
+    // When ciphertext stealing is required, the last two blocks are handled separately
+    let stolen = input.len() % 16;
+    let regular = if stolen == 0 {
+        input.len()
+    } else {
+        input.len().saturating_sub(16).saturating_sub(stolen)
+    };
+
+    for chunk in input[..regular].chunks(16) {
+        let block = chunk.try_into().expect("chunk is 16 bytes long");
+        output.extend_from_slice(&process_block(data_keys, direction, t, block));
+        t = gf128::dbl(t);
+    }
+
+    if stolen != 0 {
+        let (last_full, partial) = input[regular..].split_at(16);
+        let last_full: [u8; 16] = last_full.try_into().expect("split at 16 bytes");
+
+        // When decrypting, the second to last block was encrypted with the tweak of
+        // the last block, so the order of tweaks is swapped
+        let (first_tweak, second_tweak) = match direction {
+            Direction::Encrypt => (t, gf128::dbl(t)),
+            Direction::Decrypt => (gf128::dbl(t), t),
+        };
+
+        let intermediate = process_block(data_keys, direction, first_tweak, last_full);
+
+        let mut combined = intermediate;
+        combined[..stolen].copy_from_slice(partial);
+
+        output.extend_from_slice(&process_block(data_keys, direction, second_tweak, combined));
+        output.extend_from_slice(&intermediate[..stolen]);
+    }
+
+    output
+}
+
+fn process_sectors(
+    data_key: Key128,
+    tweak_key: Key128,
+    direction: Direction,
+    first_sector: u128,
+    sector_size: usize,
+    input: &[u8],
+) -> Vec<u8> {
+    assert!(sector_size >= 16, "sectors must be at least 16 bytes long");
+    assert!(
+        input.len().is_multiple_of(sector_size),
+        "input length needs to be a multiple of the sector size"
+    );
+
+    let data_keys = data_key.expand();
+    let tweak_keys = tweak_key.expand();
+
+    input
+        .chunks(sector_size)
+        .zip(first_sector..)
+        .flat_map(|(chunk, sector)| {
+            process_sector(&data_keys, &tweak_keys, direction, sector, chunk)
+        })
+        .collect()
+}
+
+/// Encrypts a single sector. The sector may be of any size of at least 16 bytes.
+pub fn encrypt_sector(
+    data_key: Key128,
+    tweak_key: Key128,
+    sector: u128,
+    plaintext: &[u8],
+) -> Vec<u8> {
+    process_sector(
+        &data_key.expand(),
+        &tweak_key.expand(),
+        Direction::Encrypt,
+        sector,
+        plaintext,
+    )
+}
+
+/// Decrypts a single sector. The sector may be of any size of at least 16 bytes.
+pub fn decrypt_sector(
+    data_key: Key128,
+    tweak_key: Key128,
+    sector: u128,
+    ciphertext: &[u8],
+) -> Vec<u8> {
+    process_sector(
+        &data_key.expand(),
+        &tweak_key.expand(),
+        Direction::Decrypt,
+        sector,
+        ciphertext,
+    )
+}
+
+/// Encrypts consecutive sectors of `sector_size` bytes, starting at `first_sector`.
+pub fn encrypt_sectors(
+    data_key: Key128,
+    tweak_key: Key128,
+    first_sector: u128,
+    sector_size: usize,
+    plaintext: &[u8],
+) -> Vec<u8> {
+    process_sectors(
+        data_key,
+        tweak_key,
+        Direction::Encrypt,
+        first_sector,
+        sector_size,
+        plaintext,
+    )
+}
+
+/// Decrypts consecutive sectors of `sector_size` bytes, starting at `first_sector`.
+///
+/// To decrypt part of a disk image, pass the data starting at a sector boundary, and
+/// `offset / sector_size` as the first sector (see [`sector_at()`]).
+pub fn decrypt_sectors(
+    data_key: Key128,
+    tweak_key: Key128,
+    first_sector: u128,
+    sector_size: usize,
+    ciphertext: &[u8],
+) -> Vec<u8> {
+    process_sectors(
+        data_key,
+        tweak_key,
+        Direction::Decrypt,
+        first_sector,
+        sector_size,
+        ciphertext,
+    )
+}
+
+/// Returns the sector containing byte `offset`, and the position of `offset` within
+/// that sector
+pub fn sector_at(offset: u64, sector_size: usize) -> (u128, usize) {
+    let sector_size = u64::try_from(sector_size).expect("usize fits into u64");
+    assert!(sector_size != 0, "sector size cannot be 0");
+
+    #[expect(clippy::integer_division, reason = "rounding down is intended")]
+    #[expect(
+        clippy::arithmetic_side_effects,
+        reason = "sector size is checked to be non-zero"
+    )]
+    (
+        u128::from(offset / sector_size),
+        usize::try_from(offset % sector_size).expect("remainder is smaller than usize"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{aes::parse_key, hex::parse};
+
+    struct TestCase {
+        data_key: &'static str,
+        tweak_key: &'static str,
+        sector: u128,
+        plaintext: &'static str,
+        ciphertext: &'static str,
+    }
+
+    /// Vectors 1 to 3 and 15 to 18 from IEEE 1619. The latter require ciphertext
+    /// stealing.
+    const IEEE_1619_VECTORS: [TestCase; 7] = [
+        TestCase {
+            data_key: "00000000000000000000000000000000",
+            tweak_key: "00000000000000000000000000000000",
+            sector: 0,
+            plaintext: "0000000000000000000000000000000000000000000000000000000000000000",
+            ciphertext: "917cf69ebd68b2ec9b9fe9a3eadda692cd43d2f59598ed858c02c2652fbf922e",
+        },
+        TestCase {
+            data_key: "11111111111111111111111111111111",
+            tweak_key: "22222222222222222222222222222222",
+            sector: 0x33_3333_3333,
+            plaintext: "4444444444444444444444444444444444444444444444444444444444444444",
+            ciphertext: "c454185e6a16936e39334038acef838bfb186fff7480adc4289382ecd6d394f0",
+        },
+        TestCase {
+            data_key: "fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0",
+            tweak_key: "22222222222222222222222222222222",
+            sector: 0x33_3333_3333,
+            plaintext: "4444444444444444444444444444444444444444444444444444444444444444",
+            ciphertext: "af85336b597afc1a900b2eb21ec949d292df4c047e0b21532186a5971a227a89",
+        },
+        TestCase {
+            data_key: "fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0",
+            tweak_key: "bfbebdbcbbbab9b8b7b6b5b4b3b2b1b0",
+            sector: 0x12_3456_789a,
+            plaintext: "000102030405060708090a0b0c0d0e0f10",
+            ciphertext: "6c1625db4671522d3d7599601de7ca09ed",
+        },
+        TestCase {
+            data_key: "fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0",
+            tweak_key: "bfbebdbcbbbab9b8b7b6b5b4b3b2b1b0",
+            sector: 0x12_3456_789a,
+            plaintext: "000102030405060708090a0b0c0d0e0f1011",
+            ciphertext: "d069444b7a7e0cab09e24447d24deb1fedbf",
+        },
+        TestCase {
+            data_key: "fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0",
+            tweak_key: "bfbebdbcbbbab9b8b7b6b5b4b3b2b1b0",
+            sector: 0x12_3456_789a,
+            plaintext: "000102030405060708090a0b0c0d0e0f101112",
+            ciphertext: "e5df1351c0544ba1350b3363cd8ef4beedbf9d",
+        },
+        TestCase {
+            data_key: "fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0",
+            tweak_key: "bfbebdbcbbbab9b8b7b6b5b4b3b2b1b0",
+            sector: 0x12_3456_789a,
+            plaintext: "000102030405060708090a0b0c0d0e0f10111213",
+            ciphertext: "9d84c813f719aa2c7be3f66171c7c5c2edbf9dac",
+        },
+    ];
+
+    #[test]
+    fn test_encrypt_sector_from_standard() {
+        for case in IEEE_1619_VECTORS {
+            assert_eq!(
+                encrypt_sector(
+                    parse_key(case.data_key),
+                    parse_key(case.tweak_key),
+                    case.sector,
+                    &parse(case.plaintext)
+                ),
+                parse(case.ciphertext)
+            );
+        }
+    }
+
+    #[test]
+    fn test_decrypt_sector_from_standard() {
+        for case in IEEE_1619_VECTORS {
+            assert_eq!(
+                decrypt_sector(
+                    parse_key(case.data_key),
+                    parse_key(case.tweak_key),
+                    case.sector,
+                    &parse(case.ciphertext)
+                ),
+                parse(case.plaintext)
+            );
+        }
+    }
+
+    #[test]
+    fn test_sectors_at_offset() {
+        let data_key = "27182818284590452353602874713526";
+        let tweak_key = "31415926535897932384626433832795";
+        let sector_size = 512;
+
+        let plaintext: Vec<u8> = (0..4 * sector_size)
+            .map(|i| u8::try_from(i % 251).expect("value is smaller than 251"))
+            .collect();
+
+        let image = encrypt_sectors(
+            parse_key(data_key),
+            parse_key(tweak_key),
+            0,
+            sector_size,
+            &plaintext,
+        );
+
+        // each sector is encrypted independently
+        for sector in 0..4 {
+            let range = sector * sector_size..(sector + 1) * sector_size;
+            assert_eq!(
+                encrypt_sector(
+                    parse_key(data_key),
+                    parse_key(tweak_key),
+                    u128::try_from(sector).expect("sector is small"),
+                    &plaintext[range.clone()]
+                ),
+                image[range]
+            );
+        }
+
+        // decrypt only the third and fourth sector, located via a byte offset
+        let (first_sector, position) = sector_at(1030, sector_size);
+        assert_eq!((first_sector, position), (2, 6));
+
+        assert_eq!(
+            decrypt_sectors(
+                parse_key(data_key),
+                parse_key(tweak_key),
+                first_sector,
+                sector_size,
+                &image[1024..]
+            ),
+            plaintext[1024..]
+        );
+    }
+}
@@ -0,0 +1,383 @@
+//! Byte-at-a-time ECB decryption
+//!
+//! An oracle that appends a secret to the attacker's input and encrypts the result
+//! with ECB leaks the secret one byte at a time. With a filler one byte shorter than
+//! a block, the first block ends with the first byte of the secret. Its ciphertext
+//! matches exactly one of the 256 blocks made of the filler and a candidate byte.
+//! Shortening the filler moves the next byte into place, with the bytes recovered so
+//! far completing the candidate blocks.
+//!
+//! <https://www.cryptopals.com/sets/2/challenges/12>
+//!
+//! [`recover_secret_with_prefix()`] attacks oracles that also prepend a prefix of
+//! unknown length, which may change on every query. Each input starts with two
+//! blocks of a sentinel byte, and the attack retries with more filler in front of them
+//! until they show up as two equal ciphertext blocks. The input after them is then
+//! block-aligned, so the rest of the ciphertext is what an oracle without prefix would
+//! return.
+//!
+//! <https://www.cryptopals.com/sets/2/challenges/14>
+
+use std::cell::{Cell, RefCell};
+
+use super::{ecb_detection, Error, Oracle};
+
+/// The largest block size that is detected
+const MAX_BLOCK_SIZE: usize = 64;
+
+/// The byte used for the attacker's input
+const FILLER: u8 = b'A';
+
+/// The byte of the blocks that mark the start of the attacker's input
+const SENTINEL: u8 = 0xa5;
+
+/// How often a query is retried per possible alignment before giving up
+const ATTEMPTS_PER_ALIGNMENT: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recovery {
+    pub secret: Vec<u8>,
+    pub block_size: usize,
+    /// the number of times the oracle was called
+    pub queries: usize,
+}
+
+fn block(ciphertext: &[u8], block_size: usize, index: usize) -> Option<&[u8]> {
+    let start = index.checked_mul(block_size)?;
+    ciphertext.get(start..start.checked_add(block_size)?)
+}
+
+/// Feeds the oracle growing input until its output grows by a block. Returns the
+/// block size and the length of the secret.
+fn discover_block_size<F: Fn(&[u8]) -> Vec<u8>>(
+    oracle: &mut Oracle<F>,
+) -> Result<(usize, usize), Error> {
+    let initial_len = oracle.query(&[]).len();
+
+    for filler_len in 1..=MAX_BLOCK_SIZE {
+        let len = oracle.query(&vec![FILLER; filler_len]).len();
+        if len > initial_len {
+            // the padding of the last block just got pushed into a new block, so the
+            // input exactly filled up the last block
+            let block_size = len.saturating_sub(initial_len);
+            let unknown_len = initial_len
+                .checked_sub(filler_len)
+                .ok_or(Error::BlockSizeNotFound)?;
+            return Ok((block_size, unknown_len));
+        }
+    }
+
+    Err(Error::BlockSizeNotFound)
+}
+
+/// Checks that three blocks of filler produce repeated ciphertext blocks
+fn confirm_ecb<F: Fn(&[u8]) -> Vec<u8>>(
+    oracle: &mut Oracle<F>,
+    block_size: usize,
+) -> Result<(), Error> {
+    let ciphertext = oracle.query(&vec![FILLER; block_size.saturating_mul(3)]);
+
+    if ecb_detection::score(&ciphertext).repeated_blocks > 0 {
+        Ok(())
+    } else {
+        Err(Error::NotEcb)
+    }
+}
+
+/// Recovers the secret byte by byte, stopping before the padding
+fn recover<F: Fn(&[u8]) -> Vec<u8>>(
+    oracle: &mut Oracle<F>,
+    block_size: usize,
+    secret_len: usize,
+) -> Result<Vec<u8>, Error> {
+    let mut secret = Vec::with_capacity(secret_len);
+
+    for position in 0..secret_len {
+        let offset = position
+            .checked_rem(block_size)
+            .expect("block size is not 0");
+        let filler_len = block_size.saturating_sub(1).saturating_sub(offset);
+
+        let ciphertext = oracle.query(&vec![FILLER; filler_len]);
+        let target_block = position
+            .checked_div(block_size)
+            .expect("block size is not 0");
+        let target = block(&ciphertext, block_size, target_block)
+            .ok_or(Error::NoMatch)?
+            .to_vec();
+
+        // the block before the unknown byte, made of filler and the known secret
+        let known: Vec<u8> = std::iter::repeat_n(FILLER, block_size.saturating_sub(1))
+            .chain(secret.iter().copied())
+            .collect();
+        let mut candidate = known
+            .get(known.len().saturating_sub(block_size.saturating_sub(1))..)
+            .expect("known bytes include a block of filler")
+            .to_vec();
+        candidate.push(0);
+
+        let byte = (0..=u8::MAX)
+            .find(|byte| {
+                *candidate.last_mut().expect("candidate is not empty") = *byte;
+                block(&oracle.query(&candidate), block_size, 0) == Some(&target)
+            })
+            .ok_or(Error::NoMatch)?;
+        secret.push(byte);
+    }
+
+    Ok(secret)
+}
+
+/// Recovers the secret that `oracle` appends to its input before encrypting it with
+/// ECB and PKCS#7 padding
+pub fn recover_secret(oracle: impl Fn(&[u8]) -> Vec<u8>) -> Result<Recovery, Error> {
+    let mut oracle = Oracle::new(oracle);
+
+    let (block_size, secret_len) = discover_block_size(&mut oracle)?;
+    confirm_ecb(&mut oracle, block_size)?;
+
+    let secret = recover(&mut oracle, block_size, secret_len)?;
+
+    Ok(Recovery {
+        secret,
+        block_size,
+        queries: oracle.queries,
+    })
+}
+
+const fn gcd(a: usize, b: usize) -> usize {
+    match b.checked_rem(a) {
+        Some(rem) => gcd(rem, a),
+        None => b,
+    }
+}
+
+/// The block size is the greatest common divisor of the output lengths, as
+/// the varying prefix makes it impossible to rely on a single jump in length
+fn discover_block_size_with_prefix<F: Fn(&[u8]) -> Vec<u8>>(
+    oracle: &mut Oracle<F>,
+) -> Result<usize, Error> {
+    let block_size = (0..=MAX_BLOCK_SIZE)
+        .map(|filler_len| oracle.query(&vec![FILLER; filler_len]).len())
+        .fold(0, gcd);
+
+    if (2..=MAX_BLOCK_SIZE).contains(&block_size) {
+        Ok(block_size)
+    } else {
+        Err(Error::BlockSizeNotFound)
+    }
+}
+
+/// Finds the ciphertext of a sentinel block. Three blocks of sentinel bytes fill at
+/// least two blocks regardless of the prefix, and the first two equal consecutive
+/// blocks are taken to be those.
+fn encrypt_sentinel<F: Fn(&[u8]) -> Vec<u8>>(
+    oracle: &mut Oracle<F>,
+    block_size: usize,
+) -> Result<Vec<u8>, Error> {
+    let ciphertext = oracle.query(&vec![SENTINEL; block_size.saturating_mul(3)]);
+
+    let blocks: Vec<&[u8]> = ciphertext.chunks_exact(block_size).collect();
+    blocks
+        .iter()
+        .zip(blocks.iter().skip(1))
+        .find(|&(a, b)| a == b)
+        .map(|(a, _)| a.to_vec())
+        .ok_or(Error::NotEcb)
+}
+
+/// Turns an oracle with a prefix into one without, see the module documentation
+struct Aligned<F> {
+    oracle: RefCell<Oracle<F>>,
+    block_size: usize,
+    /// the ciphertext of a block of sentinel bytes
+    sentinel: Vec<u8>,
+    /// the filler length that aligned the sentinel blocks last time
+    alignment: Cell<usize>,
+    /// the first error, as the attack only sees the output of [`Aligned::query()`]
+    error: Cell<Option<Error>>,
+}
+
+impl<F: Fn(&[u8]) -> Vec<u8>> Aligned<F> {
+    fn query(&self, input: &[u8]) -> Vec<u8> {
+        let attempts = self.block_size.saturating_mul(ATTEMPTS_PER_ALIGNMENT);
+
+        for _ in 0..attempts {
+            let alignment = self.alignment.get();
+
+            let mut aligned_input = vec![FILLER; alignment];
+            aligned_input.resize(
+                alignment.saturating_add(self.block_size.saturating_mul(2)),
+                SENTINEL,
+            );
+            aligned_input.extend_from_slice(input);
+            let ciphertext = self.oracle.borrow_mut().query(&aligned_input);
+
+            let blocks: Vec<&[u8]> = ciphertext.chunks_exact(self.block_size).collect();
+            let start = blocks
+                .iter()
+                .zip(blocks.iter().skip(1))
+                .position(|(a, b)| *a == self.sentinel && *b == self.sentinel);
+
+            if let Some(start) = start {
+                let skip = start.saturating_add(2).saturating_mul(self.block_size);
+                return ciphertext.get(skip..).unwrap_or_default().to_vec();
+            }
+
+            self.alignment.set(
+                alignment
+                    .saturating_add(1)
+                    .checked_rem(self.block_size)
+                    .expect("block size is not 0"),
+            );
+        }
+
+        self.error
+            .set(self.error.get().or(Some(Error::AlignmentNotFound)));
+        Vec::new()
+    }
+}
+
+/// Recovers the secret that `oracle` appends to its input before encrypting it with
+/// ECB and PKCS#7 padding, when it also prepends a prefix of unknown and possibly
+/// varying length
+pub fn recover_secret_with_prefix(oracle: impl Fn(&[u8]) -> Vec<u8>) -> Result<Recovery, Error> {
+    let mut oracle = Oracle::new(oracle);
+
+    let block_size = discover_block_size_with_prefix(&mut oracle)?;
+    let sentinel = encrypt_sentinel(&mut oracle, block_size)?;
+
+    let aligned = Aligned {
+        oracle: RefCell::new(oracle),
+        block_size,
+        sentinel,
+        alignment: Cell::new(0),
+        error: Cell::new(None),
+    };
+    let mut aligned_oracle = Oracle::new(|input: &[u8]| aligned.query(input));
+
+    let secret = discover_block_size(&mut aligned_oracle)
+        .and_then(|(_, secret_len)| recover(&mut aligned_oracle, block_size, secret_len));
+    if let Some(error) = aligned.error.get() {
+        return Err(error);
+    }
+
+    Ok(Recovery {
+        secret: secret?,
+        block_size,
+        queries: aligned.oracle.into_inner().queries,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{aes, base64, pkcs7, random::Random};
+
+    const SECRET: &str = "Um9sbGluJyBpbiBteSA1LjAKV2l0aCBteSByYWctdG9wIGRvd24gc28gbXkgaGFpciBjYW4gYmxvdwpUaGUgZ2lybGllcyBvbiBzdGFuZGJ5IHdhdmluZyBqdXN0IHRvIHNheSBoaQpEaWQgeW91IHN0b3A/IE5vLCBJIGp1c3QgZHJvdmUgYnkK";
+
+    fn oracle(secret: &[u8]) -> impl Fn(&[u8]) -> Vec<u8> + '_ {
+        let key = Random::from_seed([3; 32]).bytes();
+
+        move |input| {
+            let mut plaintext = input.to_vec();
+            plaintext.extend_from_slice(secret);
+            aes::encrypt_ecb(&pkcs7::pad(&plaintext, 16), aes::Key128::from_bytes(key))
+        }
+    }
+
+    #[test]
+    /// taken from cryptopals challenge 12
+    fn test_recover_secret() {
+        let secret = base64::decode_str(SECRET).expect("test data is valid base64");
+        let recovery = recover_secret(oracle(&secret)).expect("oracle uses ECB");
+
+        assert_eq!(recovery.secret, secret);
+        assert_eq!(recovery.block_size, 16);
+        assert!(recovery.queries < secret.len().saturating_mul(258));
+    }
+
+    #[test]
+    fn test_secret_lengths() {
+        for len in [0, 1, 15, 16, 17, 32] {
+            let secret = vec![0x55; len];
+            let recovery = recover_secret(oracle(&secret)).expect("oracle uses ECB");
+            assert_eq!(recovery.secret, secret);
+        }
+    }
+
+    #[test]
+    fn test_not_ecb() {
+        let oracle = |input: &[u8]| {
+            let mut plaintext = input.to_vec();
+            plaintext.extend_from_slice(b"secret");
+            aes::cbc::encrypt(
+                &pkcs7::pad(&plaintext, 16),
+                aes::Key128::from_bytes([0; 16]),
+                [0; 16],
+            )
+        };
+        assert_eq!(recover_secret(oracle), Err(Error::NotEcb));
+
+        assert_eq!(recover_secret_with_prefix(oracle), Err(Error::NotEcb));
+
+        assert_eq!(
+            recover_secret(|input: &[u8]| input.to_vec()),
+            Err(Error::BlockSizeNotFound)
+        );
+        assert_eq!(
+            recover_secret_with_prefix(|input: &[u8]| input.to_vec()),
+            Err(Error::BlockSizeNotFound)
+        );
+    }
+
+    fn oracle_with_prefix<'a>(
+        secret: &'a [u8],
+        prefix: impl Fn() -> Vec<u8> + 'a,
+    ) -> impl Fn(&[u8]) -> Vec<u8> + 'a {
+        let key = Random::from_seed([4; 32]).bytes();
+
+        move |input| {
+            let mut plaintext = prefix();
+            plaintext.extend_from_slice(input);
+            plaintext.extend_from_slice(secret);
+            aes::encrypt_ecb(&pkcs7::pad(&plaintext, 16), aes::Key128::from_bytes(key))
+        }
+    }
+
+    #[test]
+    fn test_recover_secret_with_fixed_prefix() {
+        let secret = b"Rollin' in my 5.0\nWith my rag-top down";
+        let mut random = Random::from_seed([5; 32]);
+
+        for _ in 0..3_u8 {
+            let mut prefix = vec![0; random.range(1..=40)];
+            random.fill(&mut prefix);
+
+            let recovery =
+                recover_secret_with_prefix(oracle_with_prefix(secret, || prefix.clone()))
+                    .expect("oracle uses ECB");
+            assert_eq!(recovery.secret, secret);
+            assert_eq!(recovery.block_size, 16);
+        }
+    }
+
+    #[test]
+    fn test_recover_secret_with_random_prefix() {
+        let secret = b"rag-top down";
+        let random = RefCell::new(Random::from_seed([6; 32]));
+
+        let prefix = || {
+            let mut random = random.borrow_mut();
+            let mut prefix = vec![0; random.range(0..=40)];
+            random.fill(&mut prefix);
+            prefix
+        };
+
+        let recovery = recover_secret_with_prefix(oracle_with_prefix(secret, prefix))
+            .expect("oracle uses ECB");
+        assert_eq!(recovery.secret, secret);
+        // most queries need to be retried until the prefix happens to align them
+        assert!(recovery.queries > secret.len().saturating_mul(256));
+    }
+}
@@ -0,0 +1,185 @@
+//! CBC bitflipping
+//!
+//! CBC XORs each decrypted block with the previous ciphertext block. Flipping a bit
+//! in a ciphertext block flips the same bit in the plaintext of the next block, and
+//! only scrambles the block itself. An attacker who knows part of the plaintext can
+//! so replace it with any text of the same length, like characters a service quotes
+//! in its input.
+//!
+//! <https://www.cryptopals.com/sets/2/challenges/16>
+
+use crate::{
+    aes::{cbc, Key128},
+    pkcs7,
+    random::Random,
+};
+
+const BLOCK_SIZE: usize = 16;
+
+/// The plaintext that makes a comment string grant admin rights
+pub const ADMIN: &[u8] = b";admin=true;";
+
+/// Encrypts comment strings with user data, and checks them for admin rights
+pub struct CommentService {
+    key: [u8; 16],
+    iv: [u8; 16],
+}
+
+impl CommentService {
+    pub const PREFIX: &'static str = "comment1=cooking%20MCs;userdata=";
+    pub const SUFFIX: &'static str = ";comment2=%20like%20a%20pound%20of%20bacon";
+
+    pub fn new(random: &mut Random) -> Self {
+        Self {
+            key: random.bytes(),
+            iv: random.bytes(),
+        }
+    }
+
+    /// Quotes `;` and `=` in `userdata`, so it cannot add fields
+    pub fn encrypt(&self, userdata: &str) -> Vec<u8> {
+        let userdata = userdata.replace(';', "%3B").replace('=', "%3D");
+        let plaintext = [Self::PREFIX, &userdata, Self::SUFFIX].concat();
+
+        cbc::encrypt(
+            &pkcs7::pad(plaintext.as_bytes(), 16),
+            Key128::from_bytes(self.key),
+            self.iv,
+        )
+    }
+
+    /// Whether the plaintext contains [`ADMIN`]. The plaintext is not required to be
+    /// valid UTF-8, as a service that only searches it would not notice.
+    pub fn is_admin(&self, ciphertext: &[u8]) -> bool {
+        if ciphertext.is_empty() || !ciphertext.len().is_multiple_of(BLOCK_SIZE) {
+            return false;
+        }
+
+        let plaintext = cbc::decrypt(ciphertext, Key128::from_bytes(self.key), self.iv);
+        pkcs7::unpad(&plaintext, 16)
+            .is_ok_and(|plaintext| plaintext.windows(ADMIN.len()).any(|w| w == ADMIN))
+    }
+}
+
+/// Changes the plaintext at `offset` from `known` to `target`
+///
+/// The bits that differ are flipped in the previous ciphertext block. Returns `None`
+/// if `offset` is in the first block, which would require changing the IV, if
+/// `target` crosses a block boundary, or if the ciphertext is too short.
+pub fn flip(ciphertext: &[u8], offset: usize, known: &[u8], target: &[u8]) -> Option<Vec<u8>> {
+    assert_eq!(
+        known.len(),
+        target.len(),
+        "known plaintext and target have to be the same size"
+    );
+
+    let block_offset = offset.checked_rem(BLOCK_SIZE).expect("block size is not 0");
+    if block_offset.saturating_add(target.len()) > BLOCK_SIZE {
+        return None;
+    }
+
+    let start = offset.checked_sub(BLOCK_SIZE)?;
+    let mut ciphertext = ciphertext.to_vec();
+    let previous = ciphertext.get_mut(start..start.checked_add(target.len())?)?;
+
+    for ((c, k), t) in previous.iter_mut().zip(known).zip(target) {
+        *c ^= k ^ t;
+    }
+
+    Some(ciphertext)
+}
+
+/// Injects `target` into the plaintext of `encrypt`
+///
+/// `encrypt` places its input at `input_offset` in the plaintext. The input is a
+/// block of filler that gets scrambled, followed by filler that is flipped into the
+/// target.
+pub fn inject(
+    encrypt: impl Fn(&str) -> Vec<u8>,
+    input_offset: usize,
+    target: &[u8],
+) -> Option<Vec<u8>> {
+    const FILLER: u8 = b'A';
+
+    // completes the block the input starts in, so that it is not scrambled
+    let alignment = input_offset
+        .next_multiple_of(BLOCK_SIZE)
+        .saturating_sub(input_offset);
+    let known = vec![FILLER; target.len()];
+
+    let input: String = std::iter::repeat_n(
+        char::from(FILLER),
+        alignment
+            .saturating_add(BLOCK_SIZE)
+            .saturating_add(target.len()),
+    )
+    .collect();
+    let offset = input_offset
+        .saturating_add(alignment)
+        .saturating_add(BLOCK_SIZE);
+
+    flip(&encrypt(&input), offset, &known, target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quoting() {
+        let service = CommentService::new(&mut Random::from_seed([8; 32]));
+
+        assert!(!service.is_admin(&service.encrypt(";admin=true;")));
+        assert!(!service.is_admin(&service.encrypt("")));
+        assert!(!service.is_admin(&[]));
+        assert!(!service.is_admin(&[0; 20]));
+    }
+
+    #[test]
+    fn test_inject_admin() {
+        let service = CommentService::new(&mut Random::from_seed([9; 32]));
+        let ciphertext = inject(
+            |userdata| service.encrypt(userdata),
+            CommentService::PREFIX.len(),
+            ADMIN,
+        )
+        .expect("target fits into a block");
+
+        assert!(service.is_admin(&ciphertext));
+    }
+
+    #[test]
+    fn test_inject_at_offsets() {
+        let mut random = Random::from_seed([10; 32]);
+        let key = random.bytes();
+        let iv = random.bytes();
+
+        for prefix_len in 0..40_usize {
+            for target in [&b"x"[..], b";admin=true;", b"0123456789abcdef"] {
+                let prefix = "p".repeat(prefix_len);
+                let encrypt = |input: &str| {
+                    let plaintext = [prefix.as_str(), input, ";suffix"].concat();
+                    cbc::encrypt(
+                        &pkcs7::pad(plaintext.as_bytes(), 16),
+                        Key128::from_bytes(key),
+                        iv,
+                    )
+                };
+
+                let ciphertext = inject(encrypt, prefix_len, target).expect("target fits");
+                let plaintext = cbc::decrypt(&ciphertext, Key128::from_bytes(key), iv);
+                assert!(plaintext.windows(target.len()).any(|w| w == target));
+            }
+        }
+    }
+
+    #[test]
+    fn test_flip_invalid() {
+        let ciphertext = [0; 48];
+
+        assert_eq!(flip(&ciphertext, 4, b"a", b"b"), None);
+        assert_eq!(flip(&ciphertext, 30, b"abc", b"def"), None);
+        assert_eq!(flip(&ciphertext, 64, b"a", b"b"), None);
+        assert!(flip(&ciphertext, 47, b"a", b"b").is_some());
+    }
+}
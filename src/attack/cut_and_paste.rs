@@ -0,0 +1,116 @@
+//! ECB cut-and-paste
+//!
+//! ECB encrypts every block on its own, so blocks from different ciphertexts under the
+//! same key can be recombined into a new valid ciphertext. An attacker who can only
+//! choose the email of an encrypted [`profile_for()`] forges an admin profile from
+//! two of them: one where the blocks end right before the role value, and one with a
+//! block that holds `admin` and its padding.
+//!
+//! <https://www.cryptopals.com/sets/2/challenges/13>
+
+use crate::{
+    aes::{self, Key128},
+    cookie::{profile_for, Cookie},
+    pkcs7,
+    random::Random,
+    Error,
+};
+
+const BLOCK_SIZE: usize = 16;
+
+/// Encrypts the profiles of users, and reads them back
+pub struct ProfileService {
+    key: [u8; 16],
+}
+
+impl ProfileService {
+    pub fn new(random: &mut Random) -> Self {
+        Self {
+            key: random.bytes(),
+        }
+    }
+
+    pub fn encrypt_profile(&self, email: &str) -> Vec<u8> {
+        let profile = profile_for(email);
+        aes::encrypt_ecb(
+            &pkcs7::pad(profile.as_bytes(), 16),
+            Key128::from_bytes(self.key),
+        )
+    }
+
+    pub fn decrypt_profile(&self, ciphertext: &[u8]) -> Result<Cookie, Error> {
+        if !ciphertext.len().is_multiple_of(BLOCK_SIZE) {
+            return Err(pkcs7::InvalidPadding.into());
+        }
+
+        let plaintext = aes::decrypt_ecb(ciphertext, Key128::from_bytes(self.key));
+        let profile =
+            std::str::from_utf8(pkcs7::unpad(&plaintext, 16)?).map_err(|e| Error(e.to_string()))?;
+        Ok(Cookie::parse(profile)?)
+    }
+}
+
+/// The number of filler bytes that extend `len` to a multiple of the block size
+fn alignment(len: usize) -> usize {
+    len.next_multiple_of(BLOCK_SIZE).saturating_sub(len)
+}
+
+/// Forges a ciphertext of a profile with `role=admin`, given an oracle that encrypts
+/// [`profile_for()`] an email with ECB
+pub fn forge_admin_profile(encrypt_profile: impl Fn(&str) -> Vec<u8>) -> Vec<u8> {
+    // everything except the email and the role value is fixed
+    let empty_profile = profile_for("");
+    let fixed = empty_profile
+        .strip_suffix("user")
+        .expect("profiles end with the role");
+    let before_email = fixed.find('&').expect("the email is followed by the uid");
+    let after_email = fixed.len().saturating_sub(before_email);
+
+    // an email that completes the first block, followed by a block of `admin` with
+    // padding, which only contains characters that are not escaped
+    let mut admin_email = "A".repeat(alignment(before_email));
+    let admin_block = pkcs7::pad(b"admin", 16);
+    admin_email.push_str(std::str::from_utf8(&admin_block).expect("padding is ascii"));
+    let admin_block_index = before_email.div_ceil(BLOCK_SIZE);
+    let ciphertext = encrypt_profile(&admin_email);
+    let admin = ciphertext
+        .chunks_exact(BLOCK_SIZE)
+        .nth(admin_block_index)
+        .expect("ciphertext contains the admin block");
+
+    // an email that moves the role value to the start of a block
+    let email = "A".repeat(alignment(before_email.saturating_add(after_email)));
+    let ciphertext = encrypt_profile(&email);
+    let keep = before_email
+        .saturating_add(email.len())
+        .saturating_add(after_email);
+
+    let mut forged = ciphertext
+        .get(..keep)
+        .expect("role value starts a block")
+        .to_vec();
+    forged.extend_from_slice(admin);
+    forged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forge_admin_profile() {
+        let service = ProfileService::new(&mut Random::from_seed([7; 32]));
+
+        let profile = service
+            .decrypt_profile(&service.encrypt_profile("foo@bar.com&role=admin"))
+            .expect("ciphertext is valid");
+        assert_eq!(profile.get("role"), Some("user"));
+
+        let forged = forge_admin_profile(|email| service.encrypt_profile(email));
+        let profile = service
+            .decrypt_profile(&forged)
+            .expect("forged ciphertext is valid");
+        assert_eq!(profile.get("role"), Some("admin"));
+        assert_eq!(profile.get("uid"), Some("10"));
+    }
+}
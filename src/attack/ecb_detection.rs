@@ -0,0 +1,198 @@
+//! Detecting ECB by its repeated blocks
+//!
+//! ECB encrypts equal plaintext blocks to equal ciphertext blocks, so a ciphertext
+//! with repeated blocks was most likely encrypted with ECB. Ciphertexts without
+//! repetitions are ranked by the smallest Hamming distance between two of their
+//! blocks, which is lower for blocks of similar plaintext.
+//!
+//! <https://www.cryptopals.com/sets/1/challenges/8>
+
+use std::cmp;
+
+use crate::text;
+
+const BLOCK_SIZE: usize = 16;
+
+/// How strongly a ciphertext suggests ECB. Greater scores are more likely ECB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EcbScore {
+    /// the number of blocks that equal an earlier block
+    pub repeated_blocks: usize,
+    /// the smallest Hamming distance in bits between any two blocks, or `None` if the
+    /// ciphertext has less than two blocks
+    pub min_distance: Option<usize>,
+}
+
+impl Ord for EcbScore {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        let distance = |score: &Self| score.min_distance.unwrap_or(usize::MAX);
+
+        self.repeated_blocks
+            .cmp(&other.repeated_blocks)
+            .then_with(|| distance(other).cmp(&distance(self)))
+    }
+}
+
+impl PartialOrd for EcbScore {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Scores the full blocks of `ciphertext`, an incomplete last block is ignored
+pub fn score(ciphertext: &[u8]) -> EcbScore {
+    let blocks: Vec<&[u8]> = ciphertext.chunks_exact(BLOCK_SIZE).collect();
+
+    let mut repeated_blocks = 0_usize;
+    let mut min_distance = None;
+
+    for (i, block) in blocks.iter().enumerate() {
+        if blocks.iter().take(i).any(|earlier| earlier == block) {
+            repeated_blocks = repeated_blocks.saturating_add(1);
+        }
+
+        for other in blocks.iter().skip(i.saturating_add(1)) {
+            let distance = text::hamming_bits(block, other);
+            min_distance = Some(min_distance.map_or(distance, |min: usize| min.min(distance)));
+        }
+    }
+
+    EcbScore {
+        repeated_blocks,
+        min_distance,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Detection {
+    /// the position of the ciphertext in the input
+    pub index: usize,
+    pub score: EcbScore,
+}
+
+/// Splits raw binary `data` into records of `record_size` bytes to be ranked. The
+/// last record is shorter if `data` does not end on a record boundary.
+pub fn split_records(data: &[u8], record_size: usize) -> Vec<&[u8]> {
+    assert!(record_size != 0, "record size cannot be 0");
+    data.chunks(record_size).collect()
+}
+
+/// Scores all `ciphertexts` and returns them ranked, the most likely ECB first
+pub fn rank<'a>(ciphertexts: impl IntoIterator<Item = &'a [u8]>) -> Vec<Detection> {
+    let mut detections: Vec<Detection> = ciphertexts
+        .into_iter()
+        .enumerate()
+        .map(|(index, ciphertext)| Detection {
+            index,
+            score: score(ciphertext),
+        })
+        .collect();
+
+    // the sort is stable, so equal scores stay in input order
+    detections.sort_by_key(|detection| cmp::Reverse(detection.score));
+    detections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes::{self, cbc, Key128};
+
+    const KEY: &[u8; 16] = b"YELLOW SUBMARINE";
+    /// the second half repeats the first one
+    const PLAINTEXT: &[u8; 64] =
+        b"I'm back and I'm ringin' the belI'm back and I'm ringin' the bel";
+
+    fn encrypt_ecb(plaintext: &[u8]) -> Vec<u8> {
+        aes::encrypt_ecb(plaintext, Key128::from_bytes(*KEY))
+    }
+
+    #[test]
+    fn test_score() {
+        assert_eq!(score(&encrypt_ecb(PLAINTEXT)).repeated_blocks, 2);
+        assert_eq!(score(&encrypt_ecb(PLAINTEXT)).min_distance, Some(0));
+
+        let ciphertext = cbc::encrypt(PLAINTEXT, Key128::from_bytes(*KEY), [0; 16]);
+        assert_eq!(score(&ciphertext).repeated_blocks, 0);
+        assert!(score(&ciphertext).min_distance > Some(0));
+
+        assert_eq!(
+            score(&[0; 40]),
+            EcbScore {
+                repeated_blocks: 1,
+                min_distance: Some(0),
+            }
+        );
+        assert_eq!(
+            score(&[0; 16]),
+            EcbScore {
+                repeated_blocks: 0,
+                min_distance: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_rank() {
+        let ciphertexts: Vec<Vec<u8>> = (0..10_u8)
+            .map(|i| {
+                if i == 7 {
+                    encrypt_ecb(PLAINTEXT)
+                } else {
+                    cbc::encrypt(PLAINTEXT, Key128::from_bytes(*KEY), [i; 16])
+                }
+            })
+            .collect();
+
+        let ranking = rank(ciphertexts.iter().map(Vec::as_slice));
+        assert_eq!(ranking.len(), 10);
+        assert_eq!(ranking.first().map(|detection| detection.index), Some(7));
+        assert!(ranking
+            .iter()
+            .zip(ranking.iter().skip(1))
+            .all(|(a, b)| a.score >= b.score));
+    }
+
+    #[test]
+    fn test_rank_binary_records() {
+        let mut data: Vec<u8> = (0..6_u8)
+            .flat_map(|i| {
+                if i == 4 {
+                    encrypt_ecb(PLAINTEXT)
+                } else {
+                    cbc::encrypt(PLAINTEXT, Key128::from_bytes(*KEY), [i; 16])
+                }
+            })
+            .collect();
+        // an incomplete record at the end
+        data.extend_from_slice(&[0; 20]);
+
+        let records = split_records(&data, PLAINTEXT.len());
+        assert_eq!(records.len(), 7);
+        assert_eq!(records.last().map(|record| record.len()), Some(20));
+
+        let ranking = rank(records);
+        assert_eq!(ranking.first().map(|detection| detection.index), Some(4));
+    }
+
+    #[test]
+    #[should_panic(expected = "record size cannot be 0")]
+    fn test_empty_records() {
+        split_records(&[0; 16], 0);
+    }
+
+    #[test]
+    #[ignore = "needs 8.txt of cryptopals challenge 8 next to 4.txt, 6.txt and 7.txt"]
+    fn test_rank_challenge_data() {
+        let records: Vec<Vec<u8>> = std::fs::read_to_string("8.txt")
+            .expect("8.txt is readable")
+            .lines()
+            .map(|line| crate::hex::parse_hex_string(line).expect("8.txt is valid hex"))
+            .collect();
+        assert_eq!(records.len(), 204);
+
+        // line 133 is the one encrypted with ECB
+        let ranking = rank(records.iter().map(Vec::as_slice));
+        assert_eq!(ranking.first().map(|detection| detection.index), Some(132));
+    }
+}
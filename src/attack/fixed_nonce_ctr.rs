@@ -0,0 +1,257 @@
+//! Breaking CTR with a fixed nonce
+//!
+//! CTR turns a block cipher into a stream cipher. When several plaintexts are
+//! encrypted under the same key and nonce, they are all combined with the same
+//! keystream: the bytes at one position of every ciphertext share a single keystream
+//! byte. Truncated to a common length and transposed, each position is a single-byte
+//! XOR that is solved with english letter frequencies.
+//!
+//! Past the common length only the longer ciphertexts remain, and near the end there
+//! are too few bytes for frequencies. There each keystream byte is chosen by how well
+//! the plaintext bytes it gives follow the ones already recovered before them.
+//!
+//! <https://www.cryptopals.com/sets/3/challenges/19>
+//! <https://www.cryptopals.com/sets/3/challenges/20>
+
+use crate::{ascii, text, xor};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recovery {
+    /// the keystream, as long as the longest ciphertext
+    pub keystream: Vec<u8>,
+    /// the plaintexts, in the order of the ciphertexts
+    pub plaintexts: Vec<Vec<u8>>,
+}
+
+/// Scores a column of plaintext bytes from different lines. Unlike
+/// [`text::score_english_plaintext()`] it tells upper and lower case apart, as lines
+/// start with a capital letter and continue in lower case.
+fn score_column(column: &str, line_start: bool) -> usize {
+    let spaces = column.chars().filter(|c| *c == ' ').count();
+    let expected_case = column
+        .chars()
+        .filter(|c| {
+            if line_start {
+                c.is_ascii_uppercase()
+            } else {
+                c.is_ascii_lowercase()
+            }
+        })
+        .count();
+
+    text::score_english_plaintext(column)
+        .saturating_add(spaces)
+        .saturating_add(expected_case)
+}
+
+/// Chooses the keystream byte after `keystream` that gives the most likely
+/// continuation of each plaintext
+fn solve_in_context<C: AsRef<[u8]>>(ciphertexts: &[C], keystream: &[u8]) -> u8 {
+    let position = keystream.len();
+
+    (0..=u8::MAX)
+        .max_by_key(|key| {
+            ciphertexts
+                .iter()
+                .filter_map(|ciphertext| {
+                    let ciphertext = ciphertext.as_ref();
+                    let next = ciphertext.get(position)? ^ key;
+                    let Some(next) = ascii::from_bytes(&[next]).and_then(|t| t.chars().next())
+                    else {
+                        return Some(0);
+                    };
+
+                    let bigram = position
+                        .checked_sub(1)
+                        .and_then(|previous| {
+                            Some(ciphertext.get(previous)? ^ keystream.get(previous)?)
+                        })
+                        .map_or(0, |previous| {
+                            text::score_english_bigram(char::from(previous), next)
+                        });
+
+                    // every printable byte is better than an unprintable one
+                    Some(
+                        score_column(&next.to_string(), position == 0)
+                            .saturating_add(bigram)
+                            .saturating_add(1),
+                    )
+                })
+                .sum::<usize>()
+        })
+        .expect("range is not empty")
+}
+
+/// Recovers the keystream that encrypted all `ciphertexts`, and their plaintexts
+pub fn recover_keystream<C: AsRef<[u8]>>(ciphertexts: &[C]) -> Recovery {
+    let lengths = ciphertexts
+        .iter()
+        .map(|ciphertext| ciphertext.as_ref().len());
+    let common_len = lengths.clone().min().unwrap_or(0);
+    let max_len = lengths.max().unwrap_or(0);
+
+    let mut keystream = Vec::with_capacity(max_len);
+
+    for position in 0..common_len {
+        let column: Vec<u8> = ciphertexts
+            .iter()
+            .map(|ciphertext| {
+                *ciphertext
+                    .as_ref()
+                    .get(position)
+                    .expect("position is below the common length")
+            })
+            .collect();
+
+        let key = if let Some([candidate]) =
+            xor::guess_single_xor_key::<1>(&column, |column| score_column(column, position == 0))
+        {
+            candidate.key
+        } else {
+            solve_in_context(ciphertexts, &keystream)
+        };
+        keystream.push(key);
+    }
+
+    for _ in common_len..max_len {
+        let key = solve_in_context(ciphertexts, &keystream);
+        keystream.push(key);
+    }
+
+    let plaintexts = ciphertexts
+        .iter()
+        .map(|ciphertext| {
+            ciphertext
+                .as_ref()
+                .iter()
+                .zip(&keystream)
+                .map(|(c, k)| c ^ k)
+                .collect()
+        })
+        .collect();
+
+    Recovery {
+        keystream,
+        plaintexts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        aes::{
+            ctr::{self, Counter},
+            Key128,
+        },
+        random::Random,
+    };
+
+    /// taken from cryptopals challenge 19, which uses "Easter, 1916" by W. B. Yeats
+    const LINES: [&str; 40] = [
+        "I have met them at close of day",
+        "Coming with vivid faces",
+        "From counter or desk among grey",
+        "Eighteenth-century houses.",
+        "I have passed with a nod of the head",
+        "Or polite meaningless words,",
+        "Or have lingered awhile and said",
+        "Polite meaningless words,",
+        "And thought before I had done",
+        "Of a mocking tale or a gibe",
+        "To please a companion",
+        "Around the fire at the club,",
+        "Being certain that they and I",
+        "But lived where motley is worn:",
+        "All changed, changed utterly:",
+        "A terrible beauty is born.",
+        "That woman's days were spent",
+        "In ignorant good will,",
+        "Her nights in argument",
+        "Until her voice grew shrill.",
+        "What voice more sweet than hers",
+        "When young and beautiful,",
+        "She rode to harriers?",
+        "This man had kept a school",
+        "And rode our winged horse.",
+        "This other his helper and friend",
+        "Was coming into his force;",
+        "He might have won fame in the end,",
+        "So sensitive his nature seemed,",
+        "So daring and sweet his thought.",
+        "This other man I had dreamed",
+        "A drunken, vain-glorious lout.",
+        "He had done most bitter wrong",
+        "To some who are near my heart,",
+        "Yet I number him in the song;",
+        "He, too, has resigned his part",
+        "In the casual comedy;",
+        "He, too, has been changed in his turn,",
+        "Transformed utterly:",
+        "A terrible beauty is born.",
+    ];
+
+    fn encrypt_lines(seed: u8) -> (Vec<Vec<u8>>, Vec<u8>) {
+        let key: [u8; 16] = Random::from_seed([seed; 32]).bytes();
+        let counter = Counter::LittleEndian64 { nonce: 0 };
+
+        let ciphertexts = LINES
+            .iter()
+            .map(|line| ctr::encrypt(line.as_bytes(), Key128::from_bytes(key), counter))
+            .collect();
+        let max_len = LINES.iter().map(|line| line.len()).max().unwrap_or(0);
+        let keystream = ctr::encrypt(&vec![0; max_len], Key128::from_bytes(key), counter);
+
+        (ciphertexts, keystream)
+    }
+
+    #[test]
+    fn test_recover_keystream() {
+        let (ciphertexts, keystream) = encrypt_lines(12);
+        let recovery = recover_keystream(&ciphertexts);
+
+        assert_eq!(recovery.keystream.len(), keystream.len());
+        assert_eq!(recovery.plaintexts.len(), LINES.len());
+
+        // the last bytes are only in one or two lines, and cannot be told apart from
+        // other text
+        let reliable = (0..keystream.len())
+            .take_while(|position| LINES.iter().filter(|line| line.len() > *position).count() >= 3)
+            .count();
+        assert!(reliable > LINES.iter().map(|line| line.len()).min().unwrap_or(0));
+        assert_eq!(
+            recovery.keystream.get(..reliable),
+            keystream.get(..reliable)
+        );
+
+        for (plaintext, line) in recovery.plaintexts.iter().zip(LINES) {
+            assert_eq!(plaintext.len(), line.len());
+            assert!(plaintext
+                .iter()
+                .take(reliable)
+                .eq(line.as_bytes().iter().take(reliable)));
+        }
+    }
+
+    #[test]
+    fn test_common_length() {
+        let (ciphertexts, keystream) = encrypt_lines(13);
+        let ciphertexts: Vec<&[u8]> = ciphertexts
+            .iter()
+            .map(|ciphertext| ciphertext.get(..20).expect("lines are long enough"))
+            .collect();
+
+        let recovery = recover_keystream(&ciphertexts);
+        assert_eq!(recovery.keystream.get(..), keystream.get(..20));
+        for (plaintext, line) in recovery.plaintexts.iter().zip(LINES) {
+            assert_eq!(plaintext.get(..), line.as_bytes().get(..20));
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let recovery = recover_keystream::<Vec<u8>>(&[]);
+        assert!(recovery.keystream.is_empty());
+        assert!(recovery.plaintexts.is_empty());
+    }
+}
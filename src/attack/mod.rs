@@ -0,0 +1,66 @@
+//! Attacks on the constructions in this crate, following the cryptopals challenges
+//!
+//! <https://www.cryptopals.com/>
+//!
+//! The attacks take their oracles as closures, so they work against any target that
+//! leaks the same information, and report how many queries they needed.
+
+use std::{error, fmt};
+
+pub mod byte_at_a_time;
+pub mod cbc_bitflip;
+pub mod cut_and_paste;
+pub mod ecb_detection;
+pub mod fixed_nonce_ctr;
+pub mod mode_detection;
+pub mod mt19937_clone;
+pub mod mt19937_seed;
+pub mod padding_oracle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The lengths of the oracle output do not reveal a block size
+    BlockSizeNotFound,
+    /// The oracle does not encrypt with ECB
+    NotEcb,
+    /// No candidate byte reproduced the ciphertext of the oracle
+    NoMatch,
+    /// The input could not be aligned to a block boundary after the prefix
+    AlignmentNotFound,
+    /// The ciphertext is empty or not a multiple of the block size
+    InvalidLength,
+}
+
+impl error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::BlockSizeNotFound => write!(f, "could not determine the block size"),
+            Self::NotEcb => write!(f, "oracle does not use ECB"),
+            Self::NoMatch => write!(f, "no candidate matched the oracle output"),
+            Self::AlignmentNotFound => write!(f, "could not align the input to a block"),
+            Self::InvalidLength => write!(f, "invalid ciphertext length"),
+        }
+    }
+}
+
+/// Wraps an oracle to count the queries
+struct Oracle<F> {
+    oracle: F,
+    queries: usize,
+}
+
+impl<F> Oracle<F> {
+    const fn new(oracle: F) -> Self {
+        Self { oracle, queries: 0 }
+    }
+
+    fn query<T>(&mut self, input: &[u8]) -> T
+    where
+        F: Fn(&[u8]) -> T,
+    {
+        self.queries = self.queries.saturating_add(1);
+        (self.oracle)(input)
+    }
+}
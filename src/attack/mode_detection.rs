@@ -0,0 +1,116 @@
+//! Telling ECB and CBC apart with chosen plaintext
+//!
+//! The [`encryption_oracle()`] encrypts under a random key, randomly with ECB or CBC,
+//! and surrounds the plaintext with random bytes. A plaintext of equal bytes still
+//! results in at least two equal aligned blocks, which ECB encrypts to equal
+//! ciphertext blocks.
+//!
+//! <https://www.cryptopals.com/sets/2/challenges/11>
+
+use std::fmt;
+
+use super::ecb_detection;
+use crate::{
+    aes::{self, cbc},
+    pkcs7,
+    random::Random,
+};
+
+/// A plaintext that still contains two aligned blocks of zeros after a prefix of
+/// up to 10 bytes
+pub const CHOSEN_PLAINTEXT: [u8; 48] = [0; 48];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Ecb,
+    Cbc,
+}
+
+fn random_padding(random: &mut Random) -> Vec<u8> {
+    let mut padding = vec![0; random.range(5..=10)];
+    random.fill(&mut padding);
+    padding
+}
+
+/// Encrypts `plaintext` with a random key and a random mode, after adding 5 to 10
+/// random bytes before and after it. Returns the mode as well, to check detections
+/// against.
+pub fn encryption_oracle(random: &mut Random, plaintext: &[u8]) -> (Vec<u8>, Mode) {
+    let mut input = random_padding(random);
+    input.extend_from_slice(plaintext);
+    input.extend(random_padding(random));
+    let input = pkcs7::pad(&input, 16);
+
+    let key = random.key();
+    if random.bool() {
+        (aes::encrypt_ecb(&input, key), Mode::Ecb)
+    } else {
+        (cbc::encrypt(&input, key, random.bytes()), Mode::Cbc)
+    }
+}
+
+/// Detects the mode of a ciphertext of [`CHOSEN_PLAINTEXT`]
+pub fn detect(ciphertext: &[u8]) -> Mode {
+    if ecb_detection::score(ciphertext).repeated_blocks > 0 {
+        Mode::Ecb
+    } else {
+        Mode::Cbc
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Accuracy {
+    pub trials: usize,
+    pub correct: usize,
+}
+
+impl fmt::Display for Accuracy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{} detections correct", self.correct, self.trials)
+    }
+}
+
+/// Runs [`detect()`] against the [`encryption_oracle()`] `trials` times
+pub fn measure_accuracy(random: &mut Random, trials: usize) -> Accuracy {
+    let correct = (0..trials)
+        .filter(|_| {
+            let (ciphertext, mode) = encryption_oracle(random, &CHOSEN_PLAINTEXT);
+            detect(&ciphertext) == mode
+        })
+        .count();
+
+    Accuracy { trials, correct }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encryption_oracle() {
+        let mut random = Random::from_seed([1; 32]);
+        let mut modes = Vec::new();
+
+        for _ in 0..100_u8 {
+            let (ciphertext, mode) = encryption_oracle(&mut random, b"YELLOW SUBMARINE");
+            // 16 bytes of plaintext and 10 to 20 random bytes, padded
+            assert!(matches!(ciphertext.len(), 32 | 48));
+            modes.push(mode);
+        }
+
+        assert!(modes.contains(&Mode::Ecb));
+        assert!(modes.contains(&Mode::Cbc));
+    }
+
+    #[test]
+    fn test_measure_accuracy() {
+        let mut random = Random::from_seed([2; 32]);
+        assert_eq!(
+            measure_accuracy(&mut random, 1000),
+            Accuracy {
+                trials: 1000,
+                correct: 1000,
+            }
+        );
+    }
+}
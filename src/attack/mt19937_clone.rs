@@ -0,0 +1,119 @@
+//! Cloning a Mersenne Twister from its outputs
+//!
+//! Every output of MT19937 is one word of its state, passed through an invertible
+//! tempering function. Untempering 624 consecutive outputs gives 624 consecutive
+//! state words, which is all the generator needs to compute the next ones. The clone
+//! then predicts every future output, like the tokens of other users.
+//!
+//! <https://www.cryptopals.com/sets/3/challenges/21>
+//! <https://www.cryptopals.com/sets/3/challenges/23>
+
+use crate::mt19937::{Mt19937, Mt19937_64};
+
+/// Inverts `y ^= (y >> shift) & mask` on words of `bits` bits
+///
+/// Each round recovers another `shift` bits from the top, starting with the ones
+/// that were not changed.
+fn undo_right_shift(y: u64, shift: u32, mask: u64, bits: u32) -> u64 {
+    let mut x = y;
+    for _ in 0..bits.div_ceil(shift) {
+        x = y ^ ((x >> shift) & mask);
+    }
+    x
+}
+
+/// Inverts `y ^= (y << shift) & mask`, as [`undo_right_shift()`] from the bottom.
+/// `mask` has to be limited to the word size.
+fn undo_left_shift(y: u64, shift: u32, mask: u64, bits: u32) -> u64 {
+    let mut x = y;
+    for _ in 0..bits.div_ceil(shift) {
+        x = y ^ ((x << shift) & mask);
+    }
+    x
+}
+
+/// Inverts [`Mt19937::temper()`], recovering the state word of an output
+pub fn untemper(output: u32) -> u32 {
+    let y = u64::from(output);
+    let y = undo_right_shift(y, 18, u64::MAX, 32);
+    let y = undo_left_shift(y, 15, 0xefc6_0000, 32);
+    let y = undo_left_shift(y, 7, 0x9d2c_5680, 32);
+    let y = undo_right_shift(y, 11, u64::MAX, 32);
+    u32::try_from(y).expect("all masks and shifts stay within 32 bits")
+}
+
+/// Inverts [`Mt19937_64::temper()`]
+pub fn untemper_64(output: u64) -> u64 {
+    let y = undo_right_shift(output, 43, u64::MAX, 64);
+    let y = undo_left_shift(y, 37, 0xfff7_eee0_0000_0000, 64);
+    let y = undo_left_shift(y, 17, 0x71d6_7fff_eda6_0000, 64);
+    undo_right_shift(y, 29, 0x5555_5555_5555_5555, 64)
+}
+
+/// Rebuilds a generator from consecutive `outputs`, which continues with the output
+/// after the last one
+///
+/// The outputs do not have to start at a twist, as each new state word only depends
+/// on the words 624, 623 and 227 positions before it.
+pub fn clone(outputs: &[u32; Mt19937::STATE_WORDS]) -> Mt19937 {
+    Mt19937::from_state(outputs.map(untemper))
+}
+
+/// Rebuilds a [`Mt19937_64`] from consecutive `outputs`, as [`clone()`]
+pub fn clone_64(outputs: &[u64; Mt19937_64::STATE_WORDS]) -> Mt19937_64 {
+    Mt19937_64::from_state(outputs.map(untemper_64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::Random;
+
+    #[test]
+    fn test_untemper() {
+        let mut random = Random::from_seed([14; 32]);
+
+        for word in [0, 1, u32::MAX, 0x8000_0000, 0x1234_5678] {
+            assert_eq!(untemper(Mt19937::temper(word)), word);
+        }
+        for _ in 0..1000_u16 {
+            let word = u32::from_be_bytes(random.bytes());
+            assert_eq!(untemper(Mt19937::temper(word)), word);
+
+            let word = u64::from_be_bytes(random.bytes());
+            assert_eq!(untemper_64(Mt19937_64::temper(word)), word);
+        }
+    }
+
+    #[test]
+    fn test_clone() {
+        // an unknown seed, and outputs that do not start at a twist
+        let mut mt = Mt19937::new(u32::from_be_bytes(Random::from_seed([15; 32]).bytes()));
+        for _ in 0..1000_u16 {
+            mt.next_u32();
+        }
+
+        let outputs = [0; Mt19937::STATE_WORDS].map(|_: u32| mt.next_u32());
+        let mut clone = clone(&outputs);
+
+        for _ in 0..2000_u16 {
+            assert_eq!(clone.next_u32(), mt.next_u32());
+        }
+    }
+
+    #[test]
+    fn test_clone_64() {
+        let mut mt =
+            Mt19937_64::from_key(&[u64::from_be_bytes(Random::from_seed([16; 32]).bytes())]);
+        for _ in 0..100_u16 {
+            mt.next_u64();
+        }
+
+        let outputs = [0; Mt19937_64::STATE_WORDS].map(|_: u64| mt.next_u64());
+        let mut clone = clone_64(&outputs);
+
+        for _ in 0..1000_u16 {
+            assert_eq!(clone.next_u64(), mt.next_u64());
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! Recovering the seed of a Mersenne Twister
+//!
+//! MT19937 is often seeded with something small or predictable, like the current Unix
+//! timestamp or a 16 bit number. Then the seed can be found by trying every candidate
+//! and comparing the outputs with the observed ones. The candidates are split across
+//! threads, and every seed that matches is reported.
+//!
+//! <https://www.cryptopals.com/sets/3/challenges/22>
+//! <https://www.cryptopals.com/sets/3/challenges/24>
+
+use std::{
+    iter,
+    ops::RangeInclusive,
+    sync::{Mutex, PoisonError},
+};
+
+use crate::{mt19937::Mt19937, parallel};
+
+/// The length of a password reset token from [`reset_token()`]
+pub const TOKEN_LENGTH: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// The number of threads that try seeds, including the calling thread
+    pub threads: usize,
+    /// The number of consecutive seeds a thread takes from the queue at once
+    pub batch_size: u32,
+}
+
+impl Default for Options {
+    /// One thread per available core, with batches of 1024 seeds
+    fn default() -> Self {
+        Self {
+            threads: parallel::available_threads(),
+            batch_size: 1024,
+        }
+    }
+}
+
+/// Returns all seeds in `seeds` for which `is_match` is true, in ascending order
+fn scan<F>(seeds: RangeInclusive<u32>, options: Options, is_match: F) -> Vec<u32>
+where
+    F: Fn(u32) -> bool + Sync,
+{
+    assert!(options.batch_size != 0, "batch size cannot be 0");
+
+    let end = *seeds.end();
+    let batches = seeds
+        .step_by(usize::try_from(options.batch_size).expect("u32 fits into usize"))
+        .map(|first| {
+            first
+                ..=first
+                    .saturating_add(options.batch_size.saturating_sub(1))
+                    .min(end)
+        });
+    let found = Mutex::new(Vec::new());
+
+    parallel::for_each(batches, options.threads, |batch| {
+        let matched: Vec<u32> = batch.filter(|seed| is_match(*seed)).collect();
+        if !matched.is_empty() {
+            found
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .extend(matched);
+        }
+    });
+
+    let mut found = found.into_inner().unwrap_or_else(PoisonError::into_inner);
+    found.sort_unstable();
+    found
+}
+
+/// The keystream of the stream cipher, the lowest byte of each output
+fn keystream(seed: u32) -> impl Iterator<Item = u8> {
+    let mut mt = Mt19937::new(seed);
+    iter::repeat_with(move || {
+        let [.., low] = mt.next_u32().to_be_bytes();
+        low
+    })
+}
+
+/// Encrypts `plaintext` with a stream cipher that takes its keystream from MT19937
+pub fn encrypt(seed: u16, plaintext: &[u8]) -> Vec<u8> {
+    plaintext
+        .iter()
+        .zip(keystream(u32::from(seed)))
+        .map(|(p, k)| p ^ k)
+        .collect()
+}
+
+/// Decrypts `ciphertext`, which is the same operation as [`encrypt()`]
+pub fn decrypt(seed: u16, ciphertext: &[u8]) -> Vec<u8> {
+    encrypt(seed, ciphertext)
+}
+
+/// Generates a password reset token from MT19937 seeded with a `timestamp`
+pub fn reset_token(timestamp: u32) -> [u8; TOKEN_LENGTH] {
+    let mut token = [0; TOKEN_LENGTH];
+    token
+        .iter_mut()
+        .zip(keystream(timestamp))
+        .for_each(|(t, k)| *t = k);
+    token
+}
+
+/// Finds the timestamps in `window` that produce `first_output` as the first output
+/// of MT19937
+pub fn recover_timestamp_seed(
+    first_output: u32,
+    window: RangeInclusive<u32>,
+    options: Options,
+) -> Vec<u32> {
+    scan(window, options, |seed| {
+        Mt19937::new(seed).next_u32() == first_output
+    })
+}
+
+/// Finds the seeds of [`encrypt()`] that turn the end of `ciphertext` into
+/// `known_suffix`. An empty `known_suffix` does not narrow down the seed, so no seeds
+/// are returned for it.
+pub fn recover_stream_seed(ciphertext: &[u8], known_suffix: &[u8], options: Options) -> Vec<u16> {
+    if known_suffix.is_empty() {
+        return Vec::new();
+    }
+
+    let Some(offset) = ciphertext.len().checked_sub(known_suffix.len()) else {
+        return Vec::new();
+    };
+    let suffix = ciphertext
+        .get(offset..)
+        .expect("offset is within the ciphertext");
+
+    scan(0..=u32::from(u16::MAX), options, |seed| {
+        keystream(seed)
+            .skip(offset)
+            .zip(suffix.iter().zip(known_suffix))
+            .all(|(k, (c, p))| c ^ k == *p)
+    })
+    .into_iter()
+    .map(|seed| u16::try_from(seed).expect("only 16 bit seeds are scanned"))
+    .collect()
+}
+
+/// Checks whether `token` came from [`reset_token()`] with a timestamp in `window`,
+/// and returns the timestamp if so
+pub fn classify_token(
+    token: &[u8; TOKEN_LENGTH],
+    window: RangeInclusive<u32>,
+    options: Options,
+) -> Option<u32> {
+    scan(window, options, |seed| reset_token(seed) == *token)
+        .first()
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::Random;
+
+    const NOW: u32 = 1_700_000_000;
+
+    const OPTIONS: [Options; 3] = [
+        Options {
+            threads: 1,
+            batch_size: 1,
+        },
+        Options {
+            threads: 3,
+            batch_size: 7,
+        },
+        Options {
+            threads: 8,
+            batch_size: 1024,
+        },
+    ];
+
+    #[test]
+    fn test_scan() {
+        let expected: Vec<u32> = (100..=200).filter(|seed| seed % 7 == 0).collect();
+        for options in OPTIONS {
+            assert_eq!(scan(100..=200, options, |seed| seed % 7 == 0), expected);
+        }
+
+        // the batches must not overflow at the end of the seed space
+        assert_eq!(
+            scan(u32::MAX - 10..=u32::MAX, OPTIONS[1], |seed| seed % 2 == 1),
+            [
+                u32::MAX - 10,
+                u32::MAX - 8,
+                u32::MAX - 6,
+                u32::MAX - 4,
+                u32::MAX - 2,
+                u32::MAX
+            ]
+        );
+        assert!(scan(RangeInclusive::new(200, 100), Options::default(), |_| true).is_empty());
+    }
+
+    #[test]
+    fn test_recover_timestamp_seed() {
+        let mut random = Random::from_seed([17; 32]);
+        // seeded a while ago, and the output observed later
+        let seed = NOW
+            .saturating_sub(u32::try_from(random.range(40..=1000)).expect("range fits into u32"));
+        let first_output = Mt19937::new(seed).next_u32();
+
+        for options in OPTIONS {
+            assert_eq!(
+                recover_timestamp_seed(first_output, NOW.saturating_sub(2000)..=NOW, options),
+                [seed]
+            );
+        }
+        assert!(recover_timestamp_seed(first_output, NOW..=NOW, Options::default()).is_empty());
+    }
+
+    #[test]
+    fn test_recover_stream_seed() {
+        let mut random = Random::from_seed([18; 32]);
+        let seed = u16::from_be_bytes(random.bytes());
+        let known = b"AAAAAAAAAAAAAA";
+
+        let mut plaintext = vec![0; random.range(5..=20)];
+        random.fill(&mut plaintext);
+        plaintext.extend_from_slice(known);
+
+        let ciphertext = encrypt(seed, &plaintext);
+        assert_eq!(decrypt(seed, &ciphertext), plaintext);
+
+        let seeds = recover_stream_seed(&ciphertext, known, Options::default());
+        assert_eq!(seeds, [seed]);
+        assert!(recover_stream_seed(known, &ciphertext, Options::default()).is_empty());
+        assert!(recover_stream_seed(&ciphertext, &[], Options::default()).is_empty());
+    }
+
+    #[test]
+    fn test_classify_token() {
+        let window = NOW.saturating_sub(3600)..=NOW;
+
+        let token = reset_token(NOW.saturating_sub(100));
+        assert_eq!(
+            classify_token(&token, window.clone(), Options::default()),
+            Some(NOW.saturating_sub(100))
+        );
+
+        let token = Random::from_seed([19; 32]).bytes();
+        assert_eq!(classify_token(&token, window, Options::default()), None);
+    }
+}
@@ -0,0 +1,284 @@
+//! The CBC padding oracle attack
+//!
+//! A service that reveals whether a CBC ciphertext decrypts to valid PKCS#7 padding
+//! leaks the whole plaintext. The attacker puts a forged block in front of a
+//! ciphertext block and varies its last byte until the padding is valid, which means
+//! the block decrypts to `0x01` in that position. This reveals the intermediate state
+//! of the block cipher, the decrypted block before the XOR with the previous block,
+//! one byte at a time. Combined with the real previous block or the IV it gives the
+//! plaintext. Combined with chosen plaintext it gives a forged previous block, which
+//! allows encrypting arbitrary plaintext without the key.
+//!
+//! <https://www.cryptopals.com/sets/3/challenges/17>
+//! <https://www.usenix.org/legacy/event/woot10/tech/full_papers/Rizzo.pdf>
+
+use super::{Error, Oracle};
+use crate::pkcs7;
+
+const BLOCK_SIZE: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decryption {
+    /// the plaintext with the padding removed
+    pub plaintext: Vec<u8>,
+    /// the oracle queries needed for each byte of the ciphertext, in order
+    pub queries_per_byte: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Encryption {
+    pub iv: [u8; 16],
+    pub ciphertext: Vec<u8>,
+    /// the oracle queries needed for each byte of the IV and the ciphertext except
+    /// for the last block, which is chosen freely
+    pub queries_per_byte: Vec<usize>,
+}
+
+/// Recovers the intermediate state of the block cipher for `block`. `previous` is
+/// only the starting point of the forged block, and determines which false positives
+/// occur.
+fn intermediate<F: Fn(&[u8]) -> bool>(
+    oracle: &mut Oracle<F>,
+    previous: [u8; 16],
+    block: [u8; 16],
+    queries_per_byte: &mut Vec<usize>,
+) -> Result<[u8; 16], Error> {
+    let mut intermediate = [0; BLOCK_SIZE];
+    let mut byte_queries = [0; BLOCK_SIZE];
+
+    for (position, padding) in (0..BLOCK_SIZE).rev().zip(1_u8..) {
+        // make the bytes after the position decrypt to the padding value
+        let mut forged = previous;
+        for (f, i) in forged
+            .iter_mut()
+            .zip(intermediate)
+            .skip(position.saturating_add(1))
+        {
+            *f = i ^ padding;
+        }
+
+        let start = oracle.queries;
+        let mut query =
+            |forged: &[u8; 16]| oracle.query([&forged[..], &block[..]].concat().as_slice());
+
+        let guess = (0..=u8::MAX)
+            .find(|guess| {
+                *forged
+                    .get_mut(position)
+                    .expect("position is within the block") = *guess;
+                if !query(&forged) {
+                    return false;
+                }
+
+                // the last byte may also have produced valid padding by decrypting
+                // to 0x02 next to a 0x02, or to 0x03 next to 0x03 0x03 and so on.
+                // Changing the byte before tells those apart from a 0x01.
+                if padding == 1 {
+                    let mut check = forged;
+                    *check
+                        .get_mut(position.saturating_sub(1))
+                        .expect("position is within the block") ^= 0xff;
+                    return query(&check);
+                }
+                true
+            })
+            .ok_or(Error::NoMatch)?;
+
+        *intermediate
+            .get_mut(position)
+            .expect("position is within the block") = guess ^ padding;
+        *byte_queries
+            .get_mut(position)
+            .expect("position is within the block") = oracle.queries.saturating_sub(start);
+    }
+
+    queries_per_byte.extend(byte_queries);
+    Ok(intermediate)
+}
+
+fn xor(a: [u8; 16], b: &[u8]) -> [u8; 16] {
+    let mut output = a;
+    output.iter_mut().zip(b).for_each(|(o, b)| *o ^= b);
+    output
+}
+
+/// Decrypts `ciphertext` with an `oracle` that tells whether an IV followed by a
+/// ciphertext decrypts to valid padding
+pub fn decrypt(
+    oracle: impl Fn(&[u8]) -> bool,
+    iv: [u8; 16],
+    ciphertext: &[u8],
+) -> Result<Decryption, Error> {
+    if ciphertext.is_empty() || !ciphertext.len().is_multiple_of(BLOCK_SIZE) {
+        return Err(Error::InvalidLength);
+    }
+
+    let mut oracle = Oracle::new(oracle);
+    let mut plaintext = Vec::with_capacity(ciphertext.len());
+    let mut queries_per_byte = Vec::with_capacity(ciphertext.len());
+
+    let mut previous = iv;
+    for block in ciphertext.chunks_exact(BLOCK_SIZE) {
+        let block: [u8; 16] = block.try_into().expect("chunks are exactly one block");
+        let intermediate = intermediate(&mut oracle, previous, block, &mut queries_per_byte)?;
+
+        plaintext.extend(xor(intermediate, &previous));
+        previous = block;
+    }
+
+    let len = pkcs7::unpad(&plaintext, 16)
+        .map_err(|_e| Error::NoMatch)?
+        .len();
+    plaintext.truncate(len);
+
+    Ok(Decryption {
+        plaintext,
+        queries_per_byte,
+    })
+}
+
+/// Encrypts `plaintext` with an `oracle` as for [`decrypt()`]. The last ciphertext
+/// block is all zeros, and every block before it is forged from the one after it.
+pub fn encrypt(oracle: impl Fn(&[u8]) -> bool, plaintext: &[u8]) -> Result<Encryption, Error> {
+    let mut oracle = Oracle::new(oracle);
+    let plaintext = pkcs7::pad(plaintext, 16);
+
+    let mut blocks = vec![[0; BLOCK_SIZE]];
+    let mut queries_per_block = Vec::new();
+
+    for plaintext_block in plaintext.chunks_exact(BLOCK_SIZE).rev() {
+        let next = *blocks.last().expect("blocks start with the last block");
+        let mut queries = Vec::with_capacity(BLOCK_SIZE);
+        let intermediate = intermediate(&mut oracle, [0; BLOCK_SIZE], next, &mut queries)?;
+
+        blocks.push(xor(intermediate, plaintext_block));
+        queries_per_block.push(queries);
+    }
+
+    let mut blocks = blocks.into_iter().rev();
+    let iv = blocks.next().expect("the iv is forged last");
+
+    Ok(Encryption {
+        iv,
+        ciphertext: blocks.flatten().collect(),
+        queries_per_byte: queries_per_block.into_iter().rev().flatten().collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::{
+        aes::{cbc, Key128},
+        base64,
+        random::Random,
+    };
+
+    const KEY: [u8; 16] = *b"YELLOW SUBMARINE";
+
+    fn oracle(data: &[u8]) -> bool {
+        let Some((iv, ciphertext)) = data.split_first_chunk::<16>() else {
+            return false;
+        };
+        if ciphertext.is_empty() || !ciphertext.len().is_multiple_of(16) {
+            return false;
+        }
+
+        let plaintext = cbc::decrypt(ciphertext, Key128::from_bytes(KEY), *iv);
+        pkcs7::unpad(&plaintext, 16).is_ok()
+    }
+
+    fn encrypt_cbc(plaintext: &[u8], iv: [u8; 16]) -> Vec<u8> {
+        cbc::encrypt(&pkcs7::pad(plaintext, 16), Key128::from_bytes(KEY), iv)
+    }
+
+    #[test]
+    /// taken from cryptopals challenge 17
+    fn test_decrypt() {
+        let strings = [
+            "MDAwMDAwTm93IHRoYXQgdGhlIHBhcnR5IGlzIGp1bXBpbmc=",
+            "MDAwMDAxV2l0aCB0aGUgYmFzcyBraWNrZWQgaW4gYW5kIHRoZSBWZWdhJ3MgYXJlIHB1bXBpbic=",
+            "MDAwMDAyUXVpY2sgdG8gdGhlIHBvaW50LCB0byB0aGUgcG9pbnQsIG5vIGZha2luZw==",
+            "MDAwMDAzQ29va2luZyBNQydzIGxpa2UgYSBwb3VuZCBvZiBiYWNvbg==",
+            "MDAwMDA0QnVybmluZyAnZW0sIGlmIHlvdSBhaW4ndCBxdWljayBhbmQgbmltYmxl",
+            "MDAwMDA1SSBnbyBjcmF6eSB3aGVuIEkgaGVhciBhIGN5bWJhbA==",
+            "MDAwMDA2QW5kIGEgaGlnaCBoYXQgd2l0aCBhIHNvdXBlZCB1cCB0ZW1wbw==",
+            "MDAwMDA3SSdtIG9uIGEgcm9sbCwgaXQncyB0aW1lIHRvIGdvIHNvbG8=",
+            "MDAwMDA4b2xsaW4nIGluIG15IGZpdmUgcG9pbnQgb2g=",
+            "MDAwMDA5aXRoIG15IHJhZy10b3AgZG93biBzbyBteSBoYWlyIGNhbiBibG93",
+        ];
+        let mut random = Random::from_seed([11; 32]);
+
+        for string in strings {
+            let plaintext = base64::decode_str(string).expect("test data is valid base64");
+            let iv = random.bytes();
+            let ciphertext = encrypt_cbc(&plaintext, iv);
+
+            let decryption = decrypt(oracle, iv, &ciphertext).expect("oracle leaks padding");
+            assert_eq!(decryption.plaintext, plaintext);
+            assert_eq!(decryption.queries_per_byte.len(), ciphertext.len());
+            assert!(decryption
+                .queries_per_byte
+                .iter()
+                .all(|queries| (1..=258).contains(queries)));
+        }
+    }
+
+    #[test]
+    fn test_false_positive() {
+        // with this iv, the guess that decrypts the last byte of the first block to
+        // 0x02 comes before the one for 0x01, and the byte before it is a 0x02
+        let iv = [1; 16];
+        let plaintext = b"fourteen bytes\x02\x03";
+
+        let ciphertext = encrypt_cbc(plaintext, iv);
+        let decryption = decrypt(oracle, iv, &ciphertext).expect("oracle leaks padding");
+        assert_eq!(decryption.plaintext, plaintext);
+    }
+
+    #[test]
+    fn test_encrypt() {
+        let plaintext = b"forged without the key, but decrypts just fine";
+
+        let encryption = encrypt(oracle, plaintext).expect("oracle leaks padding");
+        assert_eq!(
+            pkcs7::unpad(
+                &cbc::decrypt(
+                    &encryption.ciphertext,
+                    Key128::from_bytes(KEY),
+                    encryption.iv
+                ),
+                16
+            ),
+            Ok(&plaintext[..])
+        );
+        assert_eq!(
+            encryption.queries_per_byte.len(),
+            encryption.ciphertext.len()
+        );
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert_eq!(
+            decrypt(oracle, [0; 16], &[0; 20]),
+            Err(Error::InvalidLength)
+        );
+        assert_eq!(decrypt(oracle, [0; 16], &[]), Err(Error::InvalidLength));
+
+        // the length is checked before the oracle is queried for the first block
+        let queries = Cell::new(0_usize);
+        let counting = |ciphertext: &[u8]| {
+            queries.set(queries.get().saturating_add(1));
+            oracle(ciphertext)
+        };
+        assert_eq!(
+            decrypt(counting, [0; 16], &[0; 36]),
+            Err(Error::InvalidLength)
+        );
+        assert_eq!(queries.get(), 0);
+        assert_eq!(decrypt(|_| false, [0; 16], &[0; 16]), Err(Error::NoMatch));
+    }
+}
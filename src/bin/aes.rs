@@ -1,6 +1,18 @@
-use std::fs;
+use std::{
+    fs,
+    io::{self, Read, Write},
+};
 
-use lib::{aes, base64, Error};
+use lib::{
+    aes::{
+        self, openssl,
+        stream::{self, Decryptor, Encryptor},
+    },
+    attack::{ecb_detection, mode_detection},
+    base64, hex,
+    random::Random,
+    Error,
+};
 
 use clap::{Args, Parser, Subcommand, ValueEnum};
 
@@ -13,7 +25,10 @@ struct Cli {
 
 #[derive(Subcommand, Debug)]
 enum Commands {
-    Decrypt(DecryptArgs),
+    Encrypt(CryptArgs),
+    Decrypt(CryptArgs),
+    DetectEcb(DetectArgs),
+    DetectMode(DetectModeArgs),
 }
 
 #[derive(ValueEnum, Clone, Debug)]
@@ -24,51 +39,358 @@ enum Encoding {
 #[derive(ValueEnum, Clone, Debug)]
 enum Mode {
     Ecb,
+    Cbc,
+    Pcbc,
+    Ige,
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+enum Padding {
+    Pkcs7,
+    None,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum KeySize {
+    #[value(name = "128")]
+    Aes128,
+    #[value(name = "192")]
+    Aes192,
+    #[value(name = "256")]
+    Aes256,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Digest {
+    Md5,
+    Sha256,
 }
 
 #[derive(Args, Debug)]
-struct DecryptArgs {
-    #[arg(long)]
-    key: String,
+struct CryptArgs {
+    #[arg(
+        long,
+        required_unless_present = "password",
+        help = "the key, 16, 24 or 32 bytes for AES-128, AES-192 or AES-256"
+    )]
+    key: Option<String>,
 
-    #[arg(long)]
-    path: String,
+    #[arg(
+        long,
+        conflicts_with = "key",
+        help = "derive key and iv from a password, in the file format of `openssl enc`"
+    )]
+    password: Option<String>,
 
-    #[arg(long)]
-    encoding: Encoding,
+    #[arg(
+        long,
+        default_value = "128",
+        conflicts_with = "key",
+        help = "the key size in bits when deriving the key from a password"
+    )]
+    key_size: KeySize,
+
+    #[arg(
+        long,
+        default_value = "sha256",
+        help = "the digest used to derive the key from the password"
+    )]
+    md: Digest,
+
+    #[arg(long, help = "derive the key with PBKDF2 instead of EVP_BytesToKey")]
+    pbkdf2: bool,
+
+    #[arg(
+        long,
+        help = "the PBKDF2 iteration count, implies --pbkdf2 [default: 10000]"
+    )]
+    iter: Option<u32>,
+
+    #[arg(
+        long,
+        conflicts_with = "nosalt",
+        help = "the salt as hex when encrypting with a password, random if not given"
+    )]
+    salt: Option<String>,
+
+    #[arg(long, help = "use no salt and no Salted__ header with a password")]
+    nosalt: bool,
+
+    #[arg(long, help = "the file to read from, defaults to stdin")]
+    path: Option<String>,
+
+    #[arg(long, help = "the file to write to, defaults to stdout")]
+    output: Option<String>,
+
+    #[arg(
+        long,
+        help = "the encoding of the ciphertext, raw binary if not given. Encoded data is processed in memory"
+    )]
+    encoding: Option<Encoding>,
 
     #[arg(long)]
     mode: Mode,
+
+    #[arg(
+        long,
+        conflicts_with = "password",
+        help = "the iv as hex, 32 bytes for ige and 16 bytes otherwise"
+    )]
+    iv: Option<String>,
+
+    #[arg(long, default_value = "pkcs7")]
+    padding: Padding,
+}
+
+#[derive(Args, Debug)]
+struct DetectArgs {
+    #[arg(
+        long,
+        help = "a path to the file to read ciphertexts from, one hex string per line"
+    )]
+    path: String,
+
+    #[arg(
+        long,
+        help = "read raw binary instead of hex lines, split into records of this many bytes"
+    )]
+    record_size: Option<usize>,
+}
+
+#[derive(Args, Debug)]
+struct DetectModeArgs {
+    #[arg(
+        long,
+        default_value = "1000",
+        help = "how often to query the ECB/CBC encryption oracle"
+    )]
+    trials: usize,
+}
+
+impl CryptArgs {
+    fn key(&self) -> Result<aes::AnyKey, Error> {
+        let key = self
+            .key
+            .as_ref()
+            .ok_or_else(|| Error("no key given".to_owned()))?
+            .as_bytes();
+
+        Ok(match key.len() {
+            16 => aes::Key128::from_bytes(key.try_into().expect("key has 16 bytes")).into(),
+            24 => aes::Key192::from_bytes(key.try_into().expect("key has 24 bytes")).into(),
+            32 => aes::Key256::from_bytes(key.try_into().expect("key has 32 bytes")).into(),
+            _ => return Err(Error("invalid key size".to_owned())),
+        })
+    }
+
+    fn mode(&self) -> Result<stream::Mode, Error> {
+        fn iv<const N: usize>(iv: Option<&String>) -> Result<[u8; N], Error> {
+            hex::parse_hex_string(iv.ok_or_else(|| Error("mode requires an iv".to_owned()))?)?
+                .try_into()
+                .map_err(|_e| Error("invalid iv size".to_owned()))
+        }
+
+        Ok(match self.mode {
+            Mode::Ecb => stream::Mode::Ecb,
+            Mode::Cbc => stream::Mode::Cbc(iv(self.iv.as_ref())?),
+            Mode::Pcbc => stream::Mode::Pcbc(iv(self.iv.as_ref())?),
+            Mode::Ige => stream::Mode::Ige(iv(self.iv.as_ref())?),
+        })
+    }
+
+    const fn padding(&self) -> stream::Padding {
+        match self.padding {
+            Padding::Pkcs7 => stream::Padding::Pkcs7,
+            Padding::None => stream::Padding::None,
+        }
+    }
+
+    fn cipher(&self) -> Result<openssl::Cipher, Error> {
+        if matches!(self.padding, Padding::None) {
+            return Err(Error(
+                "password based encryption always uses pkcs7 padding".to_owned(),
+            ));
+        }
+
+        let (ecb, cbc) = match self.key_size {
+            KeySize::Aes128 => (openssl::Cipher::Aes128Ecb, openssl::Cipher::Aes128Cbc),
+            KeySize::Aes192 => (openssl::Cipher::Aes192Ecb, openssl::Cipher::Aes192Cbc),
+            KeySize::Aes256 => (openssl::Cipher::Aes256Ecb, openssl::Cipher::Aes256Cbc),
+        };
+
+        match self.mode {
+            Mode::Ecb => Ok(ecb),
+            Mode::Cbc => Ok(cbc),
+            Mode::Pcbc | Mode::Ige => Err(Error(
+                "password based encryption only supports ecb and cbc".to_owned(),
+            )),
+        }
+    }
+
+    const fn key_derivation(&self) -> openssl::KeyDerivation {
+        let digest = match self.md {
+            Digest::Md5 => openssl::Digest::Md5,
+            Digest::Sha256 => openssl::Digest::Sha256,
+        };
+
+        match (self.pbkdf2, self.iter) {
+            (_, Some(iterations)) => openssl::KeyDerivation::Pbkdf2 { digest, iterations },
+            (true, None) => openssl::KeyDerivation::Pbkdf2 {
+                digest,
+                iterations: openssl::DEFAULT_ITERATIONS,
+            },
+            (false, None) => openssl::KeyDerivation::BytesToKey(digest),
+        }
+    }
+
+    fn salt(&self) -> Result<Option<[u8; 8]>, Error> {
+        if self.nosalt {
+            return Ok(None);
+        }
+
+        let salt = if let Some(ref salt) = self.salt {
+            hex::parse_hex_string(salt)?
+                .try_into()
+                .map_err(|_e| Error("invalid salt size".to_owned()))?
+        } else {
+            Random::new()?.bytes()
+        };
+
+        Ok(Some(salt))
+    }
+
+    fn encryptor<W: Write>(&self, inner: W) -> Result<Encryptor<W>, Error> {
+        Ok(match self.password {
+            Some(ref password) => openssl::encryptor(
+                inner,
+                password.as_bytes(),
+                self.salt()?,
+                self.cipher()?,
+                self.key_derivation(),
+            )?,
+            None => Encryptor::new(inner, self.key()?, self.mode()?, self.padding()),
+        })
+    }
+
+    fn decryptor<R: Read>(&self, inner: R) -> Result<Decryptor<R>, Error> {
+        Ok(match self.password {
+            Some(ref password) => {
+                if self.salt.is_some() {
+                    return Err(Error(
+                        "the salt is read from the file when decrypting".to_owned(),
+                    ));
+                }
+
+                openssl::decryptor(
+                    inner,
+                    password.as_bytes(),
+                    !self.nosalt,
+                    self.cipher()?,
+                    self.key_derivation(),
+                )?
+            }
+            None => Decryptor::new(inner, self.key()?, self.mode()?, self.padding()),
+        })
+    }
+
+    fn input(&self) -> Result<Box<dyn Read>, Error> {
+        Ok(match self.path {
+            Some(ref path) => Box::new(fs::File::open(path)?),
+            None => Box::new(io::stdin().lock()),
+        })
+    }
+
+    fn output(&self) -> Result<Box<dyn Write>, Error> {
+        Ok(match self.output {
+            Some(ref path) => Box::new(io::BufWriter::new(fs::File::create(path)?)),
+            None => Box::new(io::stdout().lock()),
+        })
+    }
 }
 
-#[expect(clippy::print_stdout, reason = "main function")]
 fn main() -> Result<(), Error> {
     let args = Cli::parse();
 
     match args.command {
-        Commands::Decrypt(decrypt_args) => {
-            let input = fs::read_to_string(decrypt_args.path)?;
+        Commands::Encrypt(args) => {
+            let mut input = args.input()?;
+            let mut output = args.output()?;
 
-            let decoded = match decrypt_args.encoding {
-                Encoding::Base64 => base64::decode_str(&input)?,
-            };
+            match args.encoding {
+                None => {
+                    let mut encryptor = args.encryptor(output)?;
+                    io::copy(&mut input, &mut encryptor)?;
+                    encryptor.finish()?;
+                }
+                Some(Encoding::Base64) => {
+                    let mut encryptor = args.encryptor(Vec::new())?;
+                    io::copy(&mut input, &mut encryptor)?;
+                    let encrypted = encryptor.finish()?;
 
-            let key = decrypt_args.key.as_bytes();
+                    writeln!(output, "{}", base64::bytes_to_base64_string(&encrypted))?;
+                    output.flush()?;
+                }
+            }
+        }
+        Commands::Decrypt(args) => {
+            let mut output = args.output()?;
 
-            let key: [u8; 16] = key
-                .try_into()
-                .map_err(|_e| Error("invalid key size".to_owned()))?;
+            let input: Box<dyn Read> = match args.encoding {
+                None => args.input()?,
+                Some(Encoding::Base64) => {
+                    let mut encoded = String::new();
+                    args.input()?.read_to_string(&mut encoded)?;
+                    Box::new(io::Cursor::new(base64::decode_str(&encoded)?))
+                }
+            };
 
-            let key = aes::Key128::from_bytes(key);
+            let mut decryptor = args.decryptor(input)?;
+            io::copy(&mut decryptor, &mut output)?;
+            output.flush()?;
+        }
+        Commands::DetectEcb(args) => {
+            const CANDIDATE_COUNT: usize = 10;
 
-            let decrypted = match decrypt_args.mode {
-                Mode::Ecb => aes::decrypt_ecb(&decoded, key),
+            let records: Vec<Vec<u8>> = match args.record_size {
+                None => fs::read_to_string(args.path)?
+                    .lines()
+                    .map(hex::parse_hex_string)
+                    .collect::<Result<_, _>>()?,
+                Some(0) => return Err(Error("record size cannot be 0".to_owned())),
+                Some(record_size) => {
+                    ecb_detection::split_records(&fs::read(args.path)?, record_size)
+                        .into_iter()
+                        .map(<[u8]>::to_vec)
+                        .collect()
+                }
             };
 
-            println!(
-                "{}",
-                String::from_utf8(decrypted).expect("decryption produced invalid utf-8")
-            );
+            let ranking = ecb_detection::rank(records.iter().map(Vec::as_slice));
+
+            let mut output = io::stdout().lock();
+            writeln!(output, "{CANDIDATE_COUNT} best candidates:")?;
+            for detection in ranking.iter().take(CANDIDATE_COUNT) {
+                let record = records
+                    .get(detection.index)
+                    .expect("ranking only contains indices of records");
+                let min_distance = detection
+                    .score
+                    .min_distance
+                    .map_or_else(|| "-".to_owned(), |distance| distance.to_string());
+
+                writeln!(
+                    output,
+                    "| repeated blocks {:03} | min distance {:>3} | record {:03} | {}",
+                    detection.score.repeated_blocks,
+                    min_distance,
+                    detection.index,
+                    hex::to_str(record)
+                )?;
+            }
+        }
+        Commands::DetectMode(args) => {
+            let accuracy = mode_detection::measure_accuracy(&mut Random::new()?, args.trials);
+            writeln!(io::stdout().lock(), "{accuracy}")?;
         }
     }
 
@@ -0,0 +1,199 @@
+//! Structured cookies of the form `k=v&k=v`
+//!
+//! Keys and values are escaped, so they can contain the separators: `%`, `&` and `=`
+//! are encoded as `%25`, `%26` and `%3D`. Parsing accepts any `%XX` escape, as long
+//! as the result is valid UTF-8.
+//!
+//! <https://www.cryptopals.com/sets/2/challenges/13>
+
+use std::{error, fmt};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    /// A pair does not contain a `=`
+    MissingSeparator,
+    /// A `%` is not followed by two hex digits, or the unescaped text is not UTF-8
+    InvalidEscape,
+}
+
+impl error::Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::MissingSeparator => write!(f, "cookie pair without '='"),
+            Self::InvalidEscape => write!(f, "invalid escape sequence in cookie"),
+        }
+    }
+}
+
+fn escape(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '%' => output.push_str("%25"),
+            '&' => output.push_str("%26"),
+            '=' => output.push_str("%3D"),
+            c => output.push(c),
+        }
+    }
+    output
+}
+
+fn unescape(input: &str) -> Result<String, ParseError> {
+    let mut output = Vec::with_capacity(input.len());
+    let mut bytes = input.bytes();
+
+    while let Some(byte) = bytes.next() {
+        if byte == b'%' {
+            let digits = [
+                bytes.next().ok_or(ParseError::InvalidEscape)?,
+                bytes.next().ok_or(ParseError::InvalidEscape)?,
+            ];
+            // `from_str_radix` would also accept a sign
+            if !digits.iter().all(u8::is_ascii_hexdigit) {
+                return Err(ParseError::InvalidEscape);
+            }
+            let digits = std::str::from_utf8(&digits).expect("hex digits are ascii");
+            output.push(u8::from_str_radix(digits, 16).expect("digits are valid hex"));
+        } else {
+            output.push(byte);
+        }
+    }
+
+    String::from_utf8(output).map_err(|_e| ParseError::InvalidEscape)
+}
+
+/// The pairs of a cookie, in order. Keys may appear more than once.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cookie {
+    pairs: Vec<(String, String)>,
+}
+
+impl Cookie {
+    pub const fn new() -> Self {
+        Self { pairs: Vec::new() }
+    }
+
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        if input.is_empty() {
+            return Ok(Self::new());
+        }
+
+        let pairs = input
+            .split('&')
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').ok_or(ParseError::MissingSeparator)?;
+                Ok((unescape(key)?, unescape(value)?))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { pairs })
+    }
+
+    #[must_use]
+    pub fn with(mut self, key: &str, value: &str) -> Self {
+        self.push(key, value);
+        self
+    }
+
+    pub fn push(&mut self, key: &str, value: &str) {
+        self.pairs.push((key.to_owned(), value.to_owned()));
+    }
+
+    /// Returns the value of the first pair with `key`
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|pair| pair.0 == key)
+            .map(|pair| pair.1.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pairs
+            .iter()
+            .map(|pair| (pair.0.as_str(), pair.1.as_str()))
+    }
+}
+
+impl fmt::Display for Cookie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (key, value)) in self.iter().enumerate() {
+            if i != 0 {
+                write!(f, "&")?;
+            }
+            write!(f, "{}={}", escape(key), escape(value))?;
+        }
+        Ok(())
+    }
+}
+
+/// Encodes the profile of a user with `email`
+pub fn profile_for(email: &str) -> String {
+    Cookie::new()
+        .with("email", email)
+        .with("uid", "10")
+        .with("role", "user")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// taken from cryptopals challenge 13
+    fn test_parse() {
+        let cookie = Cookie::parse("foo=bar&baz=qux&zap=zazzle").expect("cookie is valid");
+
+        assert_eq!(
+            cookie.iter().collect::<Vec<_>>(),
+            [("foo", "bar"), ("baz", "qux"), ("zap", "zazzle")]
+        );
+        assert_eq!(cookie.get("baz"), Some("qux"));
+        assert_eq!(cookie.get("qux"), None);
+        assert_eq!(cookie.to_string(), "foo=bar&baz=qux&zap=zazzle");
+    }
+
+    #[test]
+    fn test_profile_for() {
+        assert_eq!(
+            profile_for("foo@bar.com"),
+            "email=foo@bar.com&uid=10&role=user"
+        );
+
+        let profile = profile_for("foo@bar.com&role=admin");
+        assert_eq!(profile, "email=foo@bar.com%26role%3Dadmin&uid=10&role=user");
+
+        let cookie = Cookie::parse(&profile).expect("cookie is valid");
+        assert_eq!(cookie.get("email"), Some("foo@bar.com&role=admin"));
+        assert_eq!(cookie.get("role"), Some("user"));
+    }
+
+    #[test]
+    fn test_escaping() {
+        let cookie = Cookie::new()
+            .with("k%=&", "v%=&")
+            .with("", "")
+            .with("ä", "100%");
+        let encoded = cookie.to_string();
+
+        assert_eq!(encoded, "k%25%3D%26=v%25%3D%26&=&ä=100%25");
+        assert_eq!(Cookie::parse(&encoded), Ok(cookie));
+        assert_eq!(Cookie::parse(""), Ok(Cookie::new()));
+        assert_eq!(Cookie::parse("a=%3d%26"), Ok(Cookie::new().with("a", "=&")));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert_eq!(
+            Cookie::parse("foo=bar&baz"),
+            Err(ParseError::MissingSeparator)
+        );
+        assert_eq!(Cookie::parse("foo=bar&"), Err(ParseError::MissingSeparator));
+        assert_eq!(Cookie::parse("foo=%2"), Err(ParseError::InvalidEscape));
+        assert_eq!(Cookie::parse("foo=%zz"), Err(ParseError::InvalidEscape));
+        assert_eq!(Cookie::parse("foo=%ff"), Err(ParseError::InvalidEscape));
+        assert_eq!(Cookie::parse("foo=%+f"), Err(ParseError::InvalidEscape));
+    }
+}
@@ -0,0 +1,408 @@
+//! `CTR_DRBG` with AES-128
+//!
+//! The state is an AES key and a counter block `V`. Output is the CTR mode keystream
+//! for the counter blocks following `V`, and after each request the key and `V` are
+//! replaced with further keystream, so earlier output cannot be recomputed from the
+//! new state.
+//!
+//! With the derivation function, inputs of any length are first compressed into a
+//! seed with CBC-MAC. Without it, the entropy input has to be exactly
+//! [`SEED_LENGTH`] bytes of full entropy, and all other inputs are limited to that
+//! length.
+
+use super::{Drbg, Error, ReseedCounter, MAX_RESEED_INTERVAL};
+use crate::aes::{
+    self,
+    cbc_mac::CbcMac,
+    ctr::{Counter, Keystream},
+    Key128,
+};
+
+/// The length of the key and the counter block together
+pub const SEED_LENGTH: usize = 32;
+
+/// The minimum length of the entropy input, i.e. the security strength of AES-128
+pub const MIN_ENTROPY_LENGTH: usize = 16;
+
+/// The minimum length of the nonce with the derivation function
+pub const MIN_NONCE_LENGTH: usize = 8;
+
+/// The fixed key used by the derivation function
+const DF_KEY: [u8; 16] = [
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+];
+
+pub struct CtrDrbg {
+    key: [u8; 16],
+    v: u128,
+    derivation_function: bool,
+    reseed_counter: ReseedCounter,
+}
+
+/// `Block_Cipher_df`: compresses `input` into a seed
+fn derive(input: &[&[u8]]) -> [u8; SEED_LENGTH] {
+    let len: usize = input.iter().map(|part| part.len()).sum();
+
+    // S = L || N || input || 0x80, padded with zeros to a multiple of the block size
+    let mut s = u32::try_from(len)
+        .expect("input length fits into u32")
+        .to_be_bytes()
+        .to_vec();
+    s.extend_from_slice(
+        &u32::try_from(SEED_LENGTH)
+            .expect("seed length fits into u32")
+            .to_be_bytes(),
+    );
+    for part in input {
+        s.extend_from_slice(part);
+    }
+    s.push(0x80);
+    s.resize(s.len().next_multiple_of(16), 0);
+
+    let mut temp = [0; SEED_LENGTH];
+    for (i, chunk) in (0_u32..).zip(temp.chunks_exact_mut(16)) {
+        let mut iv = [0; 16];
+        iv.iter_mut().zip(i.to_be_bytes()).for_each(|(v, i)| *v = i);
+
+        let mut mac = CbcMac::new(Key128::from_bytes(DF_KEY), [0; 16]);
+        mac.update(&iv);
+        mac.update(&s);
+        chunk.copy_from_slice(&mac.finalize());
+    }
+
+    let (key, x) = temp.split_at(16);
+    let key: [u8; 16] = key.try_into().expect("split at 16 bytes");
+    let mut x: [u8; 16] = x.try_into().expect("split at 16 bytes");
+
+    let mut output = [0; SEED_LENGTH];
+    for chunk in output.chunks_exact_mut(16) {
+        x = aes::encrypt_block(x, Key128::from_bytes(key));
+        chunk.copy_from_slice(&x);
+    }
+    output
+}
+
+/// Without the derivation function, shorter inputs are padded with zeros
+fn pad(input: &[u8]) -> Result<[u8; SEED_LENGTH], Error> {
+    if input.len() > SEED_LENGTH {
+        return Err(Error::InvalidInputLength);
+    }
+    let mut output = [0; SEED_LENGTH];
+    output.iter_mut().zip(input).for_each(|(o, i)| *o = *i);
+    Ok(output)
+}
+
+fn xor(mut a: [u8; SEED_LENGTH], b: [u8; SEED_LENGTH]) -> [u8; SEED_LENGTH] {
+    a.iter_mut().zip(b).for_each(|(a, b)| *a ^= b);
+    a
+}
+
+impl CtrDrbg {
+    /// Instantiates the DRBG with the derivation function
+    pub fn new(entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> Result<Self, Error> {
+        if entropy.len() < MIN_ENTROPY_LENGTH || nonce.len() < MIN_NONCE_LENGTH {
+            return Err(Error::InvalidInputLength);
+        }
+
+        Ok(Self::from_seed(
+            derive(&[entropy, nonce, personalization]),
+            true,
+        ))
+    }
+
+    /// Instantiates the DRBG without the derivation function. `entropy` has to be
+    /// exactly [`SEED_LENGTH`] bytes long.
+    pub fn without_derivation_function(
+        entropy: &[u8],
+        personalization: &[u8],
+    ) -> Result<Self, Error> {
+        let entropy: [u8; SEED_LENGTH] =
+            entropy.try_into().map_err(|_e| Error::InvalidInputLength)?;
+
+        Ok(Self::from_seed(xor(entropy, pad(personalization)?), false))
+    }
+
+    fn from_seed(seed: [u8; SEED_LENGTH], derivation_function: bool) -> Self {
+        let mut drbg = Self {
+            key: [0; 16],
+            v: 0,
+            derivation_function,
+            reseed_counter: ReseedCounter::new(),
+        };
+        drbg.update(seed);
+        drbg
+    }
+
+    /// Requires a reseed after `interval` requests instead of [`MAX_RESEED_INTERVAL`]
+    #[must_use]
+    pub fn with_reseed_interval(mut self, interval: u64) -> Self {
+        assert!(
+            (1..=MAX_RESEED_INTERVAL).contains(&interval),
+            "reseed interval needs to be between 1 and 2^48"
+        );
+        self.reseed_counter.interval = interval;
+        self
+    }
+
+    /// Fills `output` with the keystream for the blocks following `V`, and advances
+    /// `V` accordingly
+    fn keystream(&mut self, output: &mut [u8]) {
+        let keystream = Keystream::new(
+            Key128::from_bytes(self.key),
+            Counter::BigEndian128 {
+                initial: self.v.wrapping_add(1),
+            },
+        );
+        output.fill(0);
+        keystream.apply(0, output);
+
+        let blocks = u128::try_from(output.len().div_ceil(16)).expect("usize fits into u128");
+        self.v = self.v.wrapping_add(blocks);
+    }
+
+    /// `CTR_DRBG_Update`: replaces key and `V` with keystream combined with `provided`
+    fn update(&mut self, provided: [u8; SEED_LENGTH]) {
+        let mut temp = [0; SEED_LENGTH];
+        self.keystream(&mut temp);
+        let temp = xor(temp, provided);
+
+        let (key, v) = temp.split_at(16);
+        self.key = key.try_into().expect("split at 16 bytes");
+        self.v = u128::from_be_bytes(v.try_into().expect("split at 16 bytes"));
+    }
+
+    /// Turns additional input into a seed, either with the derivation function or by
+    /// padding
+    fn seed_material(&self, input: &[u8]) -> Result<[u8; SEED_LENGTH], Error> {
+        if self.derivation_function {
+            Ok(derive(&[input]))
+        } else {
+            pad(input)
+        }
+    }
+}
+
+impl Drbg for CtrDrbg {
+    fn reseed(&mut self, entropy: &[u8], additional_input: &[u8]) -> Result<(), Error> {
+        let seed = if self.derivation_function {
+            if entropy.len() < MIN_ENTROPY_LENGTH {
+                return Err(Error::InvalidInputLength);
+            }
+            derive(&[entropy, additional_input])
+        } else {
+            let entropy: [u8; SEED_LENGTH] =
+                entropy.try_into().map_err(|_e| Error::InvalidInputLength)?;
+            xor(entropy, pad(additional_input)?)
+        };
+
+        self.update(seed);
+        self.reseed_counter.reset();
+        Ok(())
+    }
+
+    fn generate(&mut self, output: &mut [u8], additional_input: &[u8]) -> Result<(), Error> {
+        self.reseed_counter.check(output.len())?;
+
+        let additional_input = if additional_input.is_empty() {
+            [0; SEED_LENGTH]
+        } else {
+            let seed = self.seed_material(additional_input)?;
+            self.update(seed);
+            seed
+        };
+
+        self.keystream(output);
+        self.update(additional_input);
+        self.reseed_counter.increment();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex::parse;
+
+    #[test]
+    /// taken from the CAVP `CTR_DRBG` vectors, AES-128 with derivation function and
+    /// without prediction resistance, count 0
+    fn test_ctr_drbg_from_cavp() {
+        let mut drbg = CtrDrbg::new(
+            &parse("890eb067acf7382eff80b0c73bc872c6"),
+            &parse("aad471ef3ef1d203"),
+            &[],
+        )
+        .expect("input is valid");
+
+        let mut output = [0; 64];
+        drbg.generate(&mut output, &[]).expect("request is valid");
+        drbg.generate(&mut output, &[]).expect("request is valid");
+        assert_eq!(
+            output.to_vec(),
+            parse("a5514ed7095f64f3d0d3a5760394ab42062f373a25072a6ea6bcfd8489e94af6cf18659fea22ed1ca0a9e33f718b115ee536b12809c31b72b08ddd8be1910fa3")
+        );
+    }
+
+    #[test]
+    /// taken from the CAVP `CTR_DRBG` vectors, AES-128 without derivation function and
+    /// without prediction resistance, count 0, which reseeds before the first request
+    fn test_ctr_drbg_without_df_from_cavp() {
+        let mut drbg = CtrDrbg::without_derivation_function(
+            &parse("ed1e7f21ef66ea5d8e2a85b9337245445b71d6393a4eecb0e63c193d0f72f9a9"),
+            &[],
+        )
+        .expect("input is valid");
+        drbg.reseed(
+            &parse("303fb519f0a4e17d6df0b6426aa0ecb2a36079bd48be47ad2a8dbfe48da3efad"),
+            &[],
+        )
+        .expect("entropy is valid");
+
+        let mut output = [0; 64];
+        drbg.generate(&mut output, &[]).expect("request is valid");
+        drbg.generate(&mut output, &[]).expect("request is valid");
+        assert_eq!(
+            output.to_vec(),
+            parse("f80111d08e874672f32f42997133a5210f7a9375e22cea70587f9cfafebe0f6a6aa2eb68e7dd9164536d53fa020fcab20f54caddfab7d6d91e5ffec1dfd8deaa")
+        );
+    }
+
+    #[test]
+    /// taken from the CAVP `CTR_DRBG` vectors, AES-128 with derivation function and
+    /// prediction resistance, count 0
+    fn test_ctr_drbg_prediction_resistance_from_cavp() {
+        let mut drbg = CtrDrbg::new(
+            &parse("92898f31fa1cff6d182f260643dff818"),
+            &parse("c2a4d972c3b9b697"),
+            &parse("ea65ee60264e7eb60e8268c4373c5c0b"),
+        )
+        .expect("input is valid");
+
+        let mut output = [0; 64];
+        drbg.generate_with_prediction_resistance(
+            &parse("20728a06f86f8dd441e272b7c42ce810"),
+            &mut output,
+            &parse("1a40fae3cc6c7ca0f8daba59236dad1d"),
+        )
+        .expect("request is valid");
+        drbg.generate_with_prediction_resistance(
+            &parse("3db0f094f305503317863e2208f7a501"),
+            &mut output,
+            &parse("9f72766cc746e5ed2e532012bc59318c"),
+        )
+        .expect("request is valid");
+        assert_eq!(
+            output.to_vec(),
+            parse("5a3539870f4d22a40924ee71c96fac720ad6f08882d0832873ec3f93d8ab4523f07eac45145e939fb1d676433db6e80888f6da89087742fe1af43fc423c51f68")
+        );
+    }
+
+    fn sequence(start: u8, len: u8) -> Vec<u8> {
+        (0..len).map(|i| start.wrapping_add(i)).collect()
+    }
+
+    fn with_df() -> CtrDrbg {
+        CtrDrbg::new(&sequence(0x40, 16), &sequence(0x20, 8), &sequence(0x60, 32))
+            .expect("input is valid")
+    }
+
+    fn without_df(personalization: &[u8]) -> CtrDrbg {
+        CtrDrbg::without_derivation_function(&sequence(0x00, 32), personalization)
+            .expect("input is valid")
+    }
+
+    /// Reseeds, then runs two requests with additional input and returns the output
+    /// of the second one
+    fn reseed_and_generate(mut drbg: CtrDrbg, entropy: &[u8]) -> Vec<u8> {
+        drbg.reseed(entropy, &sequence(0x80, 32))
+            .expect("entropy is valid");
+
+        let mut output = vec![0; 64];
+        drbg.generate(&mut output, &sequence(0x80, 32))
+            .expect("request is valid");
+        drbg.generate(&mut output, &sequence(0xa0, 32))
+            .expect("request is valid");
+        output
+    }
+
+    /// Runs two requests with prediction resistance and returns the output of the
+    /// second one
+    fn generate_with_prediction_resistance(mut drbg: CtrDrbg, entropy_len: u8) -> Vec<u8> {
+        let mut output = vec![0; 64];
+        drbg.generate_with_prediction_resistance(
+            &sequence(0xc0, entropy_len),
+            &mut output,
+            &sequence(0x80, 32),
+        )
+        .expect("request is valid");
+        drbg.generate_with_prediction_resistance(
+            &sequence(0xe0, entropy_len),
+            &mut output,
+            &sequence(0xa0, 32),
+        )
+        .expect("request is valid");
+        output
+    }
+
+    #[test]
+    /// cross-checked with the `CTR-DRBG` of OpenSSL
+    fn test_reseed() {
+        assert_eq!(
+            reseed_and_generate(with_df(), &sequence(0xc0, 16)),
+            parse("42066e5b77cbcb48d9b94281558d1e06dbdbd2ea47cf022cf1cce3eeacbcdfeb00bb5a8d33e39ebcb32dff435292559c7650b4f9517a8b228df35edb95ccfbd4")
+        );
+        assert_eq!(
+            reseed_and_generate(without_df(&sequence(0x60, 32)), &sequence(0xc0, 32)),
+            parse("0a64bdd099316db1616f85d4f10c3dbb537bb337514b4401f42868cd37d59061b3efecf8e9f56cf94d95fa87fe6827ff9d7f2a90e00875eef436305d3540ba18")
+        );
+    }
+
+    #[test]
+    /// cross-checked with the `CTR-DRBG` of OpenSSL
+    fn test_prediction_resistance() {
+        assert_eq!(
+            generate_with_prediction_resistance(with_df(), 16),
+            parse("7f3709a4dc113af0bdb209b85ebf31c759d6004a500fd80c11044a47ad3ae7f0ac5431dd1bace70abfc185832c4b2139a41ec2f210b1d205292883ab85c2ea4a")
+        );
+        assert_eq!(
+            generate_with_prediction_resistance(without_df(&[]), 32),
+            parse("760378a4351ee0891ebc16048887d2d849c4e5cebd2ce480ef5b4cc39f2cb6c7376bab68776808f904156bf0f5c07e1c8708a08b1d919bca65bf30a71916d14a")
+        );
+    }
+
+    #[test]
+    fn test_limits() {
+        assert_eq!(
+            CtrDrbg::new(&[0; 15], &[0; 8], &[]).err(),
+            Some(Error::InvalidInputLength)
+        );
+        assert_eq!(
+            CtrDrbg::new(&[0; 16], &[0; 7], &[]).err(),
+            Some(Error::InvalidInputLength)
+        );
+        assert_eq!(
+            CtrDrbg::without_derivation_function(&[0; 31], &[]).err(),
+            Some(Error::InvalidInputLength)
+        );
+        assert_eq!(
+            CtrDrbg::without_derivation_function(&[0; 32], &[0; 33]).err(),
+            Some(Error::InvalidInputLength)
+        );
+
+        let mut output = [0; 16];
+        let mut drbg = without_df(&[]);
+        assert_eq!(
+            drbg.generate(&mut output, &[0; 33]),
+            Err(Error::InvalidInputLength)
+        );
+        assert_eq!(drbg.reseed(&[0; 16], &[]), Err(Error::InvalidInputLength));
+
+        let mut drbg = with_df().with_reseed_interval(1);
+        // inputs of any length are accepted with the derivation function
+        drbg.generate(&mut output, &[0; 100])
+            .expect("request is valid");
+        assert_eq!(drbg.generate(&mut output, &[]), Err(Error::ReseedRequired));
+        drbg.reseed(&[0; 16], &[]).expect("entropy is valid");
+        drbg.generate(&mut output, &[]).expect("request is valid");
+    }
+}
@@ -0,0 +1,221 @@
+//! `Hash_DRBG`
+//!
+//! The state is a value `V` and a constant `C`, both `seedlen` bits long. Output is
+//! produced by hashing `V`, `V + 1`, `V + 2` and so on, and after each request `V` is
+//! advanced by the hash of itself, `C` and the reseed counter, all modulo
+//! `2^seedlen`.
+//!
+//! The security strength is that of the hash function, at most 256 bits. The entropy
+//! input needs to provide at least that much, and the nonce at least half of it.
+
+use std::marker::PhantomData;
+
+use super::{Drbg, Error, ReseedCounter, MAX_RESEED_INTERVAL};
+use crate::hash::Hash;
+
+pub struct HashDrbg<H: Hash> {
+    v: Vec<u8>,
+    c: Vec<u8>,
+    reseed_counter: ReseedCounter,
+    hash: PhantomData<H>,
+}
+
+/// Adds `value` to `sum`, both big-endian, modulo `2^(8 * sum.len())`
+fn add(sum: &mut [u8], value: &[u8]) {
+    let mut carry = 0_u16;
+    let mut value = value.iter().rev();
+
+    for s in sum.iter_mut().rev() {
+        let total = u16::from(*s)
+            .saturating_add(u16::from(value.next().copied().unwrap_or(0)))
+            .saturating_add(carry);
+        let [high, low] = total.to_be_bytes();
+        *s = low;
+        carry = u16::from(high);
+    }
+}
+
+impl<H: Hash> HashDrbg<H> {
+    /// The minimum length of the entropy input, i.e. the security strength in bytes
+    pub const MIN_ENTROPY_LENGTH: usize = if H::OUTPUT_SIZE < 32 {
+        H::OUTPUT_SIZE
+    } else {
+        32
+    };
+
+    /// The minimum length of the nonce
+    pub const MIN_NONCE_LENGTH: usize = Self::MIN_ENTROPY_LENGTH.div_ceil(2);
+
+    /// The length of `V` and `C` in bytes, 440 bits for hashes up to 256 bits and
+    /// 888 bits for larger ones
+    pub const SEED_LENGTH: usize = if H::OUTPUT_SIZE <= 32 { 55 } else { 111 };
+
+    pub fn new(entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> Result<Self, Error> {
+        if entropy.len() < Self::MIN_ENTROPY_LENGTH || nonce.len() < Self::MIN_NONCE_LENGTH {
+            return Err(Error::InvalidInputLength);
+        }
+
+        let v = Self::derive(&[entropy, nonce, personalization]);
+        let c = Self::derive(&[&[0x00], &v]);
+        Ok(Self {
+            v,
+            c,
+            reseed_counter: ReseedCounter::new(),
+            hash: PhantomData,
+        })
+    }
+
+    /// Requires a reseed after `interval` requests instead of [`MAX_RESEED_INTERVAL`]
+    #[must_use]
+    pub fn with_reseed_interval(mut self, interval: u64) -> Self {
+        assert!(
+            (1..=MAX_RESEED_INTERVAL).contains(&interval),
+            "reseed interval needs to be between 1 and 2^48"
+        );
+        self.reseed_counter.interval = interval;
+        self
+    }
+
+    /// `Hash_df`: derives [`Self::SEED_LENGTH`] bytes from the concatenation of `input`
+    fn derive(input: &[&[u8]]) -> Vec<u8> {
+        let bits =
+            u32::try_from(Self::SEED_LENGTH.saturating_mul(8)).expect("seed length fits into u32");
+
+        let mut output = Vec::with_capacity(Self::SEED_LENGTH.next_multiple_of(H::OUTPUT_SIZE));
+        for counter in 1_u8.. {
+            if output.len() >= Self::SEED_LENGTH {
+                break;
+            }
+
+            let mut hash = H::new();
+            hash.update(&[counter]);
+            hash.update(&bits.to_be_bytes());
+            for part in input {
+                hash.update(part);
+            }
+            output.extend(hash.finalize());
+        }
+
+        output.truncate(Self::SEED_LENGTH);
+        output
+    }
+
+    /// Hashes a single byte prefix followed by `parts`
+    fn hash(prefix: u8, parts: &[&[u8]]) -> Vec<u8> {
+        let mut hash = H::new();
+        hash.update(&[prefix]);
+        for part in parts {
+            hash.update(part);
+        }
+        hash.finalize()
+    }
+}
+
+impl<H: Hash> Drbg for HashDrbg<H> {
+    fn reseed(&mut self, entropy: &[u8], additional_input: &[u8]) -> Result<(), Error> {
+        if entropy.len() < Self::MIN_ENTROPY_LENGTH {
+            return Err(Error::InvalidInputLength);
+        }
+
+        self.v = Self::derive(&[&[0x01], &self.v, entropy, additional_input]);
+        self.c = Self::derive(&[&[0x00], &self.v]);
+        self.reseed_counter.reset();
+        Ok(())
+    }
+
+    fn generate(&mut self, output: &mut [u8], additional_input: &[u8]) -> Result<(), Error> {
+        self.reseed_counter.check(output.len())?;
+
+        if !additional_input.is_empty() {
+            let w = Self::hash(0x02, &[&self.v, additional_input]);
+            add(&mut self.v, &w);
+        }
+
+        // `Hashgen`
+        let mut data = self.v.clone();
+        for chunk in output.chunks_mut(H::OUTPUT_SIZE) {
+            let w = H::digest(&data);
+            chunk.iter_mut().zip(&w).for_each(|(c, w)| *c = *w);
+            add(&mut data, &[1]);
+        }
+
+        let h = Self::hash(0x03, &[&self.v]);
+        add(&mut self.v, &h);
+        add(&mut self.v, &self.c);
+        add(&mut self.v, &self.reseed_counter.count.to_be_bytes());
+        self.reseed_counter.increment();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{drbg::MAX_REQUEST_SIZE, hash::sha256::Sha256, hex::parse};
+
+    #[test]
+    /// taken from the CAVP `Hash_DRBG` vectors for SHA-256 with prediction resistance,
+    /// count 14
+    fn test_hash_drbg_from_cavp() {
+        let mut drbg = HashDrbg::<Sha256>::new(
+            &parse("066dc8ce75b28966a685163fe2a4d427fbdb616650616ba282fc332b4e6f1220"),
+            &parse("559f7c64897083ec2d7370d9f0e5071f"),
+            &parse("886f549aad1ac63d18cbcc6685daa2c2f79eb0894cb4aef1ac544fce57f15e11"),
+        )
+        .expect("input is valid");
+
+        let mut output = vec![0; 128];
+        drbg.generate_with_prediction_resistance(
+            &parse("ff80b7d26a05bc8a7abe53286b0eeb733b715a205bfa4ff63703deadb6ea0ef4"),
+            &mut output,
+            &parse("b7215f14ac7bafd0a91772ba22f719afbd20b311636c2b1e83e4a823353fc6ea"),
+        )
+        .expect("request is valid");
+        drbg.generate_with_prediction_resistance(
+            &parse("c73832534681ede37e03846d3c841767297d246c689241d2e775be7ec996293d"),
+            &mut output,
+            &parse("ced31f7e0dae5bb5c043e246b29473e2fd39512ead4569eee3e3803314aba7a3"),
+        )
+        .expect("request is valid");
+        assert_eq!(
+            output,
+            parse("60c234cfafb468033bf195e578ce266e1465326a96a9e03f8b893670ef62754d5e80d553a1f84950208b9343079f2ef856e9c570618597b5dc82a2daeaa3fd9b2fd2a0d71bc62935ccb83da0679805a0e31efee4f0e513b08317faca935e382948d272db763e6df32510ff1b99fff8c60eb0dd292ebcbbc80a016ed3b00e4eab")
+        );
+    }
+
+    #[test]
+    fn test_add() {
+        let mut sum = [0x00, 0xff, 0xff];
+        add(&mut sum, &[0x01]);
+        assert_eq!(sum, [0x01, 0x00, 0x00]);
+
+        let mut sum = [0xff, 0xff];
+        add(&mut sum, &[0x00, 0x00, 0x02]);
+        assert_eq!(sum, [0x00, 0x01]);
+    }
+
+    #[test]
+    fn test_limits() {
+        assert_eq!(
+            HashDrbg::<Sha256>::new(&[0; 31], &[0; 16], &[]).err(),
+            Some(Error::InvalidInputLength)
+        );
+        assert_eq!(
+            HashDrbg::<Sha256>::new(&[0; 32], &[0; 15], &[]).err(),
+            Some(Error::InvalidInputLength)
+        );
+
+        let mut drbg = HashDrbg::<Sha256>::new(&[0; 32], &[0; 16], &[])
+            .expect("input is valid")
+            .with_reseed_interval(2);
+        let mut output = vec![0; MAX_REQUEST_SIZE + 1];
+        assert_eq!(drbg.generate(&mut output, &[]), Err(Error::RequestTooLarge));
+
+        drbg.generate(&mut [0; 10], &[]).expect("request is valid");
+        drbg.generate(&mut [0; 10], &[]).expect("request is valid");
+        assert_eq!(drbg.generate(&mut [0; 10], &[]), Err(Error::ReseedRequired));
+
+        drbg.reseed(&[1; 32], &[]).expect("entropy is valid");
+        drbg.generate(&mut [0; 10], &[]).expect("request is valid");
+    }
+}
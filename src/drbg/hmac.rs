@@ -0,0 +1,311 @@
+//! `HMAC_DRBG`
+//!
+//! The state is an HMAC key `K` and a value `V` of the size of the hash output. Output
+//! is produced by repeatedly replacing `V` with `HMAC(K, V)`, and after each request
+//! `K` and `V` are derived anew from the old state and the additional input.
+//!
+//! The security strength is that of the hash function, at most 256 bits. The entropy
+//! input needs to provide at least that much, and the nonce at least half of it.
+
+use std::marker::PhantomData;
+
+use super::{Drbg, Error, ReseedCounter, MAX_RESEED_INTERVAL};
+use crate::{
+    hash::Hash,
+    hmac::{self, Hmac},
+};
+
+pub struct HmacDrbg<H: Hash> {
+    key: Vec<u8>,
+    v: Vec<u8>,
+    reseed_counter: ReseedCounter,
+    hash: PhantomData<H>,
+}
+
+impl<H: Hash> HmacDrbg<H> {
+    /// The minimum length of the entropy input, i.e. the security strength in bytes
+    pub const MIN_ENTROPY_LENGTH: usize = if H::OUTPUT_SIZE < 32 {
+        H::OUTPUT_SIZE
+    } else {
+        32
+    };
+
+    /// The minimum length of the nonce
+    pub const MIN_NONCE_LENGTH: usize = Self::MIN_ENTROPY_LENGTH.div_ceil(2);
+
+    pub fn new(entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> Result<Self, Error> {
+        if entropy.len() < Self::MIN_ENTROPY_LENGTH || nonce.len() < Self::MIN_NONCE_LENGTH {
+            return Err(Error::InvalidInputLength);
+        }
+
+        let mut drbg = Self {
+            key: vec![0x00; H::OUTPUT_SIZE],
+            v: vec![0x01; H::OUTPUT_SIZE],
+            reseed_counter: ReseedCounter::new(),
+            hash: PhantomData,
+        };
+        drbg.update(&[entropy, nonce, personalization]);
+        Ok(drbg)
+    }
+
+    /// Requires a reseed after `interval` requests instead of [`MAX_RESEED_INTERVAL`]
+    #[must_use]
+    pub fn with_reseed_interval(mut self, interval: u64) -> Self {
+        assert!(
+            (1..=MAX_RESEED_INTERVAL).contains(&interval),
+            "reseed interval needs to be between 1 and 2^48"
+        );
+        self.reseed_counter.interval = interval;
+        self
+    }
+
+    /// `HMAC_DRBG_Update`: derives new `K` and `V` from the concatenation of `provided`
+    fn update(&mut self, provided: &[&[u8]]) {
+        let is_empty = provided.iter().all(|part| part.is_empty());
+
+        for separator in [0x00, 0x01] {
+            let mut mac = Hmac::<H>::new(&self.key);
+            mac.update(&self.v);
+            mac.update(&[separator]);
+            for part in provided {
+                mac.update(part);
+            }
+            self.key = mac.finalize();
+            self.v = hmac::hmac::<H>(&self.key, &self.v);
+
+            // without input, a single round suffices
+            if is_empty {
+                break;
+            }
+        }
+    }
+}
+
+impl<H: Hash> Drbg for HmacDrbg<H> {
+    fn reseed(&mut self, entropy: &[u8], additional_input: &[u8]) -> Result<(), Error> {
+        if entropy.len() < Self::MIN_ENTROPY_LENGTH {
+            return Err(Error::InvalidInputLength);
+        }
+
+        self.update(&[entropy, additional_input]);
+        self.reseed_counter.reset();
+        Ok(())
+    }
+
+    fn generate(&mut self, output: &mut [u8], additional_input: &[u8]) -> Result<(), Error> {
+        self.reseed_counter.check(output.len())?;
+
+        if !additional_input.is_empty() {
+            self.update(&[additional_input]);
+        }
+
+        for chunk in output.chunks_mut(H::OUTPUT_SIZE) {
+            self.v = hmac::hmac::<H>(&self.key, &self.v);
+            chunk.iter_mut().zip(&self.v).for_each(|(c, v)| *c = *v);
+        }
+
+        self.update(&[additional_input]);
+        self.reseed_counter.increment();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{drbg::MAX_REQUEST_SIZE, hash::sha256::Sha256, hex::parse};
+
+    fn sequence(start: u8, len: u8) -> Vec<u8> {
+        (0..len).map(|i| start.wrapping_add(i)).collect()
+    }
+
+    struct TestCase {
+        entropy: &'static str,
+        nonce: &'static str,
+        additional_input: [&'static str; 2],
+        output: &'static str,
+    }
+
+    #[test]
+    /// taken from the CAVP `HMAC_DRBG` vectors for SHA-256 without prediction
+    /// resistance. The output of the second request is compared.
+    fn test_hmac_drbg_from_cavp() {
+        let cases = [
+            TestCase {
+                entropy: "ca851911349384bffe89de1cbdc46e6831e44d34a4fb935ee285dd14b71a7488",
+                nonce: "659ba96c601dc69fc902940805ec0ca8",
+                additional_input: ["", ""],
+                output: "e528e9abf2dece54d47c7e75e5fe302149f817ea9fb4bee6f4199697d04d5b89d54fbb978a15b5c443c9ec21036d2460b6f73ebad0dc2aba6e624abf07745bc107694bb7547bb0995f70de25d6b29e2d3011bb19d27676c07162c8b5ccde0668961df86803482cb37ed6d5c0bb8d50cf1f50d476aa0458bdaba806f48be9dcb8",
+            },
+            TestCase {
+                entropy: "79737479ba4e7642a221fcfd1b820b134e9e3540a35bb48ffae29c20f5418ea3",
+                nonce: "3593259c092bef4129bc2c6c9e19f343",
+                additional_input: ["", ""],
+                output: "cf5ad5984f9e43917aa9087380dac46e410ddc8a7731859c84e9d0f31bd43655b924159413e2293b17610f211e09f770f172b8fb693a35b85d3b9e5e63b1dc252ac0e115002e9bedfb4b5b6fd43f33b8e0eafb2d072e1a6fee1f159df9b51e6c8da737e60d5032dd30544ec51558c6f080bdbdab1de8a939e961e06b5f1aca37",
+            },
+            TestCase {
+                entropy: "d3cc4d1acf3dde0c4bd2290d262337042dc632948223d3a2eaab87da44295fbd",
+                nonce: "0109b0e729f457328aa18569a9224921",
+                additional_input: [
+                    "3c311848183c9a212a26f27f8c6647e40375e466a0857cc39c4e47575d53f1f6",
+                    "fcb9abd19ccfbccef88c9c39bfb3dd7b1c12266c9808992e305bc3cff566e4e4",
+                ],
+                output: "9c7b758b212cd0fcecd5daa489821712e3cdea4467b560ef5ddc24ab47749a1f1ffdbbb118f4e62fcfca3371b8fbfc5b0646b83e06bfbbab5fac30ea09ea2bc76f1ea568c9be0444b2cc90517b20ca825f2d0eccd88e7175538b85d90ab390183ca6395535d34473af6b5a5b88f5a59ee7561573337ea819da0dcc3573a22974",
+            },
+        ];
+
+        for case in cases {
+            let mut drbg = HmacDrbg::<Sha256>::new(&parse(case.entropy), &parse(case.nonce), &[])
+                .expect("input is valid");
+
+            let mut output = vec![0; 128];
+            for additional_input in case.additional_input {
+                drbg.generate(&mut output, &parse(additional_input))
+                    .expect("request is valid");
+            }
+            assert_eq!(output, parse(case.output));
+        }
+    }
+
+    #[test]
+    /// taken from the CAVP `HMAC_DRBG` vectors for SHA-256 without prediction
+    /// resistance, count 0, which reseeds before the first request
+    fn test_hmac_drbg_reseed_from_cavp() {
+        let mut drbg = HmacDrbg::<Sha256>::new(
+            &parse("06032cd5eed33f39265f49ecb142c511da9aff2af71203bffaf34a9ca5bd9c0d"),
+            &parse("0e66f71edc43e42a45ad3c6fc6cdc4df"),
+            &[],
+        )
+        .expect("input is valid");
+        drbg.reseed(
+            &parse("01920a4e669ed3a85ae8a33b35a74ad7fb2a6bb4cf395ce00334a9c9a5a5d552"),
+            &[],
+        )
+        .expect("entropy is valid");
+
+        let mut output = vec![0; 128];
+        drbg.generate(&mut output, &[]).expect("request is valid");
+        drbg.generate(&mut output, &[]).expect("request is valid");
+        assert_eq!(
+            output,
+            parse("76fc79fe9b50beccc991a11b5635783a83536add03c157fb30645e611c2898bb2b1bc215000209208cd506cb28da2a51bdb03826aaf2bd2335d576d519160842e7158ad0949d1a9ec3e66ea1b1a064b005de914eac2e9d4f2d72a8616a80225422918250ff66a41bd2f864a6a38cc5b6499dc43f7f2bd09e1e0f8f5885935124")
+        );
+    }
+
+    #[test]
+    /// taken from the CAVP `HMAC_DRBG` vectors for SHA-256 with prediction resistance,
+    /// count 0
+    fn test_hmac_drbg_prediction_resistance_from_cavp() {
+        let mut drbg = HmacDrbg::<Sha256>::new(
+            &parse("ca851911349384bffe89de1cbdc46e6831e44d34a4fb935ee285dd14b71a7488"),
+            &parse("659ba96c601dc69fc902940805ec0ca8"),
+            &parse("e72dd8590d4ed5295515c35ed6199e9d211b8f069b3058caa6670b96ef1208d0"),
+        )
+        .expect("input is valid");
+
+        let mut output = vec![0; 128];
+        drbg.generate_with_prediction_resistance(
+            &parse("5cacc68165a2e2ee20812f35ec73a79dbf30fd475476ac0c44fc6174cdac2b55"),
+            &mut output,
+            &parse("793a7ef8f6f0482beac542bb785c10f8b7b406a4de92667ab168ecc2cf7573c6"),
+        )
+        .expect("request is valid");
+        drbg.generate_with_prediction_resistance(
+            &parse("8df013b4d103523073917ddf6a869793059e9943fc8654549e7ab22f7c29f122"),
+            &mut output,
+            &parse("2238cdb4e23d629fe0c2a83dd8d5144ce1a6229ef41dabe2a99ff722e510b530"),
+        )
+        .expect("request is valid");
+        assert_eq!(
+            output,
+            parse("b1d17c002a7febd28412d8e58a7f32318e4ee3605a99b05b05d59356d5f0c6b4960a4b8f963b7efa55bb6872fbeac7b99b78dea8f3531973637c946a9cab3349744b24a0851dd47f2b3b460c2c61846e91181d62d42c60a4efda5ed57902bfd702b349c54952c7f644769d8ef4015ecc5f5bbd4af06134688e30050e0497fb0a")
+        );
+    }
+
+    fn drbg() -> HmacDrbg<Sha256> {
+        HmacDrbg::new(
+            &sequence(0x00, 32),
+            &sequence(0x40, 16),
+            &sequence(0x60, 32),
+        )
+        .expect("input is valid")
+    }
+
+    #[test]
+    /// cross-checked with the `HMAC-DRBG` of OpenSSL
+    fn test_reseed() {
+        let mut drbg = drbg();
+        drbg.reseed(&sequence(0xc0, 32), &sequence(0x80, 32))
+            .expect("entropy is valid");
+
+        let mut output = [0; 128];
+        drbg.generate(&mut output, &sequence(0x80, 32))
+            .expect("request is valid");
+        drbg.generate(&mut output, &sequence(0xa0, 32))
+            .expect("request is valid");
+        assert_eq!(
+            output.to_vec(),
+            parse("e68f021ef528459e16eec27b9a95814ac0d68151beb8481bab20d886190a04777acab156423e85efa9946d0bd46799db38a7bc75baa02fdb0b9b73f50d5b2d0f032b0a219809b8e303da051ed0483a58168a82874d0a8b0539fe87eae10a932f1d653ee05f6b553d2e8e56b8e610c14c12dbdfbd12ce04c8a68572d892834d4b")
+        );
+    }
+
+    #[test]
+    /// cross-checked with the `HMAC-DRBG` of OpenSSL
+    fn test_prediction_resistance() {
+        let mut drbg = drbg();
+
+        let mut output = [0; 128];
+        drbg.generate_with_prediction_resistance(
+            &sequence(0xc0, 32),
+            &mut output,
+            &sequence(0x80, 32),
+        )
+        .expect("request is valid");
+        drbg.generate_with_prediction_resistance(
+            &sequence(0xe0, 32),
+            &mut output,
+            &sequence(0xa0, 32),
+        )
+        .expect("request is valid");
+        assert_eq!(
+            output.to_vec(),
+            parse("4da1d3ec3f1ed0fe2b930b5c1f6430559e3cb9140a3ea7266d7976b787376bcfbca54524669ee52b4c4b6dda11c714d1ff7e89b220c30be344f8026076b3df9bdb3cb63087f9544fe14f03dd791232026724e1d943a53c9c982e1cb5d347d034135bdd034d0e59e902e4744273d65431c1902698631a7c053a7a031729799ce7")
+        );
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let mut a = [0; 100];
+        let mut b = [0; 100];
+        drbg().generate(&mut a, b"").expect("request is valid");
+        drbg().generate(&mut b, b"").expect("request is valid");
+        assert_eq!(a, b);
+
+        // different additional input gives different output
+        drbg().generate(&mut b, b"other").expect("request is valid");
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_limits() {
+        assert_eq!(
+            HmacDrbg::<Sha256>::new(&[0; 31], &[0; 16], &[]).err(),
+            Some(Error::InvalidInputLength)
+        );
+        assert_eq!(
+            HmacDrbg::<Sha256>::new(&[0; 32], &[0; 15], &[]).err(),
+            Some(Error::InvalidInputLength)
+        );
+
+        let mut drbg = drbg().with_reseed_interval(2);
+        let mut output = vec![0; MAX_REQUEST_SIZE + 1];
+        assert_eq!(drbg.generate(&mut output, &[]), Err(Error::RequestTooLarge));
+
+        drbg.generate(&mut [0; 10], &[]).expect("request is valid");
+        drbg.generate(&mut [0; 10], &[]).expect("request is valid");
+        assert_eq!(drbg.generate(&mut [0; 10], &[]), Err(Error::ReseedRequired));
+
+        drbg.reseed(&[1; 32], &[]).expect("entropy is valid");
+        drbg.generate(&mut [0; 10], &[]).expect("request is valid");
+    }
+}
@@ -0,0 +1,102 @@
+//! Deterministic random bit generators (DRBGs) from SP 800-90A
+//!
+//! A DRBG expands a seed into a stream of pseudorandom bytes. The same entropy input,
+//! nonce and personalization string always produce the same output, so all
+//! randomness derived from a DRBG can be reproduced from those inputs.
+//!
+//! <https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-90Ar1.pdf>
+//!
+//! The caller provides all entropy explicitly, both on instantiation and when
+//! reseeding. [`Drbg::generate_with_prediction_resistance()`] reseeds with fresh
+//! entropy before each request, so even an attacker that learned the internal state
+//! cannot predict the output.
+
+use std::{error, fmt};
+
+pub mod ctr;
+pub mod hash;
+pub mod hmac;
+
+/// The maximum number of requests between reseeds permitted by SP 800-90A
+pub const MAX_RESEED_INTERVAL: u64 = 1 << 48;
+
+/// The maximum number of bytes returned by a single request, for all DRBGs
+pub const MAX_REQUEST_SIZE: usize = 1 << 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The entropy input, nonce, personalization string or additional input is too
+    /// short or too long
+    InvalidInputLength,
+    /// More than [`MAX_REQUEST_SIZE`] bytes were requested at once
+    RequestTooLarge,
+    /// The reseed interval is exhausted, the DRBG needs to be reseeded before it
+    /// generates more output
+    ReseedRequired,
+}
+
+impl error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::InvalidInputLength => write!(f, "invalid length of DRBG input"),
+            Self::RequestTooLarge => write!(f, "too many bytes requested from DRBG"),
+            Self::ReseedRequired => write!(f, "DRBG needs to be reseeded"),
+        }
+    }
+}
+
+pub trait Drbg {
+    /// Mixes fresh `entropy` into the state and resets the reseed counter
+    fn reseed(&mut self, entropy: &[u8], additional_input: &[u8]) -> Result<(), Error>;
+
+    /// Fills `output` with pseudorandom bytes
+    fn generate(&mut self, output: &mut [u8], additional_input: &[u8]) -> Result<(), Error>;
+
+    /// Reseeds with `entropy` and `additional_input`, then fills `output`
+    fn generate_with_prediction_resistance(
+        &mut self,
+        entropy: &[u8],
+        output: &mut [u8],
+        additional_input: &[u8],
+    ) -> Result<(), Error> {
+        self.reseed(entropy, additional_input)?;
+        self.generate(output, &[])
+    }
+}
+
+/// Counts the requests since the last reseed
+#[derive(Debug, Clone)]
+struct ReseedCounter {
+    count: u64,
+    interval: u64,
+}
+
+impl ReseedCounter {
+    const fn new() -> Self {
+        Self {
+            count: 1,
+            interval: MAX_RESEED_INTERVAL,
+        }
+    }
+
+    /// Checks whether another request is allowed, and whether its size is valid
+    const fn check(&self, len: usize) -> Result<(), Error> {
+        if self.count > self.interval {
+            return Err(Error::ReseedRequired);
+        }
+        if len > MAX_REQUEST_SIZE {
+            return Err(Error::RequestTooLarge);
+        }
+        Ok(())
+    }
+
+    const fn increment(&mut self) {
+        self.count = self.count.saturating_add(1);
+    }
+
+    const fn reset(&mut self) {
+        self.count = 1;
+    }
+}
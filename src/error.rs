@@ -1,5 +1,7 @@
 use std::{error, fmt, io};
 
+use crate::{aes, attack, cookie, drbg, pkcs7};
+
 #[derive(Debug)]
 pub struct Error(pub String);
 
@@ -16,3 +18,39 @@ impl From<io::Error> for Error {
         Self(value.to_string())
     }
 }
+
+impl From<aes::AuthenticationError> for Error {
+    fn from(value: aes::AuthenticationError) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl From<aes::key_wrap::UnwrapError> for Error {
+    fn from(value: aes::key_wrap::UnwrapError) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl From<pkcs7::InvalidPadding> for Error {
+    fn from(value: pkcs7::InvalidPadding) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl From<drbg::Error> for Error {
+    fn from(value: drbg::Error) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl From<attack::Error> for Error {
+    fn from(value: attack::Error) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl From<cookie::ParseError> for Error {
+    fn from(value: cookie::ParseError) -> Self {
+        Self(value.to_string())
+    }
+}
@@ -0,0 +1,194 @@
+#![expect(
+    clippy::little_endian_bytes,
+    reason = "MD5 reads words and encodes the length in little-endian"
+)]
+//! MD5
+//!
+//! MD5 is broken and must not be used where collision resistance matters. It is only
+//! here because `openssl enc` used it for key derivation before OpenSSL 1.1.0.
+//!
+//! <https://www.rfc-editor.org/rfc/rfc1321>
+
+use super::{BlockBuffer, Hash};
+
+/// The per-round shift amounts
+const S: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9,
+    14, 20, 5, 9, 14, 20, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10, 15,
+    21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+/// The integer part of `abs(sin(i + 1)) * 2^32`
+const K: [u32; 64] = [
+    0xd76a_a478,
+    0xe8c7_b756,
+    0x2420_70db,
+    0xc1bd_ceee,
+    0xf57c_0faf,
+    0x4787_c62a,
+    0xa830_4613,
+    0xfd46_9501,
+    0x6980_98d8,
+    0x8b44_f7af,
+    0xffff_5bb1,
+    0x895c_d7be,
+    0x6b90_1122,
+    0xfd98_7193,
+    0xa679_438e,
+    0x49b4_0821,
+    0xf61e_2562,
+    0xc040_b340,
+    0x265e_5a51,
+    0xe9b6_c7aa,
+    0xd62f_105d,
+    0x0244_1453,
+    0xd8a1_e681,
+    0xe7d3_fbc8,
+    0x21e1_cde6,
+    0xc337_07d6,
+    0xf4d5_0d87,
+    0x455a_14ed,
+    0xa9e3_e905,
+    0xfcef_a3f8,
+    0x676f_02d9,
+    0x8d2a_4c8a,
+    0xfffa_3942,
+    0x8771_f681,
+    0x6d9d_6122,
+    0xfde5_380c,
+    0xa4be_ea44,
+    0x4bde_cfa9,
+    0xf6bb_4b60,
+    0xbebf_bc70,
+    0x289b_7ec6,
+    0xeaa1_27fa,
+    0xd4ef_3085,
+    0x0488_1d05,
+    0xd9d4_d039,
+    0xe6db_99e5,
+    0x1fa2_7cf8,
+    0xc4ac_5665,
+    0xf429_2244,
+    0x432a_ff97,
+    0xab94_23a7,
+    0xfc93_a039,
+    0x655b_59c3,
+    0x8f0c_cc92,
+    0xffef_f47d,
+    0x8584_5dd1,
+    0x6fa8_7e4f,
+    0xfe2c_e6e0,
+    0xa301_4314,
+    0x4e08_11a1,
+    0xf753_7e82,
+    0xbd3a_f235,
+    0x2ad7_d2bb,
+    0xeb86_d391,
+];
+
+const INITIAL_STATE: [u32; 4] = [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476];
+
+#[derive(Clone)]
+pub struct Md5 {
+    state: [u32; 4],
+    buffer: BlockBuffer,
+}
+
+#[expect(
+    clippy::many_single_char_names,
+    reason = "the names follow the algorithm description in RFC 1321"
+)]
+fn compress(state: &mut [u32; 4], block: &[u8; 64]) {
+    let mut m = [0_u32; 16];
+    for (m, chunk) in m.iter_mut().zip(block.chunks_exact(4)) {
+        *m = u32::from_le_bytes(chunk.try_into().expect("chunk is exactly 4 bytes long"));
+    }
+
+    let [mut a, mut b, mut c, mut d] = *state;
+
+    for (i, (k, s)) in K.iter().zip(S).enumerate() {
+        // the message word used in each round follows a different pattern per round
+        let (f, g) = match i {
+            0..16 => ((b & c) | (!b & d), i),
+            16..32 => ((d & b) | (!d & c), i.wrapping_mul(5).wrapping_add(1)),
+            32..48 => (b ^ c ^ d, i.wrapping_mul(3).wrapping_add(5)),
+            _ => (c ^ (b | !d), i.wrapping_mul(7)),
+        };
+        let word = m.get(g & 15).expect("index is masked to 16 words");
+
+        let f = f.wrapping_add(a).wrapping_add(*k).wrapping_add(*word);
+        a = d;
+        d = c;
+        c = b;
+        b = b.wrapping_add(f.rotate_left(s));
+    }
+
+    for (s, v) in state.iter_mut().zip([a, b, c, d]) {
+        *s = s.wrapping_add(v);
+    }
+}
+
+impl Hash for Md5 {
+    const BLOCK_SIZE: usize = 64;
+    const OUTPUT_SIZE: usize = 16;
+
+    fn new() -> Self {
+        Self {
+            state: INITIAL_STATE,
+            buffer: BlockBuffer::new(),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        let state = &mut self.state;
+        self.buffer.update(data, |block| compress(state, block));
+    }
+
+    fn finalize(mut self) -> Vec<u8> {
+        let state = &mut self.state;
+        self.buffer
+            .finish(u64::to_le_bytes, |block| compress(state, block));
+
+        self.state.iter().flat_map(|s| s.to_le_bytes()).collect()
+    }
+}
+
+/// Hashes `data`
+pub fn md5(data: &[u8]) -> [u8; 16] {
+    Md5::digest(data)
+        .try_into()
+        .expect("digest is exactly 16 bytes long")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex::parse;
+
+    #[test]
+    /// taken from the test suite in RFC 1321
+    fn test_md5_from_spec() {
+        let cases: [(&[u8], &str); 7] = [
+            (b"", "d41d8cd98f00b204e9800998ecf8427e"),
+            (b"a", "0cc175b9c0f1b6a831c399e269772661"),
+            (b"abc", "900150983cd24fb0d6963f7d28e17f72"),
+            (b"message digest", "f96b697d7cb7938d525a2f31aaf161d0"),
+            (
+                b"abcdefghijklmnopqrstuvwxyz",
+                "c3fcd3d76192e4007dfb496cca67e13b",
+            ),
+            (
+                b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+                "d174ab98d277d9f5a5611c2c9f419d9f",
+            ),
+            (
+                b"12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+                "57edf4a22be3c955ac49da2e2107b67a",
+            ),
+        ];
+
+        for (input, digest) in cases {
+            assert_eq!(md5(input).to_vec(), parse(digest));
+        }
+    }
+}
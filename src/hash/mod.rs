@@ -0,0 +1,89 @@
+//! Cryptographic hash functions
+//!
+//! The hash functions implement [`Hash`], which provides the streaming interface that
+//! constructions like [`crate::hmac`] are generic over.
+
+pub mod md5;
+pub mod sha256;
+
+/// A hash function that processes its input in blocks
+pub trait Hash: Clone {
+    /// The size of the blocks processed by the compression function, in bytes
+    const BLOCK_SIZE: usize;
+    /// The size of the digest, in bytes
+    const OUTPUT_SIZE: usize;
+
+    fn new() -> Self;
+
+    fn update(&mut self, data: &[u8]);
+
+    fn finalize(self) -> Vec<u8>;
+
+    /// Hashes `data` in one go
+    fn digest(data: &[u8]) -> Vec<u8> {
+        let mut hash = Self::new();
+        hash.update(data);
+        hash.finalize()
+    }
+}
+
+/// Collects input into 64 byte blocks for a Merkle–Damgård compression function and
+/// applies the final padding: a single 1 bit, zeros, and the message length in bits.
+#[derive(Clone)]
+struct BlockBuffer {
+    block: [u8; 64],
+    buffered: usize,
+    /// the total number of bytes processed
+    len: u64,
+}
+
+impl BlockBuffer {
+    const fn new() -> Self {
+        Self {
+            block: [0; 64],
+            buffered: 0,
+            len: 0,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8], mut compress: impl FnMut(&[u8; 64])) {
+        self.len = self
+            .len
+            .wrapping_add(u64::try_from(data.len()).expect("usize fits into u64"));
+
+        while !data.is_empty() {
+            let free = self
+                .block
+                .get_mut(self.buffered..)
+                .expect("buffered bytes never exceed the block");
+            let (head, tail) = data.split_at(free.len().min(data.len()));
+            free.iter_mut().zip(head).for_each(|(b, d)| *b = *d);
+            self.buffered = self.buffered.saturating_add(head.len());
+            data = tail;
+
+            if self.buffered == self.block.len() {
+                compress(&self.block);
+                self.buffered = 0;
+            }
+        }
+    }
+
+    /// Pads the message, with the length encoded by `encode_len`, and compresses the
+    /// remaining blocks
+    fn finish(mut self, encode_len: impl Fn(u64) -> [u8; 8], mut compress: impl FnMut(&[u8; 64])) {
+        let bits = encode_len(self.len.wrapping_mul(8));
+
+        // the length goes into the last 8 bytes of a block, which needs to be an
+        // additional one if they are already taken
+        let zeros = if self.buffered < 56 { 55_usize } else { 119 }
+            .checked_sub(self.buffered)
+            .expect("at most 63 bytes are buffered");
+
+        let mut padding = vec![0x80];
+        padding.resize(zeros.saturating_add(1), 0);
+        padding.extend_from_slice(&bits);
+
+        self.update(&padding, &mut compress);
+        debug_assert!(self.buffered == 0, "padding ends on a block boundary");
+    }
+}
@@ -128,3 +128,9 @@ pub fn to_str(data: &[u8]) -> String {
         .map(|c| -> char { c.into() })
         .collect()
 }
+
+/// Parses hex test data, which is known to be valid
+#[cfg(test)]
+pub(crate) fn parse(input: &str) -> Vec<u8> {
+    parse_hex_string(input).expect("test data is valid hex")
+}
@@ -0,0 +1,126 @@
+//! HMAC
+//!
+//! A MAC built from a hash function by hashing the message with the key mixed into an
+//! inner block, and hashing that digest again with the key mixed into an outer block.
+//!
+//! <https://www.rfc-editor.org/rfc/rfc2104>
+
+use crate::{aes::AuthenticationError, hash::Hash};
+
+const IPAD: u8 = 0x36;
+const OPAD: u8 = 0x5c;
+
+#[derive(Clone)]
+pub struct Hmac<H: Hash> {
+    inner: H,
+    outer: H,
+}
+
+impl<H: Hash> Hmac<H> {
+    pub fn new(key: &[u8]) -> Self {
+        // keys longer than a block are hashed first, shorter ones are padded with zeros
+        let mut block = if key.len() > H::BLOCK_SIZE {
+            H::digest(key)
+        } else {
+            key.to_vec()
+        };
+        block.resize(H::BLOCK_SIZE, 0);
+
+        let pad = |value: u8| -> H {
+            let mut hash = H::new();
+            hash.update(&block.iter().map(|b| b ^ value).collect::<Vec<u8>>());
+            hash
+        };
+
+        Self {
+            inner: pad(IPAD),
+            outer: pad(OPAD),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        let mut outer = self.outer;
+        outer.update(&self.inner.finalize());
+        outer.finalize()
+    }
+
+    /// Compares the tag in constant time
+    pub fn verify(self, tag: &[u8]) -> Result<(), AuthenticationError> {
+        if crate::aes::constant_time_eq(&self.finalize(), tag) {
+            Ok(())
+        } else {
+            Err(AuthenticationError)
+        }
+    }
+}
+
+pub fn hmac<H: Hash>(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<H>::new(key);
+    mac.update(message);
+    mac.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hash::sha256::Sha256, hex::parse};
+
+    struct TestCase {
+        key: Vec<u8>,
+        data: Vec<u8>,
+        mac: &'static str,
+    }
+
+    #[test]
+    /// taken from RFC 4231
+    fn test_hmac_sha256_from_spec() {
+        let cases = [
+            TestCase {
+                key: vec![0x0b; 20],
+                data: b"Hi There".to_vec(),
+                mac: "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+            },
+            TestCase {
+                key: b"Jefe".to_vec(),
+                data: b"what do ya want for nothing?".to_vec(),
+                mac: "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            },
+            TestCase {
+                key: vec![0xaa; 20],
+                data: vec![0xdd; 50],
+                mac: "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe",
+            },
+            TestCase {
+                key: vec![0xaa; 131],
+                data: b"Test Using Larger Than Block-Size Key - Hash Key First".to_vec(),
+                mac: "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+            },
+        ];
+
+        for case in cases {
+            assert_eq!(hmac::<Sha256>(&case.key, &case.data), parse(case.mac));
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let tag = hmac::<Sha256>(b"key", b"message");
+
+        let mut mac = Hmac::<Sha256>::new(b"key");
+        mac.update(b"mess");
+        mac.update(b"age");
+        assert_eq!(mac.clone().verify(&tag), Ok(()));
+        assert_eq!(
+            mac.verify(tag.get(..31).expect("tag is 32 bytes long")),
+            Err(AuthenticationError)
+        );
+
+        let mut mac = Hmac::<Sha256>::new(b"key");
+        mac.update(b"massage");
+        assert_eq!(mac.verify(&tag), Err(AuthenticationError));
+    }
+}
@@ -0,0 +1,118 @@
+//! Password-based key derivation
+//!
+//! [`pbkdf2()`] is the standard construction from RFC 8018. [`evp_bytes_to_key()`] is
+//! the legacy derivation of OpenSSL, which hashes the password only once and should
+//! only be used to read existing files.
+//!
+//! <https://www.rfc-editor.org/rfc/rfc8018#section-5.2>
+//! <https://docs.openssl.org/master/man3/EVP_BytesToKey/>
+
+use crate::{hash::Hash, hmac::Hmac};
+
+/// PBKDF2 with HMAC as pseudorandom function. Returns `len` bytes.
+pub fn pbkdf2<H: Hash>(password: &[u8], salt: &[u8], iterations: u32, len: usize) -> Vec<u8> {
+    assert!(iterations != 0, "iteration count cannot be 0");
+
+    let prf = Hmac::<H>::new(password);
+    let mut output = Vec::with_capacity(len);
+
+    for i in 1_u32.. {
+        if output.len() >= len {
+            break;
+        }
+
+        let mut mac = prf.clone();
+        mac.update(salt);
+        mac.update(&i.to_be_bytes());
+        let mut u = mac.finalize();
+        let mut block = u.clone();
+
+        for _ in 1..iterations {
+            let mut mac = prf.clone();
+            mac.update(&u);
+            u = mac.finalize();
+            block.iter_mut().zip(&u).for_each(|(b, u)| *b ^= u);
+        }
+
+        output.extend(block);
+    }
+
+    output.truncate(len);
+    output
+}
+
+/// `EVP_BytesToKey` of OpenSSL. Each block is the hash of the previous block, the
+/// password and the salt, hashed again `iterations - 1` times. Returns `len` bytes.
+pub fn evp_bytes_to_key<H: Hash>(
+    password: &[u8],
+    salt: Option<&[u8; 8]>,
+    iterations: u32,
+    len: usize,
+) -> Vec<u8> {
+    assert!(iterations != 0, "iteration count cannot be 0");
+
+    let mut output = Vec::with_capacity(len);
+    let mut block = Vec::new();
+
+    while output.len() < len {
+        let mut hash = H::new();
+        hash.update(&block);
+        hash.update(password);
+        if let Some(salt) = salt {
+            hash.update(salt);
+        }
+        block = hash.finalize();
+
+        for _ in 1..iterations {
+            block = H::digest(&block);
+        }
+
+        output.extend_from_slice(&block);
+    }
+
+    output.truncate(len);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hash::{md5::Md5, sha256::Sha256},
+        hex::parse,
+    };
+
+    #[test]
+    /// taken from the PBKDF2-HMAC-SHA256 test vectors in RFC 7914
+    fn test_pbkdf2_from_spec() {
+        assert_eq!(
+            pbkdf2::<Sha256>(b"passwd", b"salt", 1, 64),
+            parse("55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783")
+        );
+        assert_eq!(
+            pbkdf2::<Sha256>(b"Password", b"NaCl", 80_000, 64),
+            parse("4ddcd8f60b98be21830cee5ef22701f9641a4418d04c0414aeff08876b34ab56a1d425a1225833549adb841b51c9b3176a272bdebba1d078478f62b397f33c8d")
+        );
+    }
+
+    #[test]
+    /// cross-checked with `openssl enc -P`
+    fn test_evp_bytes_to_key() {
+        let salt = parse("0102030405060708")
+            .try_into()
+            .expect("test salt has 8 bytes");
+
+        assert_eq!(
+            evp_bytes_to_key::<Md5>(b"password", Some(&salt), 1, 32),
+            parse("e7b0971e52ca5cc8d0539fb3412f6316f7ba2e6ee293d9f3457b99436b51ce02")
+        );
+        assert_eq!(
+            evp_bytes_to_key::<Sha256>(b"password", Some(&salt), 1, 32),
+            parse("2435177f1410536baad2acc155c0f94783d58384573cb0f72157443606285d3f")
+        );
+        assert_eq!(
+            evp_bytes_to_key::<Md5>(b"password", None, 1, 32),
+            parse("5f4dcc3b5aa765d61d8327deb882cf992b95990a9151374abd8ff8c5a7a0fe08")
+        );
+    }
+}
@@ -1,11 +1,21 @@
 mod error;
+mod parallel;
 
 pub use error::Error;
 
 pub mod aes;
 pub mod ascii;
+pub mod attack;
 pub mod base64;
+pub mod cookie;
+pub mod drbg;
+pub mod hash;
 pub mod hex;
+pub mod hmac;
+pub mod kdf;
+pub mod mt19937;
+pub mod pkcs7;
+pub mod random;
 pub mod text;
 pub mod xor;
 
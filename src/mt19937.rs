@@ -0,0 +1,376 @@
+//! The Mersenne Twister pseudorandom number generators MT19937 and MT19937-64
+//!
+//! The generators are fast and statistically good, but not cryptographically secure:
+//! every output is a tempered word of the internal state, and the tempering can be
+//! inverted. They are still a common choice for tokens and keys, so this module
+//! reproduces the reference implementations exactly.
+//!
+//! <http://www.math.sci.hiroshima-u.ac.jp/m-mat/MT/emt.html>
+
+/// The 32 bit Mersenne Twister
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mt19937 {
+    state: [u32; 624],
+    index: usize,
+}
+
+impl Mt19937 {
+    /// The number of words in the state
+    pub const STATE_WORDS: usize = 624;
+
+    const SHIFT: usize = 397;
+    const MATRIX: u32 = 0x9908_b0df;
+    const UPPER_MASK: u32 = 0x8000_0000;
+    const LOWER_MASK: u32 = 0x7fff_ffff;
+
+    /// The seed of the reference implementation if none is given
+    pub const DEFAULT_SEED: u32 = 5489;
+
+    /// Seeds the generator like `init_genrand()` of the reference implementation
+    pub fn new(seed: u32) -> Self {
+        let mut state = [0; Self::STATE_WORDS];
+        let mut previous = seed;
+        for (word, i) in state.iter_mut().zip(0_u32..) {
+            *word = if i == 0 {
+                seed
+            } else {
+                1_812_433_253_u32
+                    .wrapping_mul(previous ^ (previous >> 30_u32))
+                    .wrapping_add(i)
+            };
+            previous = *word;
+        }
+
+        Self {
+            state,
+            index: Self::STATE_WORDS,
+        }
+    }
+
+    /// Seeds the generator like `init_by_array()` of the reference implementation
+    #[expect(
+        clippy::indexing_slicing,
+        clippy::arithmetic_side_effects,
+        reason = "the indices wrap around before reaching the end of the state"
+    )]
+    pub fn from_key(key: &[u32]) -> Self {
+        assert!(!key.is_empty(), "key cannot be empty");
+
+        let mut generator = Self::new(19_650_218);
+        let state = &mut generator.state;
+        let mut i = 1;
+
+        for (k, j) in key
+            .iter()
+            .zip(0_u32..)
+            .cycle()
+            .take(key.len().max(Self::STATE_WORDS))
+        {
+            let previous = state[i - 1];
+            state[i] = (state[i] ^ (previous ^ (previous >> 30_u32)).wrapping_mul(1_664_525))
+                .wrapping_add(*k)
+                .wrapping_add(j);
+            i += 1;
+            if i >= Self::STATE_WORDS {
+                state[0] = state[Self::STATE_WORDS - 1];
+                i = 1;
+            }
+        }
+
+        for _ in 1..Self::STATE_WORDS {
+            let previous = state[i - 1];
+            state[i] = (state[i] ^ (previous ^ (previous >> 30_u32)).wrapping_mul(1_566_083_941))
+                .wrapping_sub(u32::try_from(i).expect("i is smaller than the state"));
+            i += 1;
+            if i >= Self::STATE_WORDS {
+                state[0] = state[Self::STATE_WORDS - 1];
+                i = 1;
+            }
+        }
+
+        state[0] = Self::UPPER_MASK;
+        generator
+    }
+
+    /// Continues from a state that was just twisted, so the next output is the
+    /// tempered first word of the next twist
+    pub const fn from_state(state: [u32; 624]) -> Self {
+        Self {
+            state,
+            index: Self::STATE_WORDS,
+        }
+    }
+
+    #[expect(
+        clippy::indexing_slicing,
+        clippy::arithmetic_side_effects,
+        reason = "all indices are reduced modulo the state size"
+    )]
+    fn twist(&mut self) {
+        let n = Self::STATE_WORDS;
+        for i in 0..n {
+            let y =
+                (self.state[i] & Self::UPPER_MASK) | (self.state[(i + 1) % n] & Self::LOWER_MASK);
+            let mut next = self.state[(i + Self::SHIFT) % n] ^ (y >> 1_u32);
+            if y & 1 == 1 {
+                next ^= Self::MATRIX;
+            }
+            self.state[i] = next;
+        }
+        self.index = 0;
+    }
+
+    pub const fn temper(mut y: u32) -> u32 {
+        y ^= y >> 11_u32;
+        y ^= (y << 7_u32) & 0x9d2c_5680;
+        y ^= (y << 15_u32) & 0xefc6_0000;
+        y ^ (y >> 18_u32)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        if self.index >= Self::STATE_WORDS {
+            self.twist();
+        }
+
+        let word = *self
+            .state
+            .get(self.index)
+            .expect("index is reset after a twist");
+        self.index = self.index.saturating_add(1);
+        Self::temper(word)
+    }
+}
+
+impl Default for Mt19937 {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_SEED)
+    }
+}
+
+/// The 64 bit Mersenne Twister
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mt19937_64 {
+    state: [u64; 312],
+    index: usize,
+}
+
+impl Mt19937_64 {
+    /// The number of words in the state
+    pub const STATE_WORDS: usize = 312;
+
+    const SHIFT: usize = 156;
+    const MATRIX: u64 = 0xb502_6f5a_a966_19e9;
+    const UPPER_MASK: u64 = 0xffff_ffff_8000_0000;
+    const LOWER_MASK: u64 = 0x7fff_ffff;
+
+    /// The seed of the reference implementation if none is given
+    pub const DEFAULT_SEED: u64 = 5489;
+
+    /// Seeds the generator like `init_genrand64()` of the reference implementation
+    pub fn new(seed: u64) -> Self {
+        let mut state = [0; Self::STATE_WORDS];
+        let mut previous = seed;
+        for (word, i) in state.iter_mut().zip(0_u64..) {
+            *word = if i == 0 {
+                seed
+            } else {
+                6_364_136_223_846_793_005_u64
+                    .wrapping_mul(previous ^ (previous >> 62_u32))
+                    .wrapping_add(i)
+            };
+            previous = *word;
+        }
+
+        Self {
+            state,
+            index: Self::STATE_WORDS,
+        }
+    }
+
+    /// Seeds the generator like `init_by_array64()` of the reference implementation
+    #[expect(
+        clippy::indexing_slicing,
+        clippy::arithmetic_side_effects,
+        reason = "the indices wrap around before reaching the end of the state"
+    )]
+    pub fn from_key(key: &[u64]) -> Self {
+        assert!(!key.is_empty(), "key cannot be empty");
+
+        let mut generator = Self::new(19_650_218);
+        let state = &mut generator.state;
+        let mut i = 1;
+
+        for (k, j) in key
+            .iter()
+            .zip(0_u64..)
+            .cycle()
+            .take(key.len().max(Self::STATE_WORDS))
+        {
+            let previous = state[i - 1];
+            state[i] = (state[i]
+                ^ (previous ^ (previous >> 62_u32)).wrapping_mul(3_935_559_000_370_003_845))
+            .wrapping_add(*k)
+            .wrapping_add(j);
+            i += 1;
+            if i >= Self::STATE_WORDS {
+                state[0] = state[Self::STATE_WORDS - 1];
+                i = 1;
+            }
+        }
+
+        for _ in 1..Self::STATE_WORDS {
+            let previous = state[i - 1];
+            state[i] = (state[i]
+                ^ (previous ^ (previous >> 62_u32)).wrapping_mul(2_862_933_555_777_941_757))
+            .wrapping_sub(u64::try_from(i).expect("i is smaller than the state"));
+            i += 1;
+            if i >= Self::STATE_WORDS {
+                state[0] = state[Self::STATE_WORDS - 1];
+                i = 1;
+            }
+        }
+
+        state[0] = 1 << 63_u32;
+        generator
+    }
+
+    /// Continues from a state that was just twisted, as [`Mt19937::from_state()`]
+    pub const fn from_state(state: [u64; 312]) -> Self {
+        Self {
+            state,
+            index: Self::STATE_WORDS,
+        }
+    }
+
+    #[expect(
+        clippy::indexing_slicing,
+        clippy::arithmetic_side_effects,
+        reason = "all indices are reduced modulo the state size"
+    )]
+    fn twist(&mut self) {
+        let n = Self::STATE_WORDS;
+        for i in 0..n {
+            let y =
+                (self.state[i] & Self::UPPER_MASK) | (self.state[(i + 1) % n] & Self::LOWER_MASK);
+            let mut next = self.state[(i + Self::SHIFT) % n] ^ (y >> 1_u32);
+            if y & 1 == 1 {
+                next ^= Self::MATRIX;
+            }
+            self.state[i] = next;
+        }
+        self.index = 0;
+    }
+
+    pub const fn temper(mut y: u64) -> u64 {
+        y ^= (y >> 29_u32) & 0x5555_5555_5555_5555;
+        y ^= (y << 17_u32) & 0x71d6_7fff_eda6_0000;
+        y ^= (y << 37_u32) & 0xfff7_eee0_0000_0000;
+        y ^ (y >> 43_u32)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        if self.index >= Self::STATE_WORDS {
+            self.twist();
+        }
+
+        let word = *self
+            .state
+            .get(self.index)
+            .expect("index is reset after a twist");
+        self.index = self.index.saturating_add(1);
+        Self::temper(word)
+    }
+}
+
+impl Default for Mt19937_64 {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_SEED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// taken from `mt19937ar.out` of the reference implementation
+    fn test_mt19937_reference() {
+        let mut mt = Mt19937::from_key(&[0x123, 0x234, 0x345, 0x456]);
+        let outputs: Vec<u32> = (0..5_u8).map(|_| mt.next_u32()).collect();
+        assert_eq!(
+            outputs,
+            [
+                1_067_595_299,
+                955_945_823,
+                477_289_528,
+                4_107_218_783,
+                4_228_976_476
+            ]
+        );
+    }
+
+    #[test]
+    /// taken from `mt19937-64.out` of the reference implementation
+    fn test_mt19937_64_reference() {
+        let mut mt = Mt19937_64::from_key(&[0x12345, 0x23456, 0x34567, 0x45678]);
+        let outputs: Vec<u64> = (0..5_u8).map(|_| mt.next_u64()).collect();
+        assert_eq!(
+            outputs,
+            [
+                7_266_447_313_870_364_031,
+                4_946_485_549_665_804_864,
+                16_945_909_448_695_747_420,
+                16_394_063_075_524_226_720,
+                4_873_882_236_456_199_058
+            ]
+        );
+    }
+
+    #[test]
+    /// the 10000th output with the default seed, as required by the C++ standard
+    fn test_default_seed() {
+        let mut mt = Mt19937::default();
+        for _ in 1..10_000_u16 {
+            mt.next_u32();
+        }
+        assert_eq!(mt.next_u32(), 4_123_659_995);
+
+        let mut mt = Mt19937_64::default();
+        for _ in 1..10_000_u16 {
+            mt.next_u64();
+        }
+        assert_eq!(mt.next_u64(), 9_981_545_732_273_789_042);
+    }
+
+    #[test]
+    /// cross-checked with C++ `std::mt19937` and `std::mt19937_64`
+    fn test_seed() {
+        let mut mt = Mt19937::new(1);
+        assert_eq!(
+            [mt.next_u32(), mt.next_u32()],
+            [1_791_095_845, 4_282_876_139]
+        );
+
+        let mut mt = Mt19937_64::new(1);
+        assert_eq!(
+            [mt.next_u64(), mt.next_u64()],
+            [2_469_588_189_546_311_528, 2_516_265_689_700_432_462]
+        );
+    }
+
+    #[test]
+    /// cross-checked with python's `random`, which seeds with `init_by_array()`
+    fn test_from_key() {
+        let mut mt = Mt19937::from_key(&[0]);
+        assert_eq!(
+            [mt.next_u32(), mt.next_u32(), mt.next_u32()],
+            [3_626_764_237, 1_654_615_998, 3_255_389_356]
+        );
+
+        let mut mt = Mt19937::from_key(&[5, 1]);
+        assert_eq!(
+            [mt.next_u32(), mt.next_u32(), mt.next_u32()],
+            [675_479_763, 2_085_189_291, 1_213_270_837]
+        );
+    }
+}
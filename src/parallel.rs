@@ -0,0 +1,73 @@
+//! A work queue shared by threads
+//!
+//! The items of an iterator are handed out one at a time to a fixed number of
+//! threads, so threads that finish early take over more of the work. The calling
+//! thread takes part as well.
+
+use std::{
+    num::NonZeroUsize,
+    sync::{Mutex, PoisonError},
+    thread,
+};
+
+/// The number of threads that can run in parallel, or 1 if that is unknown
+pub(crate) fn available_threads() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+/// Runs `process` for every item of `items` on `threads` threads, including the
+/// calling thread. Returns once all items are processed.
+pub(crate) fn for_each<I, F>(items: I, threads: usize, process: F)
+where
+    I: Iterator + Send,
+    I::Item: Send,
+    F: Fn(I::Item) + Sync,
+{
+    assert!(threads != 0, "thread count cannot be 0");
+
+    let queue = Mutex::new(items);
+
+    let work = || loop {
+        // the lock is only held while taking the next item
+        let next = queue.lock().unwrap_or_else(PoisonError::into_inner).next();
+
+        match next {
+            Some(item) => process(item),
+            None => break,
+        }
+    };
+
+    thread::scope(|scope| {
+        for _ in 1..threads {
+            scope.spawn(work);
+        }
+        work();
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_each() {
+        for threads in [1, 2, 7] {
+            let seen = Mutex::new(Vec::new());
+            for_each(0..1000_u16, threads, |item| {
+                seen.lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push(item);
+            });
+
+            let mut seen = seen.into_inner().unwrap_or_else(PoisonError::into_inner);
+            seen.sort_unstable();
+            assert!(seen.into_iter().eq(0..1000));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "thread count cannot be 0")]
+    fn test_no_threads() {
+        for_each(0..10_u8, 0, |_| {});
+    }
+}
@@ -0,0 +1,117 @@
+//! PKCS#7 padding
+//!
+//! The input is padded with `n` bytes of value `n`, so that its length becomes a
+//! multiple of the block size. Block-aligned input gets a full block of padding, so
+//! the padding can always be removed unambiguously.
+//!
+//! <https://www.rfc-editor.org/rfc/rfc5652#section-6.3>
+//! <https://www.cryptopals.com/sets/2/challenges/9>
+
+use std::{error, fmt};
+
+/// Returned by [`unpad()`] when the input does not end with valid padding
+#[derive(Debug, PartialEq, Eq)]
+pub struct InvalidPadding;
+
+impl error::Error for InvalidPadding {}
+
+impl fmt::Display for InvalidPadding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid padding")
+    }
+}
+
+/// Returns the padding for input of length `len`
+pub fn padding(len: usize, block_size: u8) -> Vec<u8> {
+    let remainder = len
+        .checked_rem(usize::from(block_size))
+        .expect("block size cannot be 0");
+
+    let padding_len = u8::try_from(
+        usize::from(block_size)
+            .checked_sub(remainder)
+            .expect("remainder is smaller than block size"),
+    )
+    .expect("padding is at most one block");
+
+    vec![padding_len; usize::from(padding_len)]
+}
+
+pub fn pad(input: &[u8], block_size: u8) -> Vec<u8> {
+    let mut output = input.to_vec();
+    output.extend(padding(input.len(), block_size));
+    output
+}
+
+/// Removes the padding from `input`, which has to be a multiple of `block_size`.
+pub fn unpad(input: &[u8], block_size: u8) -> Result<&[u8], InvalidPadding> {
+    if input.is_empty() || !input.len().is_multiple_of(usize::from(block_size)) {
+        return Err(InvalidPadding);
+    }
+
+    let padding_len = *input.last().ok_or(InvalidPadding)?;
+
+    if padding_len == 0 || padding_len > block_size {
+        return Err(InvalidPadding);
+    }
+
+    let (data, padding) = input
+        .split_at_checked(input.len().saturating_sub(usize::from(padding_len)))
+        .ok_or(InvalidPadding)?;
+
+    if padding.iter().any(|b| *b != padding_len) {
+        return Err(InvalidPadding);
+    }
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pad_cryptopals() {
+        assert_eq!(
+            pad(b"YELLOW SUBMARINE", 20),
+            b"YELLOW SUBMARINE\x04\x04\x04\x04"
+        );
+    }
+
+    #[test]
+    fn test_pad_aligned() {
+        assert_eq!(pad(b"", 4), b"\x04\x04\x04\x04");
+        assert_eq!(pad(b"abcd", 4), b"abcd\x04\x04\x04\x04");
+        assert_eq!(pad(b"abc", 4), b"abc\x01");
+    }
+
+    #[test]
+    fn test_unpad() {
+        for len in 0..40 {
+            let input = vec![0x42; len];
+            assert_eq!(unpad(&pad(&input, 16), 16), Ok(input.as_slice()));
+        }
+    }
+
+    #[test]
+    /// from cryptopals challenge 15
+    fn test_unpad_invalid() {
+        assert_eq!(
+            unpad(b"ICE ICE BABY\x04\x04\x04\x04", 16),
+            Ok(&b"ICE ICE BABY"[..])
+        );
+        assert_eq!(
+            unpad(b"ICE ICE BABY\x05\x05\x05\x05", 16),
+            Err(InvalidPadding)
+        );
+        assert_eq!(
+            unpad(b"ICE ICE BABY\x01\x02\x03\x04", 16),
+            Err(InvalidPadding)
+        );
+
+        assert_eq!(unpad(b"ICE ICE BABY\x00", 13), Err(InvalidPadding));
+        assert_eq!(unpad(b"ICE ICE BABY\x11", 13), Err(InvalidPadding));
+        assert_eq!(unpad(b"ICE ICE BABY\x01", 16), Err(InvalidPadding));
+        assert_eq!(unpad(b"", 16), Err(InvalidPadding));
+    }
+}
@@ -0,0 +1,141 @@
+//! A source of random bytes
+//!
+//! [`Random`] draws from a [`CtrDrbg`] that is seeded from the operating system, or
+//! from a fixed seed to make simulations reproducible.
+
+use std::{fs, io::Read, ops::RangeInclusive};
+
+use crate::{
+    aes::Key128,
+    drbg::{
+        ctr::{CtrDrbg, SEED_LENGTH},
+        Drbg, MAX_REQUEST_SIZE,
+    },
+    Error,
+};
+
+pub struct Random {
+    drbg: CtrDrbg,
+}
+
+impl Random {
+    /// Seeds the generator from `/dev/urandom`
+    pub fn new() -> Result<Self, Error> {
+        let mut seed = [0; SEED_LENGTH];
+        fs::File::open("/dev/urandom")?.read_exact(&mut seed)?;
+        Ok(Self::from_seed(seed))
+    }
+
+    /// Seeds the generator deterministically, every seed always produces the same
+    /// output
+    pub fn from_seed(seed: [u8; SEED_LENGTH]) -> Self {
+        Self {
+            drbg: CtrDrbg::without_derivation_function(&seed, &[])
+                .expect("seed has the required length"),
+        }
+    }
+
+    pub fn fill(&mut self, output: &mut [u8]) {
+        for chunk in output.chunks_mut(MAX_REQUEST_SIZE) {
+            self.drbg
+                .generate(chunk, &[])
+                .expect("requests are limited, and the reseed interval is never exhausted");
+        }
+    }
+
+    pub fn bytes<const N: usize>(&mut self) -> [u8; N] {
+        let mut bytes = [0; N];
+        self.fill(&mut bytes);
+        bytes
+    }
+
+    pub fn key(&mut self) -> Key128 {
+        Key128::from_bytes(self.bytes())
+    }
+
+    pub fn bool(&mut self) -> bool {
+        let [byte] = self.bytes();
+        byte & 1 == 1
+    }
+
+    /// Returns a uniformly distributed number in `0..bound`
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound != 0, "bound cannot be 0");
+
+        // values below the threshold would make smaller results more likely, as
+        // `u64::MAX + 1` is not a multiple of `bound`
+        let threshold = bound
+            .wrapping_neg()
+            .checked_rem(bound)
+            .expect("bound is not 0");
+
+        loop {
+            let value = u64::from_be_bytes(self.bytes());
+            if value >= threshold {
+                return value.checked_rem(bound).expect("bound is not 0");
+            }
+        }
+    }
+
+    /// Returns a uniformly distributed number in `range`
+    pub fn range(&mut self, range: RangeInclusive<usize>) -> usize {
+        let (start, end) = range.into_inner();
+        let span = end
+            .checked_sub(start)
+            .expect("range cannot be empty")
+            .checked_add(1);
+
+        let offset = match span {
+            Some(span) => self.below(u64::try_from(span).expect("usize fits into u64")),
+            // the range covers all values
+            None => u64::from_be_bytes(self.bytes()),
+        };
+
+        start
+            .checked_add(
+                usize::try_from(offset).expect("offset is at most the span of a usize range"),
+            )
+            .expect("offset is within the range")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reproducible() {
+        let mut a = Random::from_seed([7; SEED_LENGTH]);
+        let mut b = Random::from_seed([7; SEED_LENGTH]);
+        let mut c = Random::from_seed([8; SEED_LENGTH]);
+
+        assert_eq!(a.bytes::<64>(), b.bytes::<64>());
+        assert_ne!(a.bytes::<64>(), c.bytes::<64>());
+    }
+
+    #[test]
+    fn test_fill_large() {
+        let mut random = Random::new().expect("/dev/urandom is readable");
+        let mut output = vec![0; MAX_REQUEST_SIZE.saturating_mul(2).saturating_add(1)];
+        random.fill(&mut output);
+        assert!(output.iter().any(|b| *b != 0));
+    }
+
+    #[test]
+    fn test_range() {
+        let mut random = Random::from_seed([0; SEED_LENGTH]);
+        let mut seen = [false; 6];
+
+        for _ in 0..1000_u16 {
+            let value = random.range(5..=10);
+            assert!((5..=10).contains(&value));
+            *seen
+                .get_mut(value.saturating_sub(5))
+                .expect("value is within the range") = true;
+        }
+        assert!(seen.iter().all(|seen| *seen));
+
+        assert_eq!(random.range(3..=3), 3);
+        random.range(0..=usize::MAX);
+    }
+}
@@ -28,6 +28,49 @@ pub fn score_english_plaintext_2(text: &str) -> usize {
         .saturating_sub(control_count.checked_add(100).expect("usize overflow"))
 }
 
+/// Scores how likely `second` follows `first` in english text
+pub fn score_english_bigram(first: char, second: char) -> usize {
+    // https://en.wikipedia.org/wiki/Bigram#Bigram_frequency_in_the_English_language
+    const FREQUENT: [[char; 2]; 20] = [
+        ['t', 'h'],
+        ['h', 'e'],
+        ['i', 'n'],
+        ['e', 'r'],
+        ['a', 'n'],
+        ['r', 'e'],
+        ['n', 'd'],
+        ['o', 'n'],
+        ['e', 'n'],
+        ['a', 't'],
+        ['o', 'u'],
+        ['e', 'd'],
+        ['h', 'a'],
+        ['t', 'o'],
+        ['o', 'r'],
+        ['i', 't'],
+        ['i', 's'],
+        ['h', 'i'],
+        ['e', 's'],
+        ['n', 'g'],
+    ];
+
+    let pair = [first.to_ascii_lowercase(), second.to_ascii_lowercase()];
+
+    match (first, second) {
+        _ if FREQUENT.contains(&pair) && !second.is_ascii_uppercase() => 3,
+        // words are followed by a space or punctuation, and punctuation by a space
+        (',' | '.' | ';' | ':' | '!' | '?', ' ') => 2,
+        (first, ' ' | ',' | '.' | ';' | ':' | '!' | '?' | '\'' | '-')
+            if first.is_ascii_alphabetic() =>
+        {
+            1
+        }
+        (first, second) if first.is_ascii_alphabetic() && second.is_ascii_lowercase() => 1,
+        (' ', second) if second.is_ascii_alphabetic() => 1,
+        _ => 0,
+    }
+}
+
 pub fn hamming_bits(t1: &[u8], t2: &[u8]) -> usize {
     assert_eq!(t1.len(), t2.len(), "t1 and t2 have to be the same size");
 
@@ -65,4 +108,13 @@ mod tests {
     fn hamming_cryptopals_example() {
         assert_eq!(hamming_bits_str("this is a test", "wokka wokka!!!"), 37);
     }
+
+    #[test]
+    fn bigram_scores() {
+        assert!(score_english_bigram('t', 'h') > score_english_bigram('t', 'x'));
+        assert!(score_english_bigram('T', 'h') > score_english_bigram('t', 'H'));
+        assert!(score_english_bigram(',', ' ') > score_english_bigram(',', 'a'));
+        assert!(score_english_bigram('d', ' ') > score_english_bigram(' ', ' '));
+        assert_eq!(score_english_bigram('\0', 'e'), 0);
+    }
 }